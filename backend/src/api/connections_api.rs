@@ -1,17 +1,52 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use sea_orm::{prelude::*, Set};
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::Utc;
-use crate::entities::{now_playing_connections, payload_mappings};
+use std::collections::hash_map::{Entry, HashMap};
+use chrono::{DateTime, FixedOffset, Utc};
+use crate::entities::{now_playing_connections, payload_mapping_versions, payload_mappings, raw_now_playing_events};
 use crate::api::AppState;
+use crate::api::credentials_api::MASKED_SECRET;
 use crate::http_headers::normalize_headers_for_storage;
-use crate::poller::utils::fetch_and_parse;
+use crate::mapping_versions::{
+    insert_mapping_with_version,
+    list_versions,
+    update_mapping_with_version,
+    MappingInput,
+};
+use crate::mapping_spec::{mapping_json_schema, validate_mapping, MappingErrors};
+use crate::poller::chain::{clear_chain_cache, validate_request_chain};
+use crate::poller::charset::is_known_charset;
+use crate::poller::cookies::{CookieJar, StoredCookie};
+use crate::poller::fingerprint::{resolve_payload_mapping, MappingResolution};
+use crate::poller::proxy::{is_valid_proxy_url, mask_proxy_userinfo, restore_proxy_userinfo, ProxyError};
+use crate::poller::reextract::{compare_extraction, reextract_event, DerivedColumns, ExtractionOutcome};
+use crate::poller::suggest::{suggest_mapping, PathCandidate};
+use crate::poller::transform::{apply_field_transforms, FieldTrace};
+use crate::poller::utils::{
+    ExtendedFields,
+    ParsedFields,
+    apply_station_timezone,
+    extract_fields,
+    extract_fields_traced,
+    extract_list_items,
+    fetch_and_parse,
+    is_hls_connection_type,
+    is_list_mode_all,
+    is_listener_connection_type,
+    is_push_connection_type,
+    is_xml_connection_type,
+    load_connection_mapping,
+    mapping_script,
+    script_fields,
+};
+use crate::poller::xpath::xpath_errors;
 
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -20,14 +55,22 @@ pub fn router() -> Router<AppState> {
         .route("/{id}/enable", post(enable_connection))
         .route("/{id}/disable", post(disable_connection))
         .route("/{id}/test", post(test_connection))
+        .route("/{id}/cookies", get(get_cookies).delete(clear_cookies))
         .route("/mappings", get(list_mappings).post(create_mapping))
+        .route("/mappings/schema", get(get_mapping_schema))
+        .route("/mappings/suggest", post(suggest_mapping_draft))
+        .route("/mappings/script/test", post(test_script_mapping))
         .route("/mappings/{id}", get(get_mapping).put(update_mapping).delete(delete_mapping))
+        .route("/mappings/{id}/versions", get(list_mapping_versions))
+        .route("/mappings/{id}/evaluate", post(evaluate_mapping))
 }
 
 #[derive(Deserialize)]
 pub struct CreateConnection {
     pub station_id: Uuid,
     pub payload_mapping_id: Option<Uuid>,
+    #[serde(default)]
+    pub auto_mapping: bool,
     pub name: String,
     pub connection_type: String,
     pub url: String,
@@ -36,6 +79,16 @@ pub struct CreateConnection {
     pub enabled: bool,
     #[serde(default)]
     pub use_duration_polling: bool,
+    pub ingest_token: Option<String>,
+    pub ingest_hmac_secret: Option<String>,
+    pub credential_id: Option<Uuid>,
+    pub request_chain_json: Option<serde_json::Value>,
+    pub response_charset: Option<String>,
+    #[serde(default)]
+    pub cookie_jar_enabled: bool,
+    pub cookie_warmup_url: Option<String>,
+    pub proxy_url: Option<String>,
+    pub proxy_pool_id: Option<Uuid>,
 }
 
 #[derive(Deserialize)]
@@ -56,23 +109,20 @@ async fn list_mappings(State(state): State<AppState>) -> Result<Json<Vec<payload
 async fn create_mapping(
     State(state): State<AppState>,
     Json(payload): Json<CreateMapping>,
-) -> Result<Json<payload_mappings::Model>, StatusCode> {
-    let now = Utc::now().fixed_offset();
-    let mapping = payload_mappings::ActiveModel {
-        id: Set(Uuid::new_v4()),
-        name: Set(payload.name),
-        description: Set(payload.description),
-        mapping_json: Set(payload.mapping_json),
-        created_at: Set(now),
-        updated_at: Set(now),
+) -> Result<Json<payload_mappings::Model>, Response> {
+    validate_mapping_payload(&payload).map_err(IntoResponse::into_response)?;
+    let input = MappingInput {
+        name: payload.name,
+        description: payload.description,
+        mapping_json: payload.mapping_json,
     };
 
-    mapping.insert(&state.db)
+    insert_mapping_with_version(&state.db, Uuid::new_v4(), input, Utc::now().fixed_offset())
         .await
         .map(Json)
         .map_err(|e| {
             tracing::error!("Failed to create mapping: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         })
 }
 
@@ -92,20 +142,338 @@ async fn update_mapping(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Json(payload): Json<CreateMapping>,
-) -> Result<Json<payload_mappings::Model>, StatusCode> {
+) -> Result<Json<payload_mappings::Model>, Response> {
+    validate_mapping_payload(&payload).map_err(IntoResponse::into_response)?;
     let mapping = payload_mappings::Entity::find_by_id(id)
         .one(&state.db)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    let input = MappingInput {
+        name: payload.name,
+        description: payload.description,
+        mapping_json: payload.mapping_json,
+    };
+
+    update_mapping_with_version(&state.db, mapping, input, Utc::now().fixed_offset())
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+// Rejections carry every validation message so the editor can show them.
+fn validate_mapping_payload(payload: &CreateMapping) -> Result<(), (StatusCode, Json<MappingErrors>)> {
+    validate_mapping(&payload.mapping_json)
+        .map(|_| ())
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(e)))
+}
+
+async fn get_mapping_schema() -> Json<serde_json::Value> {
+    Json(mapping_json_schema())
+}
+
+#[derive(Deserialize)]
+pub struct SuggestMappingRequest {
+    pub connection_id: Option<Uuid>,
+    // A raw JSON value, or a JSON/XML document as a string.
+    pub sample: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+pub struct MappingDraft {
+    pub name: String,
+    pub description: Option<String>,
+    pub mapping_json: serde_json::Value,
+}
+
+#[derive(Serialize)]
+pub struct MappingSuggestionResponse {
+    pub draft: MappingDraft,
+    pub format: String,
+    pub confidence: f64,
+    pub candidates: std::collections::BTreeMap<String, Vec<PathCandidate>>,
+}
+
+async fn suggest_mapping_draft(
+    State(state): State<AppState>,
+    Json(payload): Json<SuggestMappingRequest>,
+) -> Result<Json<MappingSuggestionResponse>, StatusCode> {
+    let (name, sample) = match (payload.sample, payload.connection_id) {
+        (Some(sample), _) => ("Suggested mapping".to_string(), sample),
+        (None, Some(id)) => {
+            let conn = now_playing_connections::Entity::find_by_id(id)
+                .one(&state.db)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .ok_or(StatusCode::NOT_FOUND)?;
+            // Push-style connections have nothing to fetch; post a sample instead.
+            if conn.connection_type.eq_ignore_ascii_case("ws_json")
+                || is_push_connection_type(&conn.connection_type)
+                || is_listener_connection_type(&conn.connection_type)
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            let result = fetch_and_parse(&state.db, &conn, None).await.map_err(|e| {
+                tracing::error!("Suggestion fetch failed: {:?}", e);
+                StatusCode::BAD_GATEWAY
+            })?;
+            (format!("{} mapping", conn.name), result.raw_payload)
+        }
+        (None, None) => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let suggestion = suggest_mapping(&sample).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    Ok(Json(MappingSuggestionResponse {
+        draft: MappingDraft {
+            name,
+            description: Some(format!("Suggested from a {} sample", suggestion.format.to_uppercase())),
+            mapping_json: suggestion.mapping_json,
+        },
+        format: suggestion.format.to_string(),
+        confidence: suggestion.confidence,
+        candidates: suggestion.candidates,
+    }))
+}
+
+// Which stored events a mapping is tried against: specific events, or the
+// latest `limit` events of a connection.
+#[derive(Deserialize)]
+pub struct EventSample {
+    #[serde(default)]
+    pub event_ids: Vec<Uuid>,
+    pub connection_id: Option<Uuid>,
+    pub limit: Option<u64>,
+}
+
+const SAMPLE_DEFAULT_LIMIT: u64 = 10;
+const SAMPLE_MAX_LIMIT: u64 = 100;
+
+// Without `event_ids` or `connection_id`, the latest events of
+// `default_connections` are used; with none of them, or with more than
+// `SAMPLE_MAX_LIMIT` ids, the request is rejected.
+async fn load_sample_events(
+    db: &DatabaseConnection,
+    sample: &EventSample,
+    default_connections: Vec<Uuid>,
+) -> Result<Vec<raw_now_playing_events::Model>, Response> {
+    if sample.event_ids.len() as u64 > SAMPLE_MAX_LIMIT {
+        let message = format!("at most {} event_ids can be sampled", SAMPLE_MAX_LIMIT);
+        return Err((StatusCode::BAD_REQUEST, message).into_response());
+    }
+    let query = if !sample.event_ids.is_empty() {
+        raw_now_playing_events::Entity::find()
+            .filter(raw_now_playing_events::Column::Id.is_in(sample.event_ids.clone()))
+    } else {
+        let connection_ids = match sample.connection_id {
+            Some(connection_id) => vec![connection_id],
+            None if !default_connections.is_empty() => default_connections,
+            None => {
+                return Err((StatusCode::BAD_REQUEST, "event_ids or connection_id is required").into_response());
+            }
+        };
+        raw_now_playing_events::Entity::find()
+            .filter(raw_now_playing_events::Column::ConnectionId.is_in(connection_ids))
+            .limit(sample.limit.unwrap_or(SAMPLE_DEFAULT_LIMIT).clamp(1, SAMPLE_MAX_LIMIT))
+    };
+    query
+        .order_by_desc(raw_now_playing_events::Column::ObservedAt)
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+#[derive(Deserialize)]
+pub struct ScriptTestRequest {
+    pub mapping_json: serde_json::Value,
+    #[serde(flatten)]
+    pub sample: EventSample,
+}
+
+#[derive(Serialize)]
+pub struct ScriptTestResult {
+    pub event_id: Uuid,
+    pub observed_at: DateTime<FixedOffset>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extracted: Option<ExtractedFields>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Runs a draft script mapping against stored events without saving it.
+async fn test_script_mapping(
+    State(state): State<AppState>,
+    Json(payload): Json<ScriptTestRequest>,
+) -> Result<Json<Vec<ScriptTestResult>>, Response> {
+    validate_mapping(&payload.mapping_json).map_err(|e| (StatusCode::BAD_REQUEST, Json(e)).into_response())?;
+    let script = payload
+        .mapping_json
+        .get("script")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "mapping_json has no script").into_response())?;
+    let events = load_sample_events(&state.db, &payload.sample, Vec::new()).await?;
+
+    let mut connection_types = HashMap::new();
+    let mut station_mappings = HashMap::new();
+    let mut results = Vec::with_capacity(events.len());
+    for event in events {
+        if let Entry::Vacant(entry) = connection_types.entry(event.connection_id) {
+            let conn = now_playing_connections::Entity::find_by_id(event.connection_id)
+                .one(&state.db)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+            entry.insert(conn.map(|c| c.connection_type).unwrap_or_default());
+        }
+        if let Entry::Vacant(entry) = station_mappings.entry(event.station_id) {
+            let mut mapping_json = payload.mapping_json.clone();
+            apply_station_timezone(&state.db, event.station_id, &mut mapping_json)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+            entry.insert(mapping_json);
+        }
+        let mapping_obj = station_mappings[&event.station_id].as_object();
+
+        let (extracted, error) =
+            match script_fields(script, &event.raw_payload, mapping_obj, &connection_types[&event.connection_id]) {
+                Ok(mut fields) => {
+                    apply_field_transforms(&mut fields, mapping_obj);
+                    (Some(ExtractedFields::from(fields)), None)
+                }
+                Err(e) => (None, Some(e)),
+            };
+        results.push(ScriptTestResult {
+            event_id: event.id,
+            observed_at: event.observed_at,
+            extracted,
+            error,
+        });
+    }
+    Ok(Json(results))
+}
+
+#[derive(Deserialize)]
+pub struct EvaluateMappingRequest {
+    // A draft to try instead of the saved `mapping_json`.
+    pub mapping_json: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub sample: EventSample,
+}
+
+#[derive(Serialize, Default)]
+pub struct EvaluationSummary {
+    pub total: usize,
+    pub improved: usize,
+    pub regressed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Serialize)]
+pub struct EventEvaluation {
+    pub event_id: Uuid,
+    pub connection_id: Uuid,
+    pub observed_at: DateTime<FixedOffset>,
+    pub outcome: ExtractionOutcome,
+    pub stored: DerivedColumns,
+    pub extracted: DerivedColumns,
+}
+
+#[derive(Serialize)]
+pub struct MappingEvaluation {
+    pub mapping_id: Uuid,
+    pub draft: bool,
+    pub summary: EvaluationSummary,
+    pub events: Vec<EventEvaluation>,
+}
+
+// Re-runs extraction over stored events with the saved mapping or a draft and
+// compares the result with what's stored, without writing anything. Without
+// `event_ids` or `connection_id`, the latest events of every connection using
+// the mapping are sampled.
+async fn evaluate_mapping(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<EvaluateMappingRequest>,
+) -> Result<Json<MappingEvaluation>, Response> {
+    let mut mapping = payload_mappings::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let draft = payload.mapping_json.is_some();
+    if let Some(mapping_json) = payload.mapping_json {
+        validate_mapping(&mapping_json).map_err(|e| (StatusCode::BAD_REQUEST, Json(e)).into_response())?;
+        mapping.mapping_json = mapping_json;
+    }
+
+    let mapped_connections = now_playing_connections::Entity::find()
+        .filter(now_playing_connections::Column::PayloadMappingId.eq(id))
+        .all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .into_iter()
+        .map(|conn| conn.id)
+        .collect();
+    let events = load_sample_events(&state.db, &payload.sample, mapped_connections).await?;
+
+    let mut connections = HashMap::new();
+    let mut station_mappings = HashMap::new();
+    let mut summary = EvaluationSummary::default();
+    let mut evaluations = Vec::with_capacity(events.len());
+    for event in events {
+        if let Entry::Vacant(entry) = connections.entry(event.connection_id) {
+            let conn = now_playing_connections::Entity::find_by_id(event.connection_id)
+                .one(&state.db)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+            entry.insert(conn);
+        }
+        let Some(conn) = &connections[&event.connection_id] else {
+            continue;
+        };
+        if let Entry::Vacant(entry) = station_mappings.entry(event.station_id) {
+            let mut station_mapping = mapping.clone();
+            apply_station_timezone(&state.db, event.station_id, &mut station_mapping.mapping_json)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+            entry.insert(station_mapping);
+        }
+
+        let stored = DerivedColumns::from_event(&event);
+        let extracted = reextract_event(&event, conn, Some(&station_mappings[&event.station_id]));
+        let outcome = compare_extraction(&stored, &extracted);
+        summary.total += 1;
+        match outcome {
+            ExtractionOutcome::Improved => summary.improved += 1,
+            ExtractionOutcome::Regressed => summary.regressed += 1,
+            ExtractionOutcome::Changed => summary.changed += 1,
+            ExtractionOutcome::Unchanged => summary.unchanged += 1,
+        }
+        evaluations.push(EventEvaluation {
+            event_id: event.id,
+            connection_id: event.connection_id,
+            observed_at: event.observed_at,
+            outcome,
+            stored,
+            extracted,
+        });
+    }
 
-    let mut mapping: payload_mappings::ActiveModel = mapping.into();
-    mapping.name = Set(payload.name);
-    mapping.description = Set(payload.description);
-    mapping.mapping_json = Set(payload.mapping_json);
-    mapping.updated_at = Set(Utc::now().fixed_offset());
+    Ok(Json(MappingEvaluation {
+        mapping_id: id,
+        draft,
+        summary,
+        events: evaluations,
+    }))
+}
 
-    mapping.update(&state.db)
+async fn list_mapping_versions(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<payload_mapping_versions::Model>>, StatusCode> {
+    list_versions(&state.db, id)
         .await
         .map(Json)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
@@ -127,11 +495,28 @@ async fn delete_mapping(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// The connection as the API returns it: push-ingest secrets are masked so
+// reading connections doesn't allow forging pushes. Creating a connection
+// returns them once, unmasked, so a generated token can be handed out.
+// Proxy credentials are always masked.
+fn redacted(mut conn: now_playing_connections::Model) -> now_playing_connections::Model {
+    let mask = |secret: Option<String>| secret.map(|_| MASKED_SECRET.to_string());
+    conn.ingest_token = mask(conn.ingest_token);
+    conn.ingest_hmac_secret = mask(conn.ingest_hmac_secret);
+    conn.proxy_url = conn.proxy_url.map(|url| mask_proxy_userinfo(&url, MASKED_SECRET));
+    conn
+}
+
+// A secret from an update request, unless the client echoed the mask back.
+fn unmasked(secret: Option<String>) -> Option<String> {
+    secret.filter(|s| s != MASKED_SECRET)
+}
+
 async fn list_connections(State(state): State<AppState>) -> Result<Json<Vec<now_playing_connections::Model>>, StatusCode> {
     now_playing_connections::Entity::find()
         .all(&state.db)
         .await
-        .map(Json)
+        .map(|conns| Json(conns.into_iter().map(redacted).collect()))
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
 }
 
@@ -139,15 +524,18 @@ async fn create_connection(
     State(state): State<AppState>,
     Json(payload): Json<CreateConnection>,
 ) -> Result<Json<now_playing_connections::Model>, StatusCode> {
+    validate_connection_payload(&payload)?;
     let now = Utc::now().fixed_offset();
     let headers_json = normalize_headers_for_storage(
         &payload.connection_type,
         payload.headers_json,
     );
+    let ingest_token = resolve_ingest_token(&payload.connection_type, payload.ingest_token, None);
     let conn = now_playing_connections::ActiveModel {
         id: Set(Uuid::new_v4()),
         station_id: Set(payload.station_id),
         payload_mapping_id: Set(payload.payload_mapping_id),
+        auto_mapping: Set(payload.auto_mapping),
         name: Set(payload.name),
         connection_type: Set(payload.connection_type),
         url: Set(payload.url),
@@ -155,6 +543,15 @@ async fn create_connection(
         headers_json: Set(headers_json),
         enabled: Set(payload.enabled),
         use_duration_polling: Set(payload.use_duration_polling),
+        ingest_token: Set(ingest_token),
+        ingest_hmac_secret: Set(payload.ingest_hmac_secret.filter(|s| !s.is_empty())),
+        credential_id: Set(payload.credential_id),
+        request_chain_json: Set(payload.request_chain_json),
+        response_charset: Set(payload.response_charset.filter(|c| !c.trim().is_empty())),
+        cookie_jar_enabled: Set(payload.cookie_jar_enabled),
+        cookie_warmup_url: Set(payload.cookie_warmup_url.filter(|u| !u.trim().is_empty())),
+        proxy_url: Set(payload.proxy_url.filter(|u| !u.trim().is_empty())),
+        proxy_pool_id: Set(payload.proxy_pool_id),
         created_at: Set(now),
         updated_at: Set(now),
         ..Default::default()
@@ -162,7 +559,15 @@ async fn create_connection(
 
     conn.insert(&state.db)
         .await
-        .map(Json)
+        .map(|conn| {
+            let ingest_token = conn.ingest_token.clone();
+            let ingest_hmac_secret = conn.ingest_hmac_secret.clone();
+            Json(now_playing_connections::Model {
+                ingest_token,
+                ingest_hmac_secret,
+                ..redacted(conn)
+            })
+        })
         .map_err(|e| {
             tracing::error!("Failed to create connection: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR
@@ -177,7 +582,7 @@ async fn get_connection(
         .one(&state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .map(Json)
+        .map(|conn| Json(redacted(conn)))
         .ok_or(StatusCode::NOT_FOUND)
 }
 
@@ -186,19 +591,39 @@ async fn update_connection(
     Path(id): Path<Uuid>,
     Json(payload): Json<CreateConnection>,
 ) -> Result<Json<now_playing_connections::Model>, StatusCode> {
+    validate_connection_payload(&payload)?;
     let conn = now_playing_connections::Entity::find_by_id(id)
         .one(&state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let mut conn: now_playing_connections::ActiveModel = conn.into();
+    let ingest_token = resolve_ingest_token(
+        &payload.connection_type,
+        unmasked(payload.ingest_token),
+        conn.ingest_token.clone(),
+    );
+    // An empty string clears the secret; omitting it (or sending the mask)
+    // keeps the current one.
+    let ingest_hmac_secret = unmasked(payload.ingest_hmac_secret)
+        .or_else(|| conn.ingest_hmac_secret.clone())
+        .filter(|s| !s.is_empty());
     let headers_json = normalize_headers_for_storage(
         &payload.connection_type,
         payload.headers_json,
     );
+    // Cached chain step values came from the old requests.
+    let chain_changed = conn.request_chain_json != payload.request_chain_json
+        || conn.url != payload.url
+        || conn.headers_json != headers_json;
+    let proxy_url = payload
+        .proxy_url
+        .filter(|u| !u.trim().is_empty())
+        .map(|u| restore_proxy_userinfo(&u, conn.proxy_url.as_deref(), MASKED_SECRET));
+    let mut conn: now_playing_connections::ActiveModel = conn.into();
     conn.station_id = Set(payload.station_id);
     conn.payload_mapping_id = Set(payload.payload_mapping_id);
+    conn.auto_mapping = Set(payload.auto_mapping);
     conn.name = Set(payload.name);
     conn.connection_type = Set(payload.connection_type);
     conn.url = Set(payload.url);
@@ -206,12 +631,69 @@ async fn update_connection(
     conn.headers_json = Set(headers_json);
     conn.enabled = Set(payload.enabled);
     conn.use_duration_polling = Set(payload.use_duration_polling);
+    conn.ingest_token = Set(ingest_token);
+    conn.ingest_hmac_secret = Set(ingest_hmac_secret);
+    conn.credential_id = Set(payload.credential_id);
+    conn.request_chain_json = Set(payload.request_chain_json);
+    conn.response_charset = Set(payload.response_charset.filter(|c| !c.trim().is_empty()));
+    if !payload.cookie_jar_enabled {
+        conn.cookies_json = Set(None);
+    }
+    conn.cookie_jar_enabled = Set(payload.cookie_jar_enabled);
+    conn.cookie_warmup_url = Set(payload.cookie_warmup_url.filter(|u| !u.trim().is_empty()));
+    conn.proxy_url = Set(proxy_url);
+    conn.proxy_pool_id = Set(payload.proxy_pool_id);
     conn.updated_at = Set(Utc::now().fixed_offset());
 
-    conn.update(&state.db)
+    let conn = conn.update(&state.db)
         .await
-        .map(Json)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if chain_changed {
+        clear_chain_cache(id);
+    }
+    Ok(Json(redacted(conn)))
+}
+
+fn validate_connection_payload(payload: &CreateConnection) -> Result<(), StatusCode> {
+    if let Some(chain) = &payload.request_chain_json
+        && let Err(e) = validate_request_chain(chain)
+    {
+        tracing::warn!("Rejected request chain: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(charset) = payload.response_charset.as_deref().filter(|c| !c.trim().is_empty())
+        && !is_known_charset(charset)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(proxy_url) = payload.proxy_url.as_deref().filter(|u| !u.trim().is_empty())
+        && !is_valid_proxy_url(proxy_url.trim())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Auto connections pick from the library, and HLS segments carry their own
+    // metadata format, so neither combination makes sense.
+    if payload.auto_mapping
+        && (payload.payload_mapping_id.is_some() || is_hls_connection_type(&payload.connection_type))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(())
+}
+
+// Push connections always get a token so the ingest endpoint is never left open.
+fn resolve_ingest_token(
+    connection_type: &str,
+    requested: Option<String>,
+    existing: Option<String>,
+) -> Option<String> {
+    let token = requested
+        .filter(|t| !t.trim().is_empty())
+        .or(existing);
+    if token.is_none() && is_push_connection_type(connection_type) {
+        return Some(format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()));
+    }
+    token
 }
 
 async fn delete_connection(
@@ -230,6 +712,39 @@ async fn delete_connection(
     Ok(StatusCode::NO_CONTENT)
 }
 
+async fn get_cookies(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<StoredCookie>>, StatusCode> {
+    let conn = now_playing_connections::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(CookieJar::from_value(conn.cookies_json.as_ref()).snapshot()))
+}
+
+async fn clear_cookies(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let conn = now_playing_connections::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut conn: now_playing_connections::ActiveModel = conn.into();
+    conn.cookies_json = Set(None);
+    conn.updated_at = Set(Utc::now().fixed_offset());
+
+    conn.update(&state.db)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 async fn enable_connection(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
@@ -276,6 +791,18 @@ pub struct TestResult {
     pub content_type: Option<String>,
     pub raw_payload: serde_json::Value,
     pub extracted: ExtractedFields,
+    // Every play found in the list when the mapping uses `"list_mode": "all"`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<ExtractedFields>,
+    // Before/after values for each `<field>_transforms` step.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<FieldTrace>,
+    // Mapping paths that couldn't be evaluated against this payload.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mapping_errors: Vec<String>,
+    // The library mapping picked for an `auto_mapping` connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected_mapping_id: Option<Uuid>,
 }
 
 #[derive(Serialize)]
@@ -283,6 +810,28 @@ pub struct ExtractedFields {
     pub artist: Option<String>,
     pub title: Option<String>,
     pub album: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reported_at: Option<DateTime<FixedOffset>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reported_at_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<i64>,
+    #[serde(flatten)]
+    pub extended: ExtendedFields,
+}
+
+impl From<ParsedFields> for ExtractedFields {
+    fn from(fields: ParsedFields) -> Self {
+        Self {
+            artist: fields.artist,
+            title: fields.title,
+            album: fields.album,
+            reported_at: fields.reported_at,
+            reported_at_error: fields.reported_at_error,
+            duration_seconds: fields.duration_seconds,
+            extended: fields.extended,
+        }
+    }
 }
 
 async fn test_connection(
@@ -295,26 +844,65 @@ async fn test_connection(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    if conn.connection_type.eq_ignore_ascii_case("ws_json") {
+    if conn.connection_type.eq_ignore_ascii_case("ws_json")
+        || is_push_connection_type(&conn.connection_type)
+        || is_listener_connection_type(&conn.connection_type)
+    {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let mapping = if let Some(mapping_id) = conn.payload_mapping_id {
-        payload_mappings::Entity::find_by_id(mapping_id)
-            .one(&state.db)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    } else {
-        None
-    };
+    let mut mapping = load_connection_mapping(&state.db, &conn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let result = fetch_and_parse(&conn, mapping.as_ref())
+    let mut result = fetch_and_parse(&state.db, &conn, mapping.as_ref())
         .await
         .map_err(|e| {
             tracing::error!("Test fetch failed: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            if e.downcast_ref::<ProxyError>().is_some() {
+                StatusCode::BAD_GATEWAY
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
         })?;
 
+    let mut mapping_errors = Vec::new();
+    let resolution = resolve_payload_mapping(&state.db, &conn, mapping.as_ref(), &result.raw_payload, &conn.connection_type)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match resolution {
+        MappingResolution::Fixed => {}
+        MappingResolution::Selected(selected) => {
+            let fields = extract_fields(&result.raw_payload, Some(&selected), &conn.connection_type);
+            result.apply_fields(fields, selected.current_version_id);
+            mapping = Some(selected);
+        }
+        MappingResolution::Mismatch(reason) => mapping_errors.push(format!("MAPPING_MISMATCH: {}", reason)),
+    }
+
+    let items = if is_list_mode_all(mapping.as_ref()) {
+        extract_list_items(&result.raw_payload, mapping.as_ref(), &conn.connection_type)
+            .into_iter()
+            .map(|item| ExtractedFields::from(item.fields))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let (_, transforms) = extract_fields_traced(&result.raw_payload, mapping.as_ref(), &conn.connection_type);
+
+    // A broken XPath or failing script otherwise only shows up as an empty field.
+    let mapping_obj = mapping.as_ref().and_then(|m| m.mapping_json.as_object());
+    match (mapping.as_ref().and_then(mapping_script), result.raw_payload.as_str()) {
+        (Some(script), _) => mapping_errors.extend(
+            script_fields(script, &result.raw_payload, mapping_obj, &conn.connection_type).err(),
+        ),
+        (None, Some(xml)) if is_xml_connection_type(&conn.connection_type) => {
+            mapping_errors.extend(xpath_errors(xml, mapping_obj))
+        }
+        _ => {}
+    }
+
     Ok(Json(TestResult {
         status: result.status,
         content_type: result.content_type,
@@ -323,6 +911,47 @@ async fn test_connection(
             artist: result.reported_artist,
             title: result.reported_title,
             album: result.reported_album,
+            reported_at: result.reported_at,
+            reported_at_error: result.reported_at_error,
+            duration_seconds: result.reported_duration_seconds,
+            extended: result.extended,
         },
+        items,
+        transforms,
+        mapping_errors,
+        selected_mapping_id: mapping.filter(|_| conn.auto_mapping).map(|m| m.id),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_conn;
+
+    #[test]
+    fn masks_ingest_secrets_and_keeps_them_on_update() {
+        let conn = now_playing_connections::Model {
+            ingest_token: Some("tok-123".to_string()),
+            ingest_hmac_secret: Some("hmac-456".to_string()),
+            ..test_conn("push")
+        };
+        let shown = redacted(conn.clone());
+        assert_eq!(shown.ingest_token.as_deref(), Some(MASKED_SECRET));
+        assert_eq!(shown.ingest_hmac_secret.as_deref(), Some(MASKED_SECRET));
+        assert_eq!(redacted(test_conn("push")).ingest_token, None);
+
+        let echoed = resolve_ingest_token("push", unmasked(shown.ingest_token), conn.ingest_token.clone());
+        assert_eq!(echoed.as_deref(), Some("tok-123"));
+        assert_eq!(unmasked(Some("new".to_string())).as_deref(), Some("new"));
+        assert_eq!(unmasked(shown.ingest_hmac_secret), None);
+    }
+
+    #[test]
+    fn masks_proxy_credentials() {
+        let conn = now_playing_connections::Model {
+            proxy_url: Some("http://user:pw@proxy.local:3128".to_string()),
+            ..test_conn("http_json")
+        };
+        assert_eq!(redacted(conn).proxy_url.as_deref(), Some("http://********@proxy.local:3128"));
+    }
+}
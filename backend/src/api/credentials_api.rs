@@ -0,0 +1,250 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use sea_orm::{prelude::*, Set};
+use serde::Deserialize;
+use uuid::Uuid;
+use chrono::Utc;
+use crate::entities::credentials;
+use crate::api::AppState;
+use crate::poller::auth::{refresh_token, uses_token};
+use crate::poller::utils::build_http_client;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_credentials).post(create_credential))
+        .route("/{id}", get(get_credential).put(update_credential).delete(delete_credential))
+        .route("/{id}/refresh", post(refresh_credential))
+}
+
+#[derive(Deserialize)]
+pub struct CreateCredential {
+    pub name: String,
+    pub kind: String,
+    pub config_json: serde_json::Value,
+}
+
+fn is_supported_kind(kind: &str) -> bool {
+    matches!(kind, "api_key" | "oauth2_client_credentials" | "login")
+}
+
+// Stands in for secret config values in responses. Sending it back on update
+// keeps the stored value.
+pub const MASKED_SECRET: &str = "********";
+
+// Keys whose values are secret; for objects (login `headers` and `body`) every
+// nested value is.
+const SECRET_KEYS: [&str; 4] = ["value", "client_secret", "headers", "body"];
+
+// The credential as the API returns it: config keys are kept so clients can
+// show and edit them, but secret values are masked.
+fn redacted(mut credential: credentials::Model) -> credentials::Model {
+    if let Some(config) = credential.config_json.as_object_mut() {
+        for key in SECRET_KEYS {
+            if let Some(value) = config.get_mut(key) {
+                mask_value(value);
+            }
+        }
+    }
+    credential
+}
+
+fn mask_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => map.values_mut().for_each(mask_value),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(mask_value),
+        serde_json::Value::Null => {}
+        other => *other = serde_json::Value::String(MASKED_SECRET.to_string()),
+    }
+}
+
+// Puts stored values back wherever the client echoed the mask.
+fn restore_masked(incoming: &mut serde_json::Value, stored: &serde_json::Value) {
+    match incoming {
+        serde_json::Value::String(s) if s == MASKED_SECRET => {
+            *incoming = stored.clone();
+        }
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if let Some(stored) = stored.get(key) {
+                    restore_masked(value, stored);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (index, value) in items.iter_mut().enumerate() {
+                if let Some(stored) = stored.get(index) {
+                    restore_masked(value, stored);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+async fn list_credentials(State(state): State<AppState>) -> Result<Json<Vec<credentials::Model>>, StatusCode> {
+    credentials::Entity::find()
+        .all(&state.db)
+        .await
+        .map(|list| Json(list.into_iter().map(redacted).collect()))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn create_credential(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateCredential>,
+) -> Result<Json<credentials::Model>, StatusCode> {
+    let kind = payload.kind.to_ascii_lowercase();
+    if !is_supported_kind(&kind) || !payload.config_json.is_object() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let now = Utc::now().fixed_offset();
+    let credential = credentials::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(payload.name),
+        kind: Set(kind),
+        config_json: Set(payload.config_json),
+        access_token: Set(None),
+        access_token_expires_at: Set(None),
+        last_error: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    credential.insert(&state.db)
+        .await
+        .map(|c| Json(redacted(c)))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn get_credential(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<credentials::Model>, StatusCode> {
+    credentials::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|c| Json(redacted(c)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn update_credential(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CreateCredential>,
+) -> Result<Json<credentials::Model>, StatusCode> {
+    let kind = payload.kind.to_ascii_lowercase();
+    if !is_supported_kind(&kind) || !payload.config_json.is_object() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let credential = credentials::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut config_json = payload.config_json;
+    restore_masked(&mut config_json, &credential.config_json);
+
+    // Any cached token was issued for the old settings.
+    let mut credential: credentials::ActiveModel = credential.into();
+    credential.name = Set(payload.name);
+    credential.kind = Set(kind);
+    credential.config_json = Set(config_json);
+    credential.access_token = Set(None);
+    credential.access_token_expires_at = Set(None);
+    credential.last_error = Set(None);
+    credential.updated_at = Set(Utc::now().fixed_offset());
+
+    credential.update(&state.db)
+        .await
+        .map(|c| Json(redacted(c)))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn delete_credential(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let res = credentials::Entity::delete_by_id(id)
+        .exec(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if res.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Forces a new token so credentials can be checked before connections use them.
+// The outcome is recorded on the credential either way.
+async fn refresh_credential(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<credentials::Model>, StatusCode> {
+    let credential = credentials::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !uses_token(&credential) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let client = build_http_client().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let _ = refresh_token(&state.db, &client, &credential, Utc::now().fixed_offset()).await;
+
+    credentials::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|c| Json(redacted(c)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_credential;
+
+    #[test]
+    fn masks_secrets_and_keeps_them_on_update() {
+        let stored = serde_json::json!({
+            "url": "https://example.com/login",
+            "headers": { "X-Client": "abc" },
+            "body": { "username": "dj", "password": "hunter2" },
+            "token_path": "data.token"
+        });
+        let credential = test_credential("login", stored.clone());
+
+        let shown = redacted(credential).config_json;
+        assert_eq!(shown["url"], "https://example.com/login");
+        assert_eq!(shown["token_path"], "data.token");
+        assert_eq!(shown["headers"]["X-Client"], MASKED_SECRET);
+        assert_eq!(shown["body"]["username"], MASKED_SECRET);
+        assert_eq!(shown["body"]["password"], MASKED_SECRET);
+
+        // Echoing the view back with one real change keeps the other secrets.
+        let mut edited = shown.clone();
+        edited["body"]["password"] = serde_json::json!("new-pass");
+        restore_masked(&mut edited, &stored);
+        assert_eq!(edited["body"], serde_json::json!({ "username": "dj", "password": "new-pass" }));
+        assert_eq!(edited["headers"]["X-Client"], "abc");
+
+        let api_key = serde_json::json!({ "value": "k-123", "header": "X-Key" });
+        let mut masked = api_key.clone();
+        mask_value(&mut masked["value"]);
+        assert_eq!(masked["value"], MASKED_SECRET);
+        restore_masked(&mut masked, &api_key);
+        assert_eq!(masked, api_key);
+    }
+}
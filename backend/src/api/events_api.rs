@@ -7,12 +7,13 @@ use axum::{
 use sea_orm::{prelude::*, QueryOrder, QuerySelect, EntityTrait};
 use serde::Deserialize;
 use uuid::Uuid;
-use crate::entities::raw_now_playing_events;
+use crate::entities::{raw_now_playing_events, rejected_events};
 use crate::api::AppState;
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/", get(list_events).on(axum::routing::MethodFilter::DELETE, clear_events))
+        .route("/rejected", get(list_rejected_events))
         .route("/{id}", get(get_event))
 }
 
@@ -30,6 +31,20 @@ pub struct EventQuery {
     pub connection_id: Option<Uuid>,
     pub limit: Option<u64>,
     pub before: Option<DateTimeWithTimeZone>,
+    pub isrc: Option<String>,
+    pub upc: Option<String>,
+    pub label: Option<String>,
+    pub composer: Option<String>,
+    pub category: Option<String>,
+    pub item_type: Option<String>,
+    // VALID, FLAGGED or NON_MUSIC.
+    pub validity_status: Option<String>,
+    // song, spot, promo, talk or station_id.
+    pub content_class: Option<String>,
+    // Matches events whose `extra_fields` has this key; with `extra_value`,
+    // the key's value (as text) must equal it too.
+    pub extra_key: Option<String>,
+    pub extra_value: Option<String>,
 }
 
 async fn list_events(
@@ -51,6 +66,33 @@ async fn list_events(
         select = select.filter(raw_now_playing_events::Column::ObservedAt.lt(before));
     }
 
+    let metadata = [
+        (raw_now_playing_events::Column::Isrc, query.isrc),
+        (raw_now_playing_events::Column::Upc, query.upc),
+        (raw_now_playing_events::Column::Label, query.label),
+        (raw_now_playing_events::Column::Composer, query.composer),
+        (raw_now_playing_events::Column::Category, query.category),
+        (raw_now_playing_events::Column::ItemType, query.item_type),
+        (raw_now_playing_events::Column::ValidityStatus, query.validity_status),
+        (raw_now_playing_events::Column::ContentClass, query.content_class),
+    ];
+    for (column, value) in metadata {
+        if let Some(value) = value {
+            select = select.filter(column.eq(value));
+        }
+    }
+
+    match (query.extra_key, query.extra_value) {
+        (Some(key), Some(value)) => {
+            select = select.filter(Expr::cust_with_values("extra_fields ->> $1 = $2", [key, value]));
+        }
+        (Some(key), None) => {
+            select = select.filter(Expr::cust_with_values("jsonb_exists(extra_fields, $1)", [key]));
+        }
+        (None, Some(_)) => return Err(StatusCode::BAD_REQUEST),
+        (None, None) => {}
+    }
+
     let limit = query.limit.unwrap_or(100);
     select = select.limit(limit);
 
@@ -60,6 +102,42 @@ async fn list_events(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
 }
 
+#[derive(Deserialize)]
+pub struct RejectedEventQuery {
+    pub station_id: Option<Uuid>,
+    pub connection_id: Option<Uuid>,
+    pub limit: Option<u64>,
+    pub before: Option<DateTimeWithTimeZone>,
+}
+
+// Payloads rejected by validity rules that have `retain` set.
+async fn list_rejected_events(
+    State(state): State<AppState>,
+    Query(query): Query<RejectedEventQuery>,
+) -> Result<Json<Vec<rejected_events::Model>>, StatusCode> {
+    let mut select = rejected_events::Entity::find()
+        .order_by_desc(rejected_events::Column::ObservedAt);
+
+    if let Some(station_id) = query.station_id {
+        select = select.filter(rejected_events::Column::StationId.eq(station_id));
+    }
+
+    if let Some(connection_id) = query.connection_id {
+        select = select.filter(rejected_events::Column::ConnectionId.eq(connection_id));
+    }
+
+    if let Some(before) = query.before {
+        select = select.filter(rejected_events::Column::ObservedAt.lt(before));
+    }
+
+    select
+        .limit(query.limit.unwrap_or(100))
+        .all(&state.db)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 async fn get_event(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
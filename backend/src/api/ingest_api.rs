@@ -0,0 +1,139 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use hmac::{Hmac, Mac};
+use sea_orm::prelude::*;
+use serde::Deserialize;
+use sha2::Sha256;
+use uuid::Uuid;
+use crate::entities::now_playing_connections;
+use crate::api::AppState;
+use crate::poller::utils::{handle_push_payload, is_push_connection_type};
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/{connection_id}", post(ingest))
+}
+
+#[derive(Deserialize)]
+pub struct IngestQuery {
+    pub token: Option<String>,
+}
+
+// Playout systems fire this on every song change. The token can be sent as a
+// bearer token, an `X-Ingest-Token` header or a `?token=` query parameter, since
+// many automation systems can only be configured with a URL.
+async fn ingest(
+    State(state): State<AppState>,
+    Path(connection_id): Path<Uuid>,
+    Query(query): Query<IngestQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let conn = now_playing_connections::Entity::find_by_id(connection_id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .filter(|c| is_push_connection_type(&c.connection_type))
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let expected_token = conn
+        .ingest_token
+        .as_deref()
+        .filter(|t| !t.is_empty())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let provided_token = request_token(&headers, query.token.as_deref()).ok_or(StatusCode::UNAUTHORIZED)?;
+    if !constant_time_eq(expected_token.as_bytes(), provided_token.as_bytes()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if let Some(secret) = conn.ingest_hmac_secret.as_deref().filter(|s| !s.is_empty()) {
+        let signature = header_str(&headers, "x-signature-256")
+            .or_else(|| header_str(&headers, "x-signature"))
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if !verify_signature(secret, &body, signature) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    if !conn.enabled {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if body.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let content_type = header_str(&headers, header::CONTENT_TYPE.as_str()).map(|s| s.to_string());
+
+    handle_push_payload(&state.db, &conn, content_type, &body)
+        .await
+        .map(|_| StatusCode::ACCEPTED)
+        .map_err(|e| {
+            tracing::error!("Failed to ingest push payload for {}: {:?}", conn.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+fn request_token<'a>(headers: &'a HeaderMap, query_token: Option<&'a str>) -> Option<&'a str> {
+    header_str(headers, header::AUTHORIZATION.as_str())
+        .and_then(|v| v.strip_prefix("Bearer ").or_else(|| v.strip_prefix("bearer ")))
+        .or_else(|| header_str(headers, "x-ingest-token"))
+        .or(query_token)
+        .map(|t| t.trim())
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+// Signatures are hex-encoded HMAC-SHA256 of the raw body, optionally prefixed
+// with `sha256=` as most webhook senders do.
+fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(expected) = hex::decode(signature) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_hex_hmac_signatures_with_optional_prefix() {
+        let body = br#"{"artist":"Tom Petty","title":"Free Fallin'"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        assert!(verify_signature("secret", body, &signature));
+        assert!(verify_signature("secret", body, &format!("sha256={}", signature)));
+        assert!(!verify_signature("other", body, &signature));
+        assert!(!verify_signature("secret", body, "not-hex"));
+    }
+
+    #[test]
+    fn reads_token_from_bearer_header_before_query() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer abc".parse().unwrap());
+        assert_eq!(request_token(&headers, Some("query")), Some("abc"));
+        assert_eq!(request_token(&HeaderMap::new(), Some("query")), Some("query"));
+    }
+}
@@ -3,7 +3,11 @@ use sea_orm::prelude::*;
 
 pub mod stations_api;
 pub mod connections_api;
+pub mod credentials_api;
 pub mod events_api;
+pub mod ingest_api;
+pub mod proxy_pools_api;
+pub mod reextraction_api;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -14,6 +18,10 @@ pub fn router(state: AppState) -> Router {
     Router::new()
         .nest("/stations", stations_api::router())
         .nest("/connections", connections_api::router())
+        .nest("/credentials", credentials_api::router())
         .nest("/events", events_api::router())
+        .nest("/ingest", ingest_api::router())
+        .nest("/proxy-pools", proxy_pools_api::router())
+        .nest("/reextraction-jobs", reextraction_api::router())
         .with_state(state)
 }
@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use sea_orm::{prelude::*, Set};
+use serde::Deserialize;
+use uuid::Uuid;
+use chrono::Utc;
+use crate::entities::proxy_pools;
+use crate::api::AppState;
+use crate::poller::proxy::is_valid_proxy_url;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_proxy_pools).post(create_proxy_pool))
+        .route("/{id}", get(get_proxy_pool).put(update_proxy_pool).delete(delete_proxy_pool))
+}
+
+#[derive(Deserialize)]
+pub struct CreateProxyPool {
+    pub name: String,
+    pub proxy_urls: Vec<String>,
+}
+
+fn normalized_proxy_urls(urls: Vec<String>) -> Result<serde_json::Value, StatusCode> {
+    let urls: Vec<String> = urls
+        .into_iter()
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .collect();
+    if urls.is_empty() || !urls.iter().all(|u| is_valid_proxy_url(u)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(serde_json::json!(urls))
+}
+
+async fn list_proxy_pools(State(state): State<AppState>) -> Result<Json<Vec<proxy_pools::Model>>, StatusCode> {
+    proxy_pools::Entity::find()
+        .all(&state.db)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn create_proxy_pool(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateProxyPool>,
+) -> Result<Json<proxy_pools::Model>, StatusCode> {
+    let proxy_urls_json = normalized_proxy_urls(payload.proxy_urls)?;
+    let now = Utc::now().fixed_offset();
+    let pool = proxy_pools::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(payload.name),
+        proxy_urls_json: Set(proxy_urls_json),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    pool.insert(&state.db)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn get_proxy_pool(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<proxy_pools::Model>, StatusCode> {
+    proxy_pools::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn update_proxy_pool(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CreateProxyPool>,
+) -> Result<Json<proxy_pools::Model>, StatusCode> {
+    let proxy_urls_json = normalized_proxy_urls(payload.proxy_urls)?;
+    let pool = proxy_pools::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut pool: proxy_pools::ActiveModel = pool.into();
+    pool.name = Set(payload.name);
+    pool.proxy_urls_json = Set(proxy_urls_json);
+    pool.updated_at = Set(Utc::now().fixed_offset());
+
+    pool.update(&state.db)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn delete_proxy_pool(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let res = proxy_pools::Entity::delete_by_id(id)
+        .exec(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if res.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
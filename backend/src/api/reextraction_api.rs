@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{prelude::*, QueryOrder, Set};
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::entities::{reextraction_jobs, stations};
+use crate::api::AppState;
+use crate::poller::reextract::{spawn_reextraction, JOB_PENDING};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs).post(create_job))
+        .route("/{id}", get(get_job))
+}
+
+#[derive(Deserialize)]
+pub struct CreateReextractionJob {
+    pub station_id: Uuid,
+    pub connection_id: Option<Uuid>,
+    pub observed_from: Option<DateTime<FixedOffset>>,
+    pub observed_to: Option<DateTime<FixedOffset>>,
+    // Defaults to a dry run so the diff can be reviewed before anything changes.
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+async fn list_jobs(State(state): State<AppState>) -> Result<Json<Vec<reextraction_jobs::Model>>, StatusCode> {
+    reextraction_jobs::Entity::find()
+        .order_by_desc(reextraction_jobs::Column::CreatedAt)
+        .all(&state.db)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn create_job(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateReextractionJob>,
+) -> Result<(StatusCode, Json<reextraction_jobs::Model>), StatusCode> {
+    if let (Some(from), Some(to)) = (payload.observed_from, payload.observed_to)
+        && from >= to
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    stations::Entity::find_by_id(payload.station_id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let job = reextraction_jobs::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        station_id: Set(payload.station_id),
+        connection_id: Set(payload.connection_id),
+        observed_from: Set(payload.observed_from),
+        observed_to: Set(payload.observed_to),
+        dry_run: Set(payload.dry_run),
+        status: Set(JOB_PENDING.to_string()),
+        total_events: Set(0),
+        processed_events: Set(0),
+        changed_events: Set(0),
+        report_json: Set(None),
+        error: Set(None),
+        created_at: Set(Utc::now().fixed_offset()),
+        started_at: Set(None),
+        finished_at: Set(None),
+    }
+    .insert(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create re-extraction job: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    spawn_reextraction(state.db.clone(), job.clone());
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<reextraction_jobs::Model>, StatusCode> {
+    reextraction_jobs::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
@@ -9,6 +9,8 @@ use serde::Deserialize;
 use uuid::Uuid;
 use chrono::Utc;
 use crate::entities::stations;
+use crate::mapping_spec::validate_validity_rules;
+use crate::poller::classify::validate_classification;
 use crate::api::AppState;
 
 pub fn router() -> Router<AppState> {
@@ -22,6 +24,33 @@ pub struct CreateStation {
     pub name: String,
     pub callsign: Option<String>,
     pub website_url: Option<String>,
+    // IANA name such as `America/Chicago`; naive feed times are read in it.
+    pub timezone: Option<String>,
+    // Validity rules for events from mappings that don't set their own.
+    pub validity_rules_json: Option<serde_json::Value>,
+    // Category map, slogans and patterns for content classification.
+    pub classification_json: Option<serde_json::Value>,
+}
+
+fn validate_timezone(timezone: Option<&str>) -> Result<(), StatusCode> {
+    match timezone {
+        Some(name) if name.parse::<chrono_tz::Tz>().is_err() => Err(StatusCode::BAD_REQUEST),
+        _ => Ok(()),
+    }
+}
+
+fn validate_rules(rules: Option<&serde_json::Value>) -> Result<(), StatusCode> {
+    match rules {
+        Some(rules) if validate_validity_rules(rules).is_err() => Err(StatusCode::BAD_REQUEST),
+        _ => Ok(()),
+    }
+}
+
+fn validate_classification_config(config: Option<&serde_json::Value>) -> Result<(), StatusCode> {
+    match config {
+        Some(config) if validate_classification(config).is_err() => Err(StatusCode::BAD_REQUEST),
+        _ => Ok(()),
+    }
 }
 
 async fn list_stations(State(state): State<AppState>) -> Result<Json<Vec<stations::Model>>, StatusCode> {
@@ -36,12 +65,18 @@ async fn create_station(
     State(state): State<AppState>,
     Json(payload): Json<CreateStation>,
 ) -> Result<Json<stations::Model>, StatusCode> {
+    validate_timezone(payload.timezone.as_deref())?;
+    validate_rules(payload.validity_rules_json.as_ref())?;
+    validate_classification_config(payload.classification_json.as_ref())?;
     let now = Utc::now().fixed_offset();
     let station = stations::ActiveModel {
         id: Set(Uuid::new_v4()),
         name: Set(payload.name),
         callsign: Set(payload.callsign),
         website_url: Set(payload.website_url),
+        timezone: Set(payload.timezone),
+        validity_rules_json: Set(payload.validity_rules_json),
+        classification_json: Set(payload.classification_json),
         created_at: Set(now),
         updated_at: Set(now),
     };
@@ -69,6 +104,9 @@ async fn update_station(
     Path(id): Path<Uuid>,
     Json(payload): Json<CreateStation>,
 ) -> Result<Json<stations::Model>, StatusCode> {
+    validate_timezone(payload.timezone.as_deref())?;
+    validate_rules(payload.validity_rules_json.as_ref())?;
+    validate_classification_config(payload.classification_json.as_ref())?;
     let station = stations::Entity::find_by_id(id)
         .one(&state.db)
         .await
@@ -79,6 +117,9 @@ async fn update_station(
     station.name = Set(payload.name);
     station.callsign = Set(payload.callsign);
     station.website_url = Set(payload.website_url);
+    station.timezone = Set(payload.timezone);
+    station.validity_rules_json = Set(payload.validity_rules_json);
+    station.classification_json = Set(payload.classification_json);
     station.updated_at = Set(Utc::now().fixed_offset());
 
     station.update(&state.db)
@@ -20,6 +20,9 @@ struct SeedStation {
     name: String,
     callsign: Option<String>,
     website_url: Option<String>,
+    timezone: Option<String>,
+    validity_rules_json: Option<Value>,
+    classification_json: Option<Value>,
 }
 
 #[derive(Serialize)]
@@ -44,6 +47,15 @@ struct SeedConnection {
     headers_json: Option<Value>,
     enabled: bool,
     use_duration_polling: bool,
+    auto_mapping: bool,
+    // Exported as a reference; the credential's secrets stay in its own table.
+    credential_id: Option<Uuid>,
+    request_chain_json: Option<Value>,
+    response_charset: Option<String>,
+    cookie_jar_enabled: bool,
+    cookie_warmup_url: Option<String>,
+    proxy_url: Option<String>,
+    proxy_pool_id: Option<Uuid>,
 }
 
 #[tokio::main]
@@ -64,6 +76,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             name: s.name,
             callsign: s.callsign,
             website_url: s.website_url,
+            timezone: s.timezone,
+            validity_rules_json: s.validity_rules_json,
+            classification_json: s.classification_json,
         })
         .collect();
     stations_out.sort_by(|a, b| a.name.cmp(&b.name));
@@ -109,6 +124,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             headers_json: c.headers_json,
             enabled: c.enabled,
             use_duration_polling: c.use_duration_polling,
+            auto_mapping: c.auto_mapping,
+            credential_id: c.credential_id,
+            request_chain_json: c.request_chain_json,
+            response_charset: c.response_charset,
+            cookie_jar_enabled: c.cookie_jar_enabled,
+            cookie_warmup_url: c.cookie_warmup_url,
+            proxy_url: c.proxy_url,
+            proxy_pool_id: c.proxy_pool_id,
         })
         .collect();
     connections_out.sort_by(|a, b| a.name.cmp(&b.name));
@@ -1,5 +1,7 @@
 use backend::entities::{now_playing_connections, payload_mappings, stations};
 use backend::http_headers::normalize_headers_for_storage;
+use backend::mapping_spec::validate_mapping;
+use backend::mapping_versions::{insert_mapping_with_version, update_mapping_with_version, MappingInput};
 use chrono::Utc;
 use sea_orm::{ActiveModelTrait, ColumnTrait, Database, EntityTrait, QueryFilter, Set};
 use serde::Deserialize;
@@ -22,6 +24,9 @@ struct SeedStation {
     name: String,
     callsign: Option<String>,
     website_url: Option<String>,
+    timezone: Option<String>,
+    validity_rules_json: Option<Value>,
+    classification_json: Option<Value>,
 }
 
 #[derive(Deserialize)]
@@ -47,6 +52,22 @@ struct SeedConnection {
     enabled: Option<bool>,
     #[serde(default)]
     use_duration_polling: Option<bool>,
+    #[serde(default)]
+    auto_mapping: Option<bool>,
+    #[serde(default)]
+    credential_id: Option<Uuid>,
+    #[serde(default)]
+    request_chain_json: Option<Value>,
+    #[serde(default)]
+    response_charset: Option<String>,
+    #[serde(default)]
+    cookie_jar_enabled: Option<bool>,
+    #[serde(default)]
+    cookie_warmup_url: Option<String>,
+    #[serde(default)]
+    proxy_url: Option<String>,
+    #[serde(default)]
+    proxy_pool_id: Option<Uuid>,
 }
 
 #[tokio::main]
@@ -115,6 +136,9 @@ async fn upsert_station(
         active.name = Set(station.name.clone());
         active.callsign = Set(station.callsign);
         active.website_url = Set(station.website_url);
+        active.timezone = Set(station.timezone);
+        active.validity_rules_json = Set(station.validity_rules_json);
+        active.classification_json = Set(station.classification_json);
         active.updated_at = Set(now);
         active.update(db).await?.id
     } else {
@@ -124,6 +148,9 @@ async fn upsert_station(
             name: Set(station.name.clone()),
             callsign: Set(station.callsign),
             website_url: Set(station.website_url),
+            timezone: Set(station.timezone),
+            validity_rules_json: Set(station.validity_rules_json),
+            classification_json: Set(station.classification_json),
             created_at: Set(now),
             updated_at: Set(now),
         };
@@ -138,6 +165,9 @@ async fn upsert_mapping(
     mapping: SeedMapping,
     now: chrono::DateTime<chrono::FixedOffset>,
 ) -> Result<(String, Uuid), Box<dyn std::error::Error>> {
+    validate_mapping(&mapping.mapping_json)
+        .map_err(|e| format!("Mapping {}: {}", mapping.name, e))?;
+
     let existing = if let Some(id) = mapping.id {
         payload_mappings::Entity::find_by_id(id).one(db).await?
     } else {
@@ -147,24 +177,17 @@ async fn upsert_mapping(
             .await?
     };
 
-    let id = if let Some(existing) = existing {
-        let mut active: payload_mappings::ActiveModel = existing.into();
-        active.name = Set(mapping.name.clone());
-        active.description = Set(mapping.description);
-        active.mapping_json = Set(mapping.mapping_json);
-        active.updated_at = Set(now);
-        active.update(db).await?.id
-    } else {
-        let id = mapping.id.unwrap_or_else(Uuid::new_v4);
-        let active = payload_mappings::ActiveModel {
-            id: Set(id),
-            name: Set(mapping.name.clone()),
-            description: Set(mapping.description),
-            mapping_json: Set(mapping.mapping_json),
-            created_at: Set(now),
-            updated_at: Set(now),
-        };
-        active.insert(db).await?.id
+    let input = MappingInput {
+        name: mapping.name.clone(),
+        description: mapping.description,
+        mapping_json: mapping.mapping_json,
+    };
+    let id = match existing {
+        Some(existing) => update_mapping_with_version(db, existing, input, now).await?.id,
+        None => {
+            let id = mapping.id.unwrap_or_else(Uuid::new_v4);
+            insert_mapping_with_version(db, id, input, now).await?.id
+        }
     };
 
     Ok((mapping.name, id))
@@ -212,6 +235,8 @@ async fn upsert_connection(
     let poll_interval_seconds = connection.poll_interval_seconds.unwrap_or(30);
     let enabled = connection.enabled.unwrap_or(true);
     let use_duration_polling = connection.use_duration_polling.unwrap_or(false);
+    let auto_mapping = connection.auto_mapping.unwrap_or(false);
+    let cookie_jar_enabled = connection.cookie_jar_enabled.unwrap_or(false);
 
     if let Some(existing) = existing {
         let mut active: now_playing_connections::ActiveModel = existing.into();
@@ -224,6 +249,14 @@ async fn upsert_connection(
         active.headers_json = Set(headers_json);
         active.enabled = Set(enabled);
         active.use_duration_polling = Set(use_duration_polling);
+        active.auto_mapping = Set(auto_mapping);
+        active.credential_id = Set(connection.credential_id);
+        active.request_chain_json = Set(connection.request_chain_json);
+        active.response_charset = Set(connection.response_charset);
+        active.cookie_jar_enabled = Set(cookie_jar_enabled);
+        active.cookie_warmup_url = Set(connection.cookie_warmup_url);
+        active.proxy_url = Set(connection.proxy_url);
+        active.proxy_pool_id = Set(connection.proxy_pool_id);
         active.updated_at = Set(now);
         active.update(db).await?;
     } else {
@@ -239,6 +272,14 @@ async fn upsert_connection(
             headers_json: Set(headers_json),
             enabled: Set(enabled),
             use_duration_polling: Set(use_duration_polling),
+            auto_mapping: Set(auto_mapping),
+            credential_id: Set(connection.credential_id),
+            request_chain_json: Set(connection.request_chain_json),
+            response_charset: Set(connection.response_charset),
+            cookie_jar_enabled: Set(cookie_jar_enabled),
+            cookie_warmup_url: Set(connection.cookie_warmup_url),
+            proxy_url: Set(connection.proxy_url),
+            proxy_pool_id: Set(connection.proxy_pool_id),
             created_at: Set(now),
             updated_at: Set(now),
             ..Default::default()
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "credentials")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub name: String,
+    pub kind: String,
+    #[schema(value_type = Object)]
+    pub config_json: Json,
+    #[serde(skip_serializing)]
+    pub access_token: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub access_token_expires_at: Option<DateTimeWithTimeZone>,
+    pub last_error: Option<String>,
+    #[schema(value_type = String)]
+    pub created_at: DateTimeWithTimeZone,
+    #[schema(value_type = String)]
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::now_playing_connections::Entity")]
+    NowPlayingConnections,
+}
+
+impl Related<super::now_playing_connections::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NowPlayingConnections.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,3 +2,8 @@ pub mod stations;
 pub mod now_playing_connections;
 pub mod raw_now_playing_events;
 pub mod payload_mappings;
+pub mod credentials;
+pub mod proxy_pools;
+pub mod payload_mapping_versions;
+pub mod reextraction_jobs;
+pub mod rejected_events;
@@ -9,6 +9,9 @@ pub struct Model {
     pub id: Uuid,
     pub station_id: Uuid,
     pub payload_mapping_id: Option<Uuid>,
+    // Pick the mapping per payload from the library by fingerprint.
+    pub auto_mapping: bool,
+    pub credential_id: Option<Uuid>,
     pub name: String,
     pub connection_type: String,
     pub url: String,
@@ -25,6 +28,19 @@ pub struct Model {
     pub error_backoff_seconds: i32,
     pub last_status: Option<String>,
     pub last_error: Option<String>,
+    pub ingest_token: Option<String>,
+    pub ingest_hmac_secret: Option<String>,
+    pub hls_last_media_sequence: Option<i64>,
+    #[schema(value_type = Option<Object>)]
+    pub request_chain_json: Option<Json>,
+    pub response_charset: Option<String>,
+    pub cookie_jar_enabled: bool,
+    pub cookie_warmup_url: Option<String>,
+    #[serde(skip_serializing)]
+    #[schema(value_type = Option<Object>)]
+    pub cookies_json: Option<Json>,
+    pub proxy_url: Option<String>,
+    pub proxy_pool_id: Option<Uuid>,
     #[schema(value_type = String)]
     pub created_at: DateTimeWithTimeZone,
     #[schema(value_type = String)]
@@ -49,6 +65,22 @@ pub enum Relation {
         on_delete = "SetNull"
     )]
     PayloadMappings,
+    #[sea_orm(
+        belongs_to = "super::credentials::Entity",
+        from = "Column::CredentialId",
+        to = "super::credentials::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Credentials,
+    #[sea_orm(
+        belongs_to = "super::proxy_pools::Entity",
+        from = "Column::ProxyPoolId",
+        to = "super::proxy_pools::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    ProxyPools,
     #[sea_orm(has_many = "super::raw_now_playing_events::Entity")]
     RawNowPlayingEvents,
 }
@@ -65,6 +97,18 @@ impl Related<super::payload_mappings::Entity> for Entity {
     }
 }
 
+impl Related<super::credentials::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Credentials.def()
+    }
+}
+
+impl Related<super::proxy_pools::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProxyPools.def()
+    }
+}
+
 impl Related<super::raw_now_playing_events::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::RawNowPlayingEvents.def()
@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "payload_mapping_versions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub mapping_id: Uuid,
+    pub version: i32,
+    #[schema(value_type = Object)]
+    pub mapping_json: Json,
+    #[schema(value_type = String)]
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::payload_mappings::Entity",
+        from = "Column::MappingId",
+        to = "super::payload_mappings::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    PayloadMappings,
+    #[sea_orm(has_many = "super::raw_now_playing_events::Entity")]
+    RawNowPlayingEvents,
+}
+
+impl Related<super::payload_mappings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PayloadMappings.def()
+    }
+}
+
+impl Related<super::raw_now_playing_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RawNowPlayingEvents.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
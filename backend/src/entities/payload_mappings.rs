@@ -11,6 +11,8 @@ pub struct Model {
     pub description: Option<String>,
     #[schema(value_type = Object)]
     pub mapping_json: Json,
+    // Latest row in `payload_mapping_versions`; versions are never edited.
+    pub current_version_id: Option<Uuid>,
     #[schema(value_type = String)]
     pub created_at: DateTimeWithTimeZone,
     #[schema(value_type = String)]
@@ -21,6 +23,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::now_playing_connections::Entity")]
     NowPlayingConnections,
+    #[sea_orm(has_many = "super::payload_mapping_versions::Entity")]
+    PayloadMappingVersions,
 }
 
 impl Related<super::now_playing_connections::Entity> for Entity {
@@ -29,4 +33,10 @@ impl Related<super::now_playing_connections::Entity> for Entity {
     }
 }
 
+impl Related<super::payload_mapping_versions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PayloadMappingVersions.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
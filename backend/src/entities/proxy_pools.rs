@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "proxy_pools")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub name: String,
+    #[schema(value_type = Object)]
+    pub proxy_urls_json: Json,
+    #[schema(value_type = String)]
+    pub created_at: DateTimeWithTimeZone,
+    #[schema(value_type = String)]
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::now_playing_connections::Entity")]
+    NowPlayingConnections,
+}
+
+impl Related<super::now_playing_connections::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NowPlayingConnections.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
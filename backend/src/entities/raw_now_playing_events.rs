@@ -13,6 +13,7 @@ pub struct Model {
     pub observed_at: DateTimeWithTimeZone,
     #[schema(value_type = Option<String>)]
     pub reported_at: Option<DateTimeWithTimeZone>,
+    pub reported_at_error: Option<String>,
     pub reported_artist: Option<String>,
     pub reported_title: Option<String>,
     pub reported_album: Option<String>,
@@ -21,6 +22,24 @@ pub struct Model {
     pub payload_hash: String,
     pub http_status: Option<i32>,
     pub content_type: Option<String>,
+    pub mapping_version_id: Option<Uuid>,
+    pub reported_duration_seconds: Option<i64>,
+    #[schema(value_type = Option<String>)]
+    pub expected_end_at: Option<DateTimeWithTimeZone>,
+    pub isrc: Option<String>,
+    pub upc: Option<String>,
+    pub artwork_url: Option<String>,
+    pub label: Option<String>,
+    pub composer: Option<String>,
+    pub category: Option<String>,
+    pub item_type: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub extra_fields: Option<Json>,
+    pub validity_status: String,
+    #[schema(value_type = Option<Vec<String>>)]
+    pub validity_reasons: Option<Json>,
+    pub content_class: Option<String>,
+    pub content_class_reason: Option<String>,
     #[schema(value_type = String)]
     pub created_at: DateTimeWithTimeZone,
 }
@@ -43,6 +62,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Stations,
+    #[sea_orm(
+        belongs_to = "super::payload_mapping_versions::Entity",
+        from = "Column::MappingVersionId",
+        to = "super::payload_mapping_versions::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    PayloadMappingVersions,
 }
 
 impl Related<super::now_playing_connections::Entity> for Entity {
@@ -57,4 +84,10 @@ impl Related<super::stations::Entity> for Entity {
     }
 }
 
+impl Related<super::payload_mapping_versions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PayloadMappingVersions.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,50 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "reextraction_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub station_id: Uuid,
+    pub connection_id: Option<Uuid>,
+    #[schema(value_type = Option<String>)]
+    pub observed_from: Option<DateTimeWithTimeZone>,
+    #[schema(value_type = Option<String>)]
+    pub observed_to: Option<DateTimeWithTimeZone>,
+    pub dry_run: bool,
+    pub status: String,
+    pub total_events: i32,
+    pub processed_events: i32,
+    pub changed_events: i32,
+    #[schema(value_type = Option<Object>)]
+    pub report_json: Option<Json>,
+    pub error: Option<String>,
+    #[schema(value_type = String)]
+    pub created_at: DateTimeWithTimeZone,
+    #[schema(value_type = Option<String>)]
+    pub started_at: Option<DateTimeWithTimeZone>,
+    #[schema(value_type = Option<String>)]
+    pub finished_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::stations::Entity",
+        from = "Column::StationId",
+        to = "super::stations::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Stations,
+}
+
+impl Related<super::stations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Stations.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -10,6 +10,11 @@ pub struct Model {
     pub name: String,
     pub callsign: Option<String>,
     pub website_url: Option<String>,
+    pub timezone: Option<String>,
+    #[schema(value_type = Option<Vec<Object>>)]
+    pub validity_rules_json: Option<Json>,
+    #[schema(value_type = Option<Object>)]
+    pub classification_json: Option<Json>,
     #[schema(value_type = String)]
     pub created_at: DateTimeWithTimeZone,
     #[schema(value_type = String)]
@@ -38,6 +38,11 @@ pub fn default_headers_value(connection_type: &str) -> Value {
             "Cache-Control": "no-cache",
             "Pragma": "no-cache",
         }),
+        "hls_id3" => json!({
+            "Accept": "application/vnd.apple.mpegurl, application/x-mpegurl;q=0.9, */*;q=0.8",
+            "Cache-Control": "no-cache",
+            "Pragma": "no-cache",
+        }),
         "rss" => json!({
             "Accept": "application/rss+xml, application/xml;q=0.9, */*;q=0.8",
             "Cache-Control": "no-cache",
@@ -72,7 +77,7 @@ pub fn browser_headers_value(connection_type: &str, url: &str) -> Value {
 }
 
 pub fn should_default_headers(connection_type: &str) -> bool {
-    !matches!(connection_type.to_ascii_lowercase().as_str(), "ws_json")
+    !matches!(connection_type.to_ascii_lowercase().as_str(), "ws_json" | "push" | "tcp_listen" | "udp_listen")
 }
 
 fn is_empty_headers(headers: &Option<Value>) -> bool {
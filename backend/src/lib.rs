@@ -1,2 +1,5 @@
 pub mod entities;
 pub mod http_headers;
+pub mod mapping_spec;
+pub mod mapping_versions;
+pub mod script_mapping;
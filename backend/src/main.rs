@@ -8,7 +8,10 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 mod api;
 mod entities;
 mod http_headers;
+mod mapping_spec;
+mod mapping_versions;
 mod poller;
+mod script_mapping;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -0,0 +1,825 @@
+use chrono::format::{Item, StrftimeItems};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::LazyLock;
+use sxd_xpath::Factory;
+use crate::script_mapping::compile_script;
+use utoipa::{PartialSchema, ToSchema};
+
+// Typed view of `payload_mappings.mapping_json`. The poller still reads the
+// raw JSON, but every mapping written through the API or the seed binary must
+// parse as a `MappingSpec`, so a typo such as `artst_path` is rejected up front
+// instead of turning every poll into INVALID_EVENT.
+
+pub const MAPPING_SPEC_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MappingSpec {
+    /// Mapping format version; defaults to the current version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Payload shape this mapping expects; used to pick it for `auto_mapping` connections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub fingerprint: Option<Fingerprint>,
+    /// Path to the list of items; the first item is used unless `list_mode` is `all`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub list_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub list_mode: Option<ListMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub artist_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub title_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub album_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub reported_at_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub duration_path: Option<PathSpec>,
+    /// chrono format string(s) tried before the built-in `reported_at` formats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub reported_at_format: Option<FormatSpec>,
+    /// IANA timezone for naive `reported_at` values; defaults to the station's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reported_at_timezone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub isrc_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub upc_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub artwork_url_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub label_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub composer_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub category_path: Option<PathSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub item_type_path: Option<PathSpec>,
+    /// Rhai script run instead of the `<field>_path` entries; see `script_mapping`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    /// Custom keys stored in the event's `extra_fields`, each with its own path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub extra_fields: Option<BTreeMap<String, PathSpec>>,
+    /// Overrides the station's validity rules for events from this mapping.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub validity_rules: Option<Vec<ValidityRule>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub artist_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub title_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub album_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub reported_at_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub duration_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub isrc_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub upc_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub artwork_url_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub label_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub composer_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub category_transforms: Option<Vec<Transform>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub item_type_transforms: Option<Vec<Transform>>,
+    /// Transforms for `extra_fields` values, by extra field key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub extra_field_transforms: Option<BTreeMap<String, Vec<Transform>>>,
+    /// How `http_text` bodies are split into keys; guessed when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline)]
+    pub text_format: Option<TextFormat>,
+    /// Regex with named captures, used when `text_format` is `regex`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_delimiter: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_pair_separator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_line_separator: Option<String>,
+}
+
+/// A single path, or an ordered list of fallback paths.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum PathSpec {
+    Single(String),
+    Fallbacks(Vec<String>),
+}
+
+impl PathSpec {
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            PathSpec::Single(path) => vec![path.as_str()],
+            PathSpec::Fallbacks(paths) => paths.iter().map(|p| p.as_str()).collect(),
+        }
+    }
+}
+
+/// Structural checks a payload must pass before the mapping is applied. A
+/// payload that fails them is a `MAPPING_MISMATCH` rather than an event with
+/// empty fields.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Fingerprint {
+    /// Paths that must all resolve, in the same syntax as `<field>_path`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_paths: Vec<String>,
+    /// Local name of the XML root element.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_element: Option<String>,
+}
+
+/// A check every extracted event must pass. All the checks set on a rule apply;
+/// failing any of them triggers the rule's action.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ValidityRule {
+    /// Used in flags and rejection reasons.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Fields that must all have a value.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(inline)]
+    pub required: Vec<EventField>,
+    /// Fields of which at least one must have a value.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(inline)]
+    pub required_any: Vec<EventField>,
+    /// Text fields the length and pattern checks look at; defaults to artist and title.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(inline)]
+    pub fields: Vec<EventField>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Case-insensitive regexes; `{callsign}` and `{station_name}` stand for the station's own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block_patterns: Vec<String>,
+    #[serde(default)]
+    #[schema(inline)]
+    pub action: RuleAction,
+    /// Keep payloads rejected by this rule in `rejected_events` for auditing.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub retain: bool,
+}
+
+impl ValidityRule {
+    // What applies when neither the mapping nor the station sets rules.
+    pub fn default_rules() -> Vec<ValidityRule> {
+        vec![ValidityRule {
+            required: vec![EventField::Artist],
+            ..Default::default()
+        }]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Don't record an event; the connection reports INVALID_EVENT.
+    #[default]
+    Reject,
+    /// Record the event with `validity_status` FLAGGED.
+    StoreFlagged,
+    /// Record the event with `validity_status` NON_MUSIC.
+    StoreNonMusic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EventField {
+    Artist,
+    Title,
+    Album,
+    ReportedAt,
+    Duration,
+    Isrc,
+    Upc,
+    ArtworkUrl,
+    Label,
+    Composer,
+    Category,
+    ItemType,
+}
+
+impl EventField {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventField::Artist => "artist",
+            EventField::Title => "title",
+            EventField::Album => "album",
+            EventField::ReportedAt => "reported_at",
+            EventField::Duration => "duration",
+            EventField::Isrc => "isrc",
+            EventField::Upc => "upc",
+            EventField::ArtworkUrl => "artwork_url",
+            EventField::Label => "label",
+            EventField::Composer => "composer",
+            EventField::Category => "category",
+            EventField::ItemType => "item_type",
+        }
+    }
+
+    pub fn is_text(self) -> bool {
+        !matches!(self, EventField::ReportedAt | EventField::Duration)
+    }
+}
+
+// `{callsign}` and `{station_name}` are replaced by the escaped station values
+// before a block pattern is compiled.
+pub fn expand_block_pattern(pattern: &str, callsign: Option<&str>, station_name: Option<&str>) -> Option<String> {
+    let mut expanded = pattern.to_string();
+    for (placeholder, value) in [("{callsign}", callsign), ("{station_name}", station_name)] {
+        if expanded.contains(placeholder) {
+            let value = value.map(str::trim).filter(|v| !v.is_empty())?;
+            expanded = expanded.replace(placeholder, &regex::escape(value));
+        }
+    }
+    Some(format!("(?i){}", expanded))
+}
+
+// Station-level rules are stored outside `mapping_json`, so they're checked on
+// their own with the same messages.
+pub fn validate_validity_rules(rules_json: &Value) -> Result<Vec<ValidityRule>, MappingErrors> {
+    let rules: Vec<ValidityRule> = serde_json::from_value(rules_json.clone()).map_err(|e| MappingErrors {
+        errors: vec![format!("validity_rules: {}", e)],
+    })?;
+    let mut errors = Vec::new();
+    check_validity_rules(&rules, &mut errors);
+    if errors.is_empty() {
+        Ok(rules)
+    } else {
+        Err(MappingErrors { errors })
+    }
+}
+
+fn check_validity_rules(rules: &[ValidityRule], errors: &mut Vec<String>) {
+    for (index, rule) in rules.iter().enumerate() {
+        let key = format!("validity_rules[{}]", index);
+        let has_check = !rule.required.is_empty()
+            || !rule.required_any.is_empty()
+            || rule.min_length.is_some()
+            || rule.max_length.is_some()
+            || !rule.block_patterns.is_empty();
+        if !has_check {
+            errors.push(format!("{}: needs at least one check", key));
+        }
+        if let Some(field) = rule.fields.iter().find(|f| !f.is_text()) {
+            errors.push(format!("{}: `{}` isn't a text field", key, field.name()));
+        }
+        if let (Some(min), Some(max)) = (rule.min_length, rule.max_length)
+            && min > max
+        {
+            errors.push(format!("{}: min_length is greater than max_length", key));
+        }
+        for pattern in &rule.block_patterns {
+            let expanded = expand_block_pattern(pattern, Some("KXYZ"), Some("Station")).unwrap_or_default();
+            if let Err(e) = Regex::new(&expanded) {
+                errors.push(format!("{}: invalid pattern {:?}: {}", key, pattern, e));
+            }
+        }
+        if rule.retain && rule.action != RuleAction::Reject {
+            errors.push(format!("{}: retain only applies to reject rules", key));
+        }
+    }
+}
+
+/// A single format, or an ordered list of formats to try.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum FormatSpec {
+    Single(String),
+    List(Vec<String>),
+}
+
+impl FormatSpec {
+    pub fn formats(&self) -> Vec<&str> {
+        match self {
+            FormatSpec::Single(format) => vec![format.as_str()],
+            FormatSpec::List(formats) => formats.iter().map(|f| f.as_str()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListMode {
+    First,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TextFormat {
+    Regex,
+    Delimited,
+    KeyValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Transform {
+    RegexReplace {
+        pattern: String,
+        #[serde(default)]
+        replacement: String,
+    },
+    RegexCapture {
+        pattern: String,
+        #[serde(default)]
+        group: Option<String>,
+    },
+    Split {
+        delimiter: String,
+        #[serde(default)]
+        index: i64,
+    },
+    Trim,
+    Case {
+        #[schema(inline)]
+        mode: CaseMode,
+    },
+    HtmlUnescape,
+    NullIf {
+        values: Vec<String>,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseMode {
+    Lower,
+    Upper,
+    Title,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MappingErrors {
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for MappingErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid mapping: {}", self.errors.join("; "))
+    }
+}
+
+impl std::error::Error for MappingErrors {}
+
+// The top-level keys, read off the schema so they follow `MappingSpec`.
+static KNOWN_KEYS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    mapping_json_schema()["properties"]
+        .as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+});
+
+// Collects every problem instead of stopping at the first, so an editor can
+// show them all at once.
+pub fn validate_mapping(mapping_json: &Value) -> Result<MappingSpec, MappingErrors> {
+    let Some(obj) = mapping_json.as_object() else {
+        return Err(MappingErrors {
+            errors: vec!["mapping_json must be an object".to_string()],
+        });
+    };
+
+    let mut errors = Vec::new();
+    for (key, value) in obj {
+        if !KNOWN_KEYS.contains(key) {
+            errors.push(match closest_key(key) {
+                Some(known) => format!("unknown key `{}` (did you mean `{}`?)", key, known),
+                None => format!("unknown key `{}`", key),
+            });
+            continue;
+        }
+        // Deserializing keys one at a time ties type errors to their key.
+        let mut single = Map::new();
+        single.insert(key.clone(), value.clone());
+        if let Err(e) = serde_json::from_value::<MappingSpec>(Value::Object(single)) {
+            errors.push(format!("{}: {}", key, e));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(MappingErrors { errors });
+    }
+
+    let spec: MappingSpec = serde_json::from_value(mapping_json.clone()).map_err(|e| MappingErrors {
+        errors: vec![e.to_string()],
+    })?;
+    check_spec(&spec, &mut errors);
+    if errors.is_empty() {
+        Ok(spec)
+    } else {
+        Err(MappingErrors { errors })
+    }
+}
+
+fn check_spec(spec: &MappingSpec, errors: &mut Vec<String>) {
+    if let Some(version) = spec.version
+        && (version == 0 || version > MAPPING_SPEC_VERSION)
+    {
+        errors.push(format!(
+            "version: unsupported version {} (latest is {})",
+            version, MAPPING_SPEC_VERSION
+        ));
+    }
+
+    let mut field_paths: Vec<(String, Option<&PathSpec>)> = vec![
+        ("artist_path".to_string(), spec.artist_path.as_ref()),
+        ("title_path".to_string(), spec.title_path.as_ref()),
+        ("album_path".to_string(), spec.album_path.as_ref()),
+        ("reported_at_path".to_string(), spec.reported_at_path.as_ref()),
+        ("duration_path".to_string(), spec.duration_path.as_ref()),
+        ("isrc_path".to_string(), spec.isrc_path.as_ref()),
+        ("upc_path".to_string(), spec.upc_path.as_ref()),
+        ("artwork_url_path".to_string(), spec.artwork_url_path.as_ref()),
+        ("label_path".to_string(), spec.label_path.as_ref()),
+        ("composer_path".to_string(), spec.composer_path.as_ref()),
+        ("category_path".to_string(), spec.category_path.as_ref()),
+        ("item_type_path".to_string(), spec.item_type_path.as_ref()),
+    ];
+    field_paths.extend(
+        spec.extra_fields
+            .iter()
+            .flatten()
+            .map(|(name, path)| (format!("extra_fields.{}", name), Some(path))),
+    );
+    let mut field_keys = Vec::new();
+    for (key, path) in field_paths {
+        if let Some(path) = path {
+            field_keys.push(key.clone());
+            let paths = path.paths();
+            if paths.is_empty() {
+                errors.push(format!("{}: fallback list must not be empty", key));
+            } else if paths.iter().any(|p| p.trim().is_empty()) {
+                errors.push(format!("{}: paths must not be empty", key));
+            }
+            for xpath in paths.into_iter().filter(|p| is_xpath_path(p)) {
+                match Factory::new().build(xpath) {
+                    Ok(Some(_)) => {}
+                    Ok(None) => errors.push(format!("{}: invalid XPath {:?}: empty expression", key, xpath)),
+                    Err(e) => errors.push(format!("{}: invalid XPath {:?}: {}", key, xpath, e)),
+                }
+            }
+        }
+    }
+
+    if let Some(fingerprint) = &spec.fingerprint {
+        if fingerprint.required_paths.is_empty() && fingerprint.root_element.is_none() {
+            errors.push("fingerprint: needs required_paths or a root_element".to_string());
+        }
+        for path in &fingerprint.required_paths {
+            if path.trim().is_empty() {
+                errors.push("fingerprint.required_paths: paths must not be empty".to_string());
+            } else if is_xpath_path(path)
+                && let Err(e) = Factory::new().build(path)
+            {
+                errors.push(format!("fingerprint.required_paths: invalid XPath {:?}: {}", path, e));
+            }
+        }
+        if fingerprint.root_element.as_deref().is_some_and(|name| name.trim().is_empty()) {
+            errors.push("fingerprint.root_element: must not be empty".to_string());
+        }
+    }
+
+    if let Some(rules) = &spec.validity_rules {
+        check_validity_rules(rules, errors);
+    }
+
+    for format in spec.reported_at_format.iter().flat_map(|f| f.formats()) {
+        if format.trim().is_empty() {
+            errors.push("reported_at_format: formats must not be empty".to_string());
+        } else if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            errors.push(format!("reported_at_format: invalid format {:?}", format));
+        }
+    }
+    if let Some(timezone) = &spec.reported_at_timezone
+        && timezone.parse::<chrono_tz::Tz>().is_err()
+    {
+        errors.push(format!("reported_at_timezone: unknown timezone {:?}", timezone));
+    }
+
+    if let Some(script) = &spec.script {
+        if script.trim().is_empty() {
+            errors.push("script: must not be empty".to_string());
+        } else if let Err(e) = compile_script(script) {
+            errors.push(format!("script: {}", e));
+        }
+        // The script decides which item and fields to use, so path-based keys
+        // would silently be ignored.
+        let conflicting = field_keys
+            .iter()
+            .map(String::as_str)
+            .chain(spec.list_path.as_ref().map(|_| "list_path"))
+            .chain(spec.list_mode.as_ref().map(|_| "list_mode"));
+        for key in conflicting {
+            errors.push(format!("{}: can't be combined with `script`", key));
+        }
+    }
+
+    let has_list_path = spec
+        .list_path
+        .as_ref()
+        .map(|p| p.paths().iter().any(|p| !p.trim().is_empty()))
+        .unwrap_or(false);
+    if spec.list_mode == Some(ListMode::All) && !has_list_path {
+        errors.push("list_mode: `all` requires a list_path".to_string());
+    }
+
+    let mut transforms: Vec<(String, Option<&Vec<Transform>>)> = [
+        ("artist_transforms", &spec.artist_transforms),
+        ("title_transforms", &spec.title_transforms),
+        ("album_transforms", &spec.album_transforms),
+        ("reported_at_transforms", &spec.reported_at_transforms),
+        ("duration_transforms", &spec.duration_transforms),
+        ("isrc_transforms", &spec.isrc_transforms),
+        ("upc_transforms", &spec.upc_transforms),
+        ("artwork_url_transforms", &spec.artwork_url_transforms),
+        ("label_transforms", &spec.label_transforms),
+        ("composer_transforms", &spec.composer_transforms),
+        ("category_transforms", &spec.category_transforms),
+        ("item_type_transforms", &spec.item_type_transforms),
+    ]
+    .into_iter()
+    .map(|(key, list)| (key.to_string(), list.as_ref()))
+    .collect();
+    for (name, list) in spec.extra_field_transforms.iter().flatten() {
+        let key = format!("extra_field_transforms.{}", name);
+        if !spec.extra_fields.as_ref().is_some_and(|extras| extras.contains_key(name)) {
+            errors.push(format!("{}: `{}` is not one of the extra_fields", key, name));
+        }
+        transforms.push((key, Some(list)));
+    }
+    for (key, list) in transforms {
+        for (index, transform) in list.into_iter().flatten().enumerate() {
+            let problem = match transform {
+                Transform::RegexReplace { pattern, .. } | Transform::RegexCapture { pattern, .. } => {
+                    Regex::new(pattern).err().map(|e| format!("invalid pattern: {}", e))
+                }
+                Transform::Split { delimiter, .. } if delimiter.is_empty() => {
+                    Some("split delimiter must not be empty".to_string())
+                }
+                _ => None,
+            };
+            if let Some(problem) = problem {
+                errors.push(format!("{}[{}]: {}", key, index, problem));
+            }
+        }
+    }
+
+    match (&spec.text_format, &spec.text_pattern) {
+        (Some(TextFormat::Regex), None) => {
+            errors.push("text_pattern: required when text_format is `regex`".to_string());
+        }
+        (_, Some(pattern)) => {
+            if let Err(e) = Regex::new(pattern) {
+                errors.push(format!("text_pattern: invalid pattern: {}", e));
+            }
+        }
+        _ => {}
+    }
+}
+
+// Paths starting with `/`, `./`, `(` or a function call (`string(//title)`)
+// are XPath 1.0 expressions, used for XML payloads; anything else is a dotted
+// or JSONPath-style path.
+pub fn is_xpath_path(path: &str) -> bool {
+    let path = path.trim_start();
+    if path.starts_with('/') || path.starts_with("./") || path.starts_with("../") || path.starts_with('(') {
+        return true;
+    }
+    let name_len = path
+        .find(|c: char| !(c.is_ascii_alphabetic() || c == '-'))
+        .unwrap_or(path.len());
+    name_len > 0 && path[name_len..].starts_with('(')
+}
+
+fn closest_key(key: &str) -> Option<&'static str> {
+    KNOWN_KEYS
+        .iter()
+        .map(|known| (known.as_str(), edit_distance(key, known)))
+        .filter(|(_, distance)| *distance <= 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(known, _)| known)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+// Standalone JSON Schema (all definitions inlined) for the frontend editor.
+pub fn mapping_json_schema() -> Value {
+    let mut schema = serde_json::to_value(MappingSpec::schema()).unwrap_or_else(|_| Value::Object(Map::new()));
+    if let Some(obj) = schema.as_object_mut() {
+        obj.insert(
+            "$schema".to_string(),
+            Value::String("https://json-schema.org/draft/2020-12/schema".to_string()),
+        );
+        obj.insert("title".to_string(), Value::String("PayloadMapping".to_string()));
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_known_mappings() {
+        let spec = validate_mapping(&serde_json::json!({
+            "version": 1,
+            "list_path": "",
+            "list_mode": "first",
+            "artist_path": ["artist", "performer"],
+            "title_path": "title",
+            "isrc_path": "codes.isrc",
+            "extra_fields": { "cart": ["cart", "cut.cart"] },
+            "title_transforms": [{ "op": "trim" }, { "op": "case", "mode": "title" }],
+            "text_format": "delimited",
+            "text_fields": ["artist", "title"]
+        }))
+        .unwrap();
+        assert_eq!(spec.artist_path.unwrap().paths(), vec!["artist", "performer"]);
+        assert_eq!(spec.title_transforms.unwrap().len(), 2);
+        assert_eq!(spec.extra_fields.unwrap()["cart"].paths(), vec!["cart", "cut.cart"]);
+
+        let err = validate_mapping(&serde_json::json!({ "extra_fields": { "cart": "" } })).unwrap_err();
+        assert_eq!(err.errors, vec!["extra_fields.cart: paths must not be empty".to_string()]);
+    }
+
+    #[test]
+    fn rejects_invalid_xpath() {
+        assert!(validate_mapping(&serde_json::json!({ "artist_path": "//Current/Song/@Artist" })).is_ok());
+        let err = validate_mapping(&serde_json::json!({ "title_path": ["title", "//item[1/title"] })).unwrap_err();
+        assert!(err.errors[0].starts_with("title_path: invalid XPath \"//item[1/title\""));
+        assert!(is_xpath_path("string(//title)"));
+        assert!(!is_xpath_path("items[?(@.type=='song')].title"));
+    }
+
+    #[test]
+    fn validates_timestamp_settings() {
+        assert!(validate_mapping(&serde_json::json!({
+            "reported_at_format": ["%m/%d/%Y %I:%M %p", "%Y-%m-%d %H:%M:%S"],
+            "reported_at_timezone": "America/Chicago"
+        }))
+        .is_ok());
+        let err = validate_mapping(&serde_json::json!({
+            "reported_at_format": "%Y-%m-%d %Q",
+            "reported_at_timezone": "Central"
+        }))
+        .unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+    }
+
+    #[test]
+    fn rejects_script_with_path_keys() {
+        assert!(validate_mapping(&serde_json::json!({ "script": "#{ title: payload.song }" })).is_ok());
+        let err = validate_mapping(&serde_json::json!({
+            "script": "#{ title: ",
+            "artist_path": "artist"
+        }))
+        .unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+        assert!(err.errors[1].starts_with("artist_path: can't be combined"));
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_key() {
+        let err = validate_mapping(&serde_json::json!({
+            "artst_path": "artist",
+            "title_path": 5,
+            "list_mode": "some",
+            "version": 1
+        }))
+        .unwrap_err();
+        assert_eq!(err.errors.len(), 3);
+        assert!(err.errors.iter().any(|e| e == "unknown key `artst_path` (did you mean `artist_path`?)"));
+        assert!(err.errors.iter().any(|e| e.starts_with("title_path: ")));
+        assert!(err.errors.iter().any(|e| e.starts_with("list_mode: unknown variant `some`")));
+
+        let err = validate_mapping(&serde_json::json!({
+            "version": 9,
+            "list_mode": "all",
+            "title_transforms": [{ "op": "regex_replace", "pattern": "(" }],
+            "text_format": "regex"
+        }))
+        .unwrap_err();
+        assert_eq!(err.errors.len(), 4);
+        assert!(err.errors.iter().any(|e| e.starts_with("title_transforms[0]: invalid pattern")));
+
+        let err = validate_mapping(&serde_json::json!({
+            "isrc_transforms": [{ "op": "split", "delimiter": "" }],
+            "extra_fields": { "cart": "cut.cart" },
+            "extra_field_transforms": { "cart": [{ "op": "trim" }], "slot": [{ "op": "trim" }] }
+        }))
+        .unwrap_err();
+        assert_eq!(
+            err.errors,
+            vec![
+                "extra_field_transforms.slot: `slot` is not one of the extra_fields".to_string(),
+                "isrc_transforms[0]: split delimiter must not be empty".to_string(),
+            ]
+        );
+
+        assert!(validate_mapping(&serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn knows_every_spec_field() {
+        assert!(KNOWN_KEYS.iter().any(|key| key == "artist_path"));
+        assert!(KNOWN_KEYS.iter().any(|key| key == "text_line_separator"));
+        let every_key: Map<String, Value> = KNOWN_KEYS.iter().map(|key| (key.clone(), Value::Null)).collect();
+        assert_eq!(validate_mapping(&Value::Object(every_key)), Ok(MappingSpec::default()));
+    }
+
+    #[test]
+    fn validates_validity_rules() {
+        assert!(validate_mapping(&serde_json::json!({
+            "artist_path": "artist",
+            "validity_rules": [{ "block_patterns": ["^{callsign} ?fm$"], "action": "store_non_music" }]
+        }))
+        .is_ok());
+
+        let err = validate_validity_rules(&serde_json::json!([
+            { "action": "store_flagged" },
+            { "fields": ["duration"], "min_length": 5, "max_length": 2, "block_patterns": ["("] },
+            { "required": ["artist"], "action": "store_flagged", "retain": true }
+        ]))
+        .unwrap_err();
+        assert_eq!(err.errors.len(), 5);
+        assert!(validate_validity_rules(&serde_json::json!([{ "required": ["artst"] }])).is_err());
+    }
+
+    #[test]
+    fn publishes_self_contained_schema() {
+        let schema = mapping_json_schema();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["additionalProperties"], false);
+        assert!(schema["properties"]["artist_transforms"].is_object());
+        assert!(!schema.to_string().contains("$ref"));
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, FixedOffset};
+use sea_orm::{prelude::*, QueryOrder, Set, TransactionTrait};
+use crate::entities::{payload_mapping_versions, payload_mappings};
+
+// Every change to a mapping's `mapping_json` is kept as an immutable row in
+// `payload_mapping_versions`; `payload_mappings.current_version_id` points at
+// the latest one and events record the version they were extracted with.
+
+pub struct MappingInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub mapping_json: Json,
+}
+
+pub async fn insert_mapping_with_version(
+    db: &DatabaseConnection,
+    id: Uuid,
+    input: MappingInput,
+    now: DateTime<FixedOffset>,
+) -> Result<payload_mappings::Model, DbErr> {
+    let txn = db.begin().await?;
+    let mapping = payload_mappings::ActiveModel {
+        id: Set(id),
+        name: Set(input.name),
+        description: Set(input.description),
+        mapping_json: Set(input.mapping_json.clone()),
+        current_version_id: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(&txn)
+    .await?;
+
+    let version = record_version(&txn, mapping.id, input.mapping_json, now).await?;
+    let mut mapping: payload_mappings::ActiveModel = mapping.into();
+    mapping.current_version_id = Set(Some(version.id));
+    let mapping = mapping.update(&txn).await?;
+    txn.commit().await?;
+    Ok(mapping)
+}
+
+// Name/description edits don't create a version; only `mapping_json` changes do.
+pub async fn update_mapping_with_version(
+    db: &DatabaseConnection,
+    existing: payload_mappings::Model,
+    input: MappingInput,
+    now: DateTime<FixedOffset>,
+) -> Result<payload_mappings::Model, DbErr> {
+    let txn = db.begin().await?;
+    let needs_version = needs_new_version(existing.current_version_id, &existing.mapping_json, &input.mapping_json);
+    let mapping_id = existing.id;
+
+    let mut mapping: payload_mappings::ActiveModel = existing.into();
+    mapping.name = Set(input.name);
+    mapping.description = Set(input.description);
+    mapping.updated_at = Set(now);
+    if needs_version {
+        let version = record_version(&txn, mapping_id, input.mapping_json.clone(), now).await?;
+        mapping.current_version_id = Set(Some(version.id));
+    }
+    mapping.mapping_json = Set(input.mapping_json);
+    let mapping = mapping.update(&txn).await?;
+    txn.commit().await?;
+    Ok(mapping)
+}
+
+fn needs_new_version(current_version_id: Option<Uuid>, stored: &Json, incoming: &Json) -> bool {
+    current_version_id.is_none() || stored != incoming
+}
+
+fn next_version_number(latest: Option<i32>) -> i32 {
+    latest.map(|v| v + 1).unwrap_or(1)
+}
+
+async fn record_version<C: ConnectionTrait>(
+    db: &C,
+    mapping_id: Uuid,
+    mapping_json: Json,
+    now: DateTime<FixedOffset>,
+) -> Result<payload_mapping_versions::Model, DbErr> {
+    let latest = payload_mapping_versions::Entity::find()
+        .filter(payload_mapping_versions::Column::MappingId.eq(mapping_id))
+        .order_by_desc(payload_mapping_versions::Column::Version)
+        .one(db)
+        .await?;
+
+    payload_mapping_versions::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        mapping_id: Set(mapping_id),
+        version: Set(next_version_number(latest.map(|v| v.version))),
+        mapping_json: Set(mapping_json),
+        created_at: Set(now),
+    }
+    .insert(db)
+    .await
+}
+
+pub async fn list_versions(
+    db: &DatabaseConnection,
+    mapping_id: Uuid,
+) -> Result<Vec<payload_mapping_versions::Model>, DbErr> {
+    payload_mapping_versions::Entity::find()
+        .filter(payload_mapping_versions::Column::MappingId.eq(mapping_id))
+        .order_by_desc(payload_mapping_versions::Column::Version)
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_only_mapping_json_changes() {
+        let version = Some(Uuid::new_v4());
+        let stored = serde_json::json!({ "artist_path": "artist" });
+        assert!(!needs_new_version(version, &stored, &stored.clone()));
+        assert!(needs_new_version(version, &stored, &serde_json::json!({ "artist_path": "performer" })));
+        // Mappings from before versioning get their first version on any save.
+        assert!(needs_new_version(None, &stored, &stored.clone()));
+    }
+
+    #[test]
+    fn numbers_versions_from_one() {
+        assert_eq!(next_version_number(None), 1);
+        assert_eq!(next_version_number(Some(1)), 2);
+        assert_eq!(next_version_number(Some(41)), 42);
+    }
+}
@@ -0,0 +1,368 @@
+use sea_orm::{prelude::*, Set};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use crate::entities::{credentials, now_playing_connections};
+use crate::http_headers::headers_value_to_map;
+use super::utils::get_path;
+
+// Credentials are shared between connections. Supported kinds and their
+// `config_json` keys:
+// - `api_key`: `value`, plus `header` (default `X-API-Key`) and optional
+//   `prefix`, or `query_param` to send it in the URL instead
+// - `oauth2_client_credentials`: `token_url`, `client_id`, `client_secret`,
+//   optional `scope`, `audience` and `auth_method` (`basic` or `body`)
+// - `login`: `url`, optional `method`, `headers`, `body`, `body_format`
+//   (`json` or `form`), `token_path`, `expires_in_path`, `expires_in_seconds`,
+//   `header` and `prefix`
+// Tokens are cached on the credential row and refreshed shortly before they
+// expire, or immediately when the origin answers 401.
+
+const DEFAULT_TOKEN_LIFETIME_SECONDS: i64 = 3600;
+const REFRESH_MARGIN_SECONDS: i64 = 60;
+
+#[derive(Debug)]
+pub struct AuthError(pub String);
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Authentication failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+#[derive(Debug, Default, Clone)]
+pub struct AuthParams {
+    pub headers: HashMap<String, String>,
+    pub query: Vec<(String, String)>,
+}
+
+impl AuthParams {
+    pub fn apply(&self, url: &str, headers: &HashMap<String, String>) -> (String, HashMap<String, String>) {
+        let mut merged = headers.clone();
+        merged.extend(self.headers.clone());
+
+        if self.query.is_empty() {
+            return (url.to_string(), merged);
+        }
+        match reqwest::Url::parse(url) {
+            Ok(mut parsed) => {
+                parsed.query_pairs_mut().extend_pairs(self.query.iter());
+                (parsed.to_string(), merged)
+            }
+            Err(_) => (url.to_string(), merged),
+        }
+    }
+}
+
+pub async fn load_credential(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+) -> Result<Option<credentials::Model>, DbErr> {
+    match conn.credential_id {
+        Some(id) => credentials::Entity::find_by_id(id).one(db).await,
+        None => Ok(None),
+    }
+}
+
+pub fn uses_token(credential: &credentials::Model) -> bool {
+    matches!(
+        credential.kind.to_ascii_lowercase().as_str(),
+        "oauth2_client_credentials" | "login"
+    )
+}
+
+pub async fn resolve_auth(
+    db: &DatabaseConnection,
+    client: &reqwest::Client,
+    credential: &credentials::Model,
+    force_refresh: bool,
+) -> Result<AuthParams, Box<dyn std::error::Error + Send + Sync>> {
+    let config = &credential.config_json;
+    let kind = credential.kind.to_ascii_lowercase();
+
+    if kind == "api_key" {
+        let value = config_str(config, "value").ok_or_else(|| AuthError("api_key credential has no value".into()))?;
+        let mut params = AuthParams::default();
+        if let Some(param) = config_str(config, "query_param") {
+            params.query.push((param.to_string(), value.to_string()));
+        } else {
+            let header = config_str(config, "header").unwrap_or("X-API-Key");
+            let prefix = config_str(config, "prefix").unwrap_or("");
+            params.headers.insert(header.to_string(), format!("{}{}", prefix, value));
+        }
+        return Ok(params);
+    }
+
+    if !uses_token(credential) {
+        return Err(AuthError(format!("Unsupported credential kind: {}", credential.kind)).into());
+    }
+
+    let now = Utc::now().fixed_offset();
+    let token = match cached_token(credential, now).filter(|_| !force_refresh) {
+        Some(token) => token.to_string(),
+        None => refresh_token(db, client, credential, now).await?,
+    };
+
+    let default_header = "Authorization";
+    let default_prefix = "Bearer ";
+    let (header, prefix) = if kind == "login" {
+        (
+            config_str(config, "header").unwrap_or(default_header),
+            config_str(config, "prefix").unwrap_or(default_prefix),
+        )
+    } else {
+        (default_header, default_prefix)
+    };
+
+    let mut params = AuthParams::default();
+    params.headers.insert(header.to_string(), format!("{}{}", prefix, token));
+    Ok(params)
+}
+
+// The stored token, unless it expires within the refresh margin.
+fn cached_token(credential: &credentials::Model, now: DateTime<FixedOffset>) -> Option<&str> {
+    let expires_at = credential.access_token_expires_at?;
+    if expires_at <= now + chrono::Duration::seconds(REFRESH_MARGIN_SECONDS) {
+        return None;
+    }
+    credential.access_token.as_deref()
+}
+
+// A 401 from the origin means the token was revoked or expired early; token
+// credentials get one refresh and retry.
+pub fn should_refresh_after(status: reqwest::StatusCode, credential: Option<&credentials::Model>) -> bool {
+    status == reqwest::StatusCode::UNAUTHORIZED && credential.is_some_and(uses_token)
+}
+
+pub async fn refresh_token(
+    db: &DatabaseConnection,
+    client: &reqwest::Client,
+    credential: &credentials::Model,
+    now: DateTime<FixedOffset>,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let result = request_token(client, credential).await;
+
+    let mut active: credentials::ActiveModel = credential.clone().into();
+    active.updated_at = Set(now);
+    match &result {
+        Ok((token, expires_in)) => {
+            active.access_token = Set(Some(token.clone()));
+            active.access_token_expires_at = Set(Some(now + chrono::Duration::seconds(*expires_in)));
+            active.last_error = Set(None);
+        }
+        Err(e) => {
+            active.access_token = Set(None);
+            active.access_token_expires_at = Set(None);
+            active.last_error = Set(Some(e.to_string()));
+        }
+    }
+    active.update(db).await?;
+
+    result.map(|(token, _)| token).map_err(|e| e.into())
+}
+
+async fn request_token(
+    client: &reqwest::Client,
+    credential: &credentials::Model,
+) -> Result<(String, i64), AuthError> {
+    let config = &credential.config_json;
+
+    let (token_path, expires_path, response) = if credential.kind.eq_ignore_ascii_case("login") {
+        let url = config_str(config, "url").ok_or_else(|| AuthError("login credential has no url".into()))?;
+        let method = config_str(config, "method")
+            .unwrap_or("POST")
+            .parse::<reqwest::Method>()
+            .map_err(|e| AuthError(e.to_string()))?;
+        let mut rb = client.request(method, url);
+        if let Some(headers) = config.get("headers") {
+            for (k, v) in headers_value_to_map(headers) {
+                rb = rb.header(k, v);
+            }
+        }
+        if let Some(body) = config.get("body") {
+            rb = match (config_str(config, "body_format"), body) {
+                (Some("form"), Value::Object(_)) => rb.form(&headers_value_to_map(body)),
+                (_, Value::String(text)) => rb.body(text.clone()),
+                _ => rb.json(body),
+            };
+        }
+        (
+            config_str(config, "token_path").unwrap_or("access_token"),
+            config_str(config, "expires_in_path").unwrap_or("expires_in"),
+            rb.send().await,
+        )
+    } else {
+        let token_url = config_str(config, "token_url")
+            .ok_or_else(|| AuthError("oauth2 credential has no token_url".into()))?;
+        let client_id = config_str(config, "client_id").unwrap_or("");
+        let client_secret = config_str(config, "client_secret").unwrap_or("");
+
+        let mut form: Vec<(&str, &str)> = vec![("grant_type", "client_credentials")];
+        for key in ["scope", "audience"] {
+            if let Some(value) = config_str(config, key) {
+                form.push((key, value));
+            }
+        }
+        let mut rb = client.post(token_url).header("Accept", "application/json");
+        if config_str(config, "auth_method") == Some("body") {
+            form.push(("client_id", client_id));
+            form.push(("client_secret", client_secret));
+        } else {
+            rb = rb.basic_auth(client_id, Some(client_secret));
+        }
+        ("access_token", "expires_in", rb.form(&form).send().await)
+    };
+
+    let response = response.map_err(|e| AuthError(e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(AuthError(format!("token endpoint returned {}", status)));
+    }
+    let body: Value = response.json().await.map_err(|e| AuthError(e.to_string()))?;
+
+    let token = get_path(&body, token_path)
+        .and_then(|v| v.as_str())
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| AuthError(format!("no token at {}", token_path)))?
+        .to_string();
+    let expires_in = get_path(&body, expires_path)
+        .and_then(|v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .or_else(|| config.get("expires_in_seconds").and_then(|v| v.as_i64()))
+        .filter(|s| *s > 0)
+        .unwrap_or(DEFAULT_TOKEN_LIFETIME_SECONDS);
+
+    Ok((token, expires_in))
+}
+
+fn config_str<'a>(config: &'a Value, key: &str) -> Option<&'a str> {
+    config.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_credential;
+    use axum::extract::Form;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::{Json, Router};
+
+    async fn token_server() -> String {
+        // Basic auth only: client credentials in the body are refused.
+        async fn basic(headers: HeaderMap, Form(form): Form<HashMap<String, String>>) -> Json<Value> {
+            let auth = headers.get("authorization").and_then(|v| v.to_str().ok()).unwrap_or("");
+            let ok = auth == "Basic aWQ6c2VjcmV0" && form.get("grant_type").map(String::as_str) == Some("client_credentials");
+            Json(if ok && !form.contains_key("client_secret") {
+                serde_json::json!({ "access_token": "basic-token", "expires_in": "120" })
+            } else {
+                serde_json::json!({ "error": "invalid_client" })
+            })
+        }
+        async fn body(Form(form): Form<HashMap<String, String>>) -> Json<Value> {
+            let ok = form.get("client_id").map(String::as_str) == Some("id")
+                && form.get("client_secret").map(String::as_str) == Some("secret")
+                && form.get("scope").map(String::as_str) == Some("np:read");
+            Json(if ok {
+                serde_json::json!({ "access_token": "body-token", "expires_in": 90 })
+            } else {
+                serde_json::json!({})
+            })
+        }
+        async fn login(Json(body): Json<Value>) -> Json<Value> {
+            Json(serde_json::json!({ "data": { "token": format!("login-{}", body["user"].as_str().unwrap_or("")) } }))
+        }
+
+        let app = Router::new()
+            .route("/basic", post(basic))
+            .route("/body", post(body))
+            .route("/login", post(login))
+            .route("/denied", post(|| async { (axum::http::StatusCode::FORBIDDEN, "no") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn requests_tokens_from_local_endpoint() {
+        let base = token_server().await;
+        let client = reqwest::Client::new();
+        let request = |kind: &str, config: Value| {
+            let credential = test_credential(kind, config);
+            let client = client.clone();
+            async move { request_token(&client, &credential).await }
+        };
+
+        let basic = serde_json::json!({ "token_url": format!("{}/basic", base), "client_id": "id", "client_secret": "secret" });
+        assert_eq!(request("oauth2_client_credentials", basic).await.unwrap(), ("basic-token".to_string(), 120));
+
+        let body = serde_json::json!({
+            "token_url": format!("{}/body", base),
+            "client_id": "id",
+            "client_secret": "secret",
+            "scope": "np:read",
+            "auth_method": "body"
+        });
+        assert_eq!(request("oauth2_client_credentials", body).await.unwrap(), ("body-token".to_string(), 90));
+
+        // No expiry in the response: the configured lifetime, then the default.
+        let login = serde_json::json!({
+            "url": format!("{}/login", base),
+            "body": { "user": "dj" },
+            "token_path": "data.token",
+            "expires_in_path": "data.ttl",
+            "expires_in_seconds": 600
+        });
+        assert_eq!(request("login", login.clone()).await.unwrap(), ("login-dj".to_string(), 600));
+        let mut login_default = login;
+        login_default.as_object_mut().unwrap().remove("expires_in_seconds");
+        assert_eq!(
+            request("login", login_default).await.unwrap(),
+            ("login-dj".to_string(), DEFAULT_TOKEN_LIFETIME_SECONDS)
+        );
+
+        let wrong_path = serde_json::json!({ "url": format!("{}/login", base), "body": { "user": "dj" } });
+        assert_eq!(request("login", wrong_path).await.unwrap_err().0, "no token at access_token");
+        let denied = serde_json::json!({ "token_url": format!("{}/denied", base) });
+        assert!(request("oauth2_client_credentials", denied).await.unwrap_err().0.contains("403"));
+    }
+
+    #[test]
+    fn cached_tokens_are_refreshed_within_the_margin() {
+        let now = Utc::now().fixed_offset();
+        let mut credential = test_credential("oauth2_client_credentials", serde_json::json!({}));
+        credential.access_token = Some("cached".to_string());
+        assert_eq!(cached_token(&credential, now), None);
+
+        credential.access_token_expires_at = Some(now + chrono::Duration::seconds(REFRESH_MARGIN_SECONDS + 30));
+        assert_eq!(cached_token(&credential, now), Some("cached"));
+        credential.access_token_expires_at = Some(now + chrono::Duration::seconds(REFRESH_MARGIN_SECONDS));
+        assert_eq!(cached_token(&credential, now), None);
+        credential.access_token_expires_at = Some(now - chrono::Duration::seconds(5));
+        assert_eq!(cached_token(&credential, now), None);
+    }
+
+    #[test]
+    fn only_token_credentials_refresh_after_401() {
+        let oauth = test_credential("oauth2_client_credentials", serde_json::json!({}));
+        let api_key = test_credential("api_key", serde_json::json!({ "value": "k" }));
+        assert!(should_refresh_after(reqwest::StatusCode::UNAUTHORIZED, Some(&oauth)));
+        assert!(!should_refresh_after(reqwest::StatusCode::FORBIDDEN, Some(&oauth)));
+        assert!(!should_refresh_after(reqwest::StatusCode::UNAUTHORIZED, Some(&api_key)));
+        assert!(!should_refresh_after(reqwest::StatusCode::UNAUTHORIZED, None));
+    }
+
+    #[test]
+    fn api_key_query_params_are_appended_to_url() {
+        let params = AuthParams {
+            headers: HashMap::from([("X-API-Key".to_string(), "k".to_string())]),
+            query: vec![("api_key".to_string(), "a b".to_string())],
+        };
+        let (url, headers) = params.apply("https://example.com/np?station=1", &HashMap::new());
+        assert_eq!(url, "https://example.com/np?station=1&api_key=a+b");
+        assert_eq!(headers.get("X-API-Key").map(String::as_str), Some("k"));
+    }
+}
@@ -0,0 +1,419 @@
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use crate::entities::now_playing_connections;
+use crate::http_headers::headers_value_to_map;
+use super::auth::AuthParams;
+use super::utils::get_path;
+
+// Some players need a lookup call (current stream, session id, ...) before the
+// now-playing endpoint can be requested. `request_chain_json` holds an ordered
+// list of such steps, run before the connection's own `url`:
+//
+// { "url": "https://host/api/streams?station=1", "method": "GET",
+//   "headers": {}, "body": null, "cache_ttl_seconds": 300,
+//   "extract": { "stream_id": "data.0.id",
+//                "session": { "regex": "sid=(\\w+)" },
+//                "token": { "header": "X-Session" } } }
+//
+// Extracted values can be used as `{{name}}` in later steps and in the
+// connection's url and headers. Only the final response is stored.
+
+#[derive(Debug)]
+pub struct ChainError(pub String);
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request chain failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+#[derive(Debug, Default)]
+pub struct ChainOutcome {
+    pub values: HashMap<String, String>,
+    pub from_cache: bool,
+}
+
+impl ChainOutcome {
+    pub fn render_request(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+    ) -> (String, HashMap<String, String>) {
+        let headers = headers
+            .iter()
+            .map(|(k, v)| (k.clone(), render_template(v, &self.values)))
+            .collect();
+        (render_template(url, &self.values), headers)
+    }
+}
+
+struct CachedStep {
+    values: HashMap<String, String>,
+    expires_at: Instant,
+}
+
+static STEP_CACHE: LazyLock<Mutex<HashMap<(Uuid, usize), CachedStep>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([A-Za-z0-9_.\-]+)\s*\}\}").unwrap());
+
+pub fn validate_request_chain(chain: &Value) -> Result<(), String> {
+    let steps = chain.as_array().ok_or("request_chain_json must be an array of steps")?;
+    for (index, step) in steps.iter().enumerate() {
+        let url = step.get("url").and_then(|v| v.as_str()).unwrap_or("");
+        if url.is_empty() {
+            return Err(format!("step {} has no url", index + 1));
+        }
+        if let Some(extract) = step.get("extract")
+            && !extract.is_object()
+        {
+            return Err(format!("step {} extract must be an object", index + 1));
+        }
+    }
+    Ok(())
+}
+
+pub async fn run_request_chain(
+    client: &reqwest::Client,
+    conn: &now_playing_connections::Model,
+    auth: &AuthParams,
+) -> Result<ChainOutcome, ChainError> {
+    let mut outcome = ChainOutcome::default();
+    let Some(steps) = conn.request_chain_json.as_ref().and_then(|v| v.as_array()) else {
+        return Ok(outcome);
+    };
+
+    // Once a step runs live, later cached values may be derived from its stale
+    // output, so every following step is re-run as well.
+    let mut refreshed = false;
+    for (index, step) in steps.iter().enumerate() {
+        let ttl = step.get("cache_ttl_seconds").and_then(|v| v.as_u64()).unwrap_or(0);
+        if ttl > 0
+            && !refreshed
+            && let Some(values) = cached_values(conn.id, index)
+        {
+            outcome.values.extend(values);
+            outcome.from_cache = true;
+            continue;
+        }
+
+        let values = run_step(client, step, &outcome.values, auth)
+            .await
+            .map_err(|e| ChainError(format!("step {}: {}", index + 1, e)))?;
+        if !refreshed {
+            clear_steps_after(conn.id, index);
+            refreshed = true;
+        }
+        if ttl > 0 {
+            store_values(conn.id, index, &values, Duration::from_secs(ttl));
+        }
+        outcome.values.extend(values);
+    }
+
+    Ok(outcome)
+}
+
+pub fn clear_chain_cache(conn_id: Uuid) {
+    if let Ok(mut cache) = STEP_CACHE.lock() {
+        cache.retain(|(id, _), _| *id != conn_id);
+    }
+}
+
+fn clear_steps_after(conn_id: Uuid, index: usize) {
+    if let Ok(mut cache) = STEP_CACHE.lock() {
+        cache.retain(|(id, step), _| *id != conn_id || *step <= index);
+    }
+}
+
+fn cached_values(conn_id: Uuid, index: usize) -> Option<HashMap<String, String>> {
+    let cache = STEP_CACHE.lock().ok()?;
+    cache
+        .get(&(conn_id, index))
+        .filter(|c| c.expires_at > Instant::now())
+        .map(|c| c.values.clone())
+}
+
+fn store_values(conn_id: Uuid, index: usize, values: &HashMap<String, String>, ttl: Duration) {
+    if let Ok(mut cache) = STEP_CACHE.lock() {
+        cache.insert(
+            (conn_id, index),
+            CachedStep {
+                values: values.clone(),
+                expires_at: Instant::now() + ttl,
+            },
+        );
+    }
+}
+
+async fn run_step(
+    client: &reqwest::Client,
+    step: &Value,
+    vars: &HashMap<String, String>,
+    auth: &AuthParams,
+) -> Result<HashMap<String, String>, String> {
+    let url = step
+        .get("url")
+        .and_then(|v| v.as_str())
+        .map(|u| render_template(u, vars))
+        .ok_or("missing url")?;
+    let headers: HashMap<String, String> = step
+        .get("headers")
+        .map(headers_value_to_map)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| (k, render_template(&v, vars)))
+        .collect();
+    let (url, headers) = auth.apply(&url, &headers);
+    let method = step
+        .get("method")
+        .and_then(|v| v.as_str())
+        .unwrap_or("GET")
+        .parse::<reqwest::Method>()
+        .map_err(|e| e.to_string())?;
+
+    let mut rb = client.request(method, &url);
+    for (k, v) in &headers {
+        rb = rb.header(k, v);
+    }
+    match step.get("body") {
+        Some(Value::String(text)) => rb = rb.body(render_template(text, vars)),
+        Some(Value::Null) | None => {}
+        Some(body) => rb = rb.json(&render_value(body, vars)),
+    }
+
+    let resp = rb.send().await.map_err(|e| e.to_string())?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("{} returned {}", url, status));
+    }
+    let response_headers = resp.headers().clone();
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+
+    extract_step_values(step.get("extract"), &body, &response_headers)
+}
+
+fn extract_step_values(
+    extract: Option<&Value>,
+    body: &str,
+    headers: &HeaderMap,
+) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    let Some(rules) = extract.and_then(|v| v.as_object()) else {
+        return Ok(values);
+    };
+    let json: Option<Value> = serde_json::from_str(body).ok();
+    let json_path = |path: &str| {
+        json.as_ref()
+            .and_then(|j| get_path(j, path))
+            .and_then(value_to_string)
+    };
+
+    for (name, rule) in rules {
+        let value = match rule {
+            Value::String(path) => json_path(path),
+            Value::Object(rule) => {
+                if let Some(path) = rule.get("path").and_then(|v| v.as_str()) {
+                    json_path(path)
+                } else if let Some(pattern) = rule.get("regex").and_then(|v| v.as_str()) {
+                    regex_value(body, pattern)?
+                } else if let Some(header) = rule.get("header").and_then(|v| v.as_str()) {
+                    headers
+                        .get(header)
+                        .and_then(|h| h.to_str().ok())
+                        .map(|h| h.to_string())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        match value.filter(|v| !v.is_empty()) {
+            Some(value) => {
+                values.insert(name.clone(), value);
+            }
+            None => return Err(format!("no value found for {}", name)),
+        }
+    }
+
+    Ok(values)
+}
+
+// Uses the `value` group when present, otherwise the first group, otherwise
+// the whole match.
+fn regex_value(body: &str, pattern: &str) -> Result<Option<String>, String> {
+    let re = Regex::new(pattern).map_err(|e| format!("invalid regex {:?}: {}", pattern, e))?;
+    Ok(re.captures(body).and_then(|caps| {
+        caps.name("value")
+            .or_else(|| caps.get(1))
+            .or_else(|| caps.get(0))
+            .map(|m| m.as_str().to_string())
+    }))
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// Unknown placeholders are left untouched so typos are visible in the request.
+pub fn render_template(template: &str, vars: &HashMap<String, String>) -> String {
+    if vars.is_empty() {
+        return template.to_string();
+    }
+    PLACEHOLDER
+        .replace_all(template, |caps: &regex::Captures| {
+            vars.get(&caps[1])
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+fn render_value(value: &Value, vars: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(s) => Value::String(render_template(s, vars)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_value(v, vars)).collect()),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(k, v)| (k.clone(), render_value(v, vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_conn;
+    use serde_json::json;
+
+    #[test]
+    fn renders_known_placeholders_only() {
+        let vars = HashMap::from([("stream_id".to_string(), "abc".to_string())]);
+        assert_eq!(
+            render_template("https://x/np/{{ stream_id }}?s={{session}}", &vars),
+            "https://x/np/abc?s={{session}}"
+        );
+    }
+
+    #[test]
+    fn extracts_values_by_path_regex_and_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Session", "s-1".parse().unwrap());
+        let extract = json!({
+            "stream_id": "data.stream.id",
+            "mount": { "regex": "\"mount\":\\s*\"(?P<value>[^\"]+)\"" },
+            "session": { "header": "x-session" }
+        });
+        let body = r#"{"data":{"stream":{"id":42}},"mount":"/live"}"#;
+        let values = extract_step_values(Some(&extract), body, &headers).unwrap();
+        assert_eq!(values["stream_id"], "42");
+        assert_eq!(values["mount"], "/live");
+        assert_eq!(values["session"], "s-1");
+
+        let missing = json!({ "other": "data.missing" });
+        assert!(extract_step_values(Some(&missing), body, &headers).is_err());
+    }
+
+    #[test]
+    fn rejects_steps_without_url() {
+        assert!(validate_request_chain(&json!([{ "url": "https://x/a" }])).is_ok());
+        assert!(validate_request_chain(&json!([{ "method": "GET" }])).is_err());
+        assert!(validate_request_chain(&json!({ "url": "https://x/a" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn final_request_uses_values_from_lookup_step() {
+        use axum::{extract::Path, routing::get, Json, Router};
+        use sea_orm::DatabaseConnection;
+
+        let app = Router::new()
+            .route("/api/stream", get(|| async { Json(json!({ "stream": { "id": "abc" } })) }))
+            .route(
+                "/np/{id}",
+                get(|Path(id): Path<String>| async move {
+                    Json(json!({ "artist": "Tom Petty", "title": format!("stream {}", id) }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let conn = now_playing_connections::Model {
+            name: "chain".to_string(),
+            url: format!("http://{}/np/{{{{stream_id}}}}", addr),
+            poll_interval_seconds: 10,
+            request_chain_json: Some(json!([{
+                "url": format!("http://{}/api/stream", addr),
+                "extract": { "stream_id": "stream.id" },
+                "cache_ttl_seconds": 60
+            }])),
+            ..test_conn("http_json")
+        };
+
+        let db = DatabaseConnection::default();
+        let result = super::super::utils::fetch_and_parse(&db, &conn, None).await.unwrap();
+        assert_eq!(result.raw_payload["title"], "stream abc");
+        assert_eq!(result.reported_artist.as_deref(), Some("Tom Petty"));
+        assert_eq!(cached_values(conn.id, 0).unwrap()["stream_id"], "abc");
+        clear_chain_cache(conn.id);
+        assert!(cached_values(conn.id, 0).is_none());
+    }
+
+    #[tokio::test]
+    async fn reruns_later_steps_after_an_earlier_step_refreshes() {
+        use axum::{extract::Path, routing::get, Json, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static STREAMS: AtomicUsize = AtomicUsize::new(0);
+        let app = Router::new()
+            .route(
+                "/api/stream",
+                get(|| async { Json(json!({ "id": STREAMS.fetch_add(1, Ordering::SeqCst) })) }),
+            )
+            .route(
+                "/api/session/{id}",
+                get(|Path(id): Path<String>| async move { Json(json!({ "session": format!("s-{}", id) })) }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let conn = now_playing_connections::Model {
+            request_chain_json: Some(json!([
+                { "url": format!("http://{}/api/stream", addr), "extract": { "stream_id": "id" } },
+                {
+                    "url": format!("http://{}/api/session/{{{{stream_id}}}}", addr),
+                    "extract": { "session": "session" },
+                    "cache_ttl_seconds": 60
+                }
+            ])),
+            ..test_conn("http_json")
+        };
+
+        let client = reqwest::Client::new();
+        let auth = AuthParams::default();
+        let first = run_request_chain(&client, &conn, &auth).await.unwrap();
+        assert_eq!(first.values["session"], "s-0");
+        let second = run_request_chain(&client, &conn, &auth).await.unwrap();
+        assert_eq!(second.values["stream_id"], "1");
+        assert_eq!(second.values["session"], "s-1");
+        assert!(!second.from_cache);
+        clear_chain_cache(conn.id);
+    }
+}
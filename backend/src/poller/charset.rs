@@ -0,0 +1,118 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use regex::bytes::Regex;
+use std::sync::LazyLock;
+
+// Response bodies are decoded using, in order: the connection's
+// `response_charset` override, a byte-order mark, the `Content-Type` charset,
+// an XML declaration, an HTML `<meta>` charset, then UTF-8 when the bytes are
+// valid UTF-8. Anything else is treated as Windows-1252, which covers the
+// Latin-1 feeds older automation systems still produce.
+
+static XML_ENCODING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*<\?xml[^>]*?\bencoding\s*=\s*["']([A-Za-z0-9._:\-]+)["']"#).unwrap()
+});
+
+static META_CHARSET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?([A-Za-z0-9._:\-]+)"#).unwrap()
+});
+
+static XML_DECLARATION_ENCODING: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r#"^(\s*<\?xml[^>]*?\bencoding\s*=\s*)["'][^"']*["']"#).unwrap()
+});
+
+// Only the start of the document is searched for declarations.
+const SNIFF_LIMIT: usize = 1024;
+
+pub fn is_known_charset(label: &str) -> bool {
+    Encoding::for_label(label.trim().as_bytes()).is_some()
+}
+
+pub fn detect_encoding(
+    body: &[u8],
+    content_type: Option<&str>,
+    override_charset: Option<&str>,
+) -> &'static Encoding {
+    if let Some(encoding) = override_charset.and_then(|l| Encoding::for_label(l.trim().as_bytes())) {
+        return encoding;
+    }
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+    if let Some(encoding) = content_type.and_then(content_type_charset) {
+        return encoding;
+    }
+
+    let head = &body[..body.len().min(SNIFF_LIMIT)];
+    let declared = XML_ENCODING
+        .captures(head)
+        .or_else(|| META_CHARSET.captures(head))
+        .and_then(|caps| Encoding::for_label(&caps[1]));
+    if let Some(encoding) = declared {
+        return encoding;
+    }
+
+    if std::str::from_utf8(body).is_ok() {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    }
+}
+
+pub fn decode_body(body: &[u8], content_type: Option<&str>, override_charset: Option<&str>) -> String {
+    let encoding = detect_encoding(body, content_type, override_charset);
+    let (text, _, had_errors) = encoding.decode(body);
+    if had_errors {
+        tracing::debug!("Body contained bytes invalid for {}", encoding.name());
+    }
+
+    // The text is UTF-8 from here on; a stale declaration would make XML
+    // parsers decode it a second time.
+    if encoding != UTF_8 {
+        return XML_DECLARATION_ENCODING
+            .replace(&text, "${1}\"UTF-8\"")
+            .into_owned();
+    }
+    text.into_owned()
+}
+
+fn content_type_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, value)| Encoding::for_label(value.trim().trim_matches('"').as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_latin1_xml_using_its_declaration() {
+        let body = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><song><artist>Beyonc\xe9</artist></song>";
+        let text = decode_body(body, Some("text/xml"), None);
+        assert!(text.contains("<artist>Beyonc\u{e9}</artist>"));
+        assert!(text.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+    }
+
+    #[test]
+    fn content_type_and_override_take_precedence() {
+        let body = b"Mot\xf6rhead - Ace of Spades";
+        assert_eq!(
+            decode_body(body, Some("text/plain; charset=windows-1252"), None),
+            "Mot\u{f6}rhead - Ace of Spades"
+        );
+        // Windows-1252 maps 0x80 to the euro sign, ISO-8859-15 uses 0xa4.
+        assert_eq!(decode_body(b"\xa4", Some("text/plain; charset=utf-8"), Some("iso-8859-15")), "\u{20ac}");
+    }
+
+    #[test]
+    fn sniffs_bom_meta_tags_and_falls_back_to_windows_1252() {
+        assert_eq!(decode_body(b"\xef\xbb\xbfSigur R\xc3\xb3s", None, None), "Sigur R\u{f3}s");
+        let html = b"<html><head><meta charset=\"iso-8859-1\"></head><body>Bj\xf6rk</body></html>";
+        assert!(decode_body(html, None, None).contains("Bj\u{f6}rk"));
+        assert_eq!(decode_body(b"Caf\xe9 \x93Live\x94", None, None), "Caf\u{e9} \u{201c}Live\u{201d}");
+        assert_eq!(decode_body("Café".as_bytes(), None, None), "Café");
+    }
+}
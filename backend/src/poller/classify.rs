@@ -0,0 +1,352 @@
+use regex::Regex;
+use sea_orm::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::entities::stations;
+use crate::mapping_spec::{expand_block_pattern, EventField};
+use super::utils::ParsedFields;
+use super::validity::field_value;
+
+// Content classification runs after extraction and sorts every stored event
+// into song, spot, promo, talk or station_id, so imaging and ad breaks don't
+// count as airplay. Stations tune it with `classification_json`:
+//
+// {
+//   "category_map": { "COM": "spot", "IMG": "station_id" },
+//   "slogans": ["The Hits", "Today's Best Music"],
+//   "rules": [{ "class": "promo", "patterns": ["^win tickets"], "fields": ["title"] }],
+//   "short_duration_seconds": 30
+// }
+//
+// Explicit feed fields win: `category_map`, then well-known words in the
+// category or item type ("commercial", "promo", "news", "jingle", ...). Next
+// come the station's callsign, name, slogans and pattern rules, and last the
+// heuristics: very short items are imaging, items without a title are talk.
+// Everything else is a song.
+
+const DEFAULT_SHORT_DURATION_SECONDS: i64 = 30;
+const DEFAULT_TEXT_FIELDS: [EventField; 2] = [EventField::Artist, EventField::Title];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentClass {
+    Song,
+    Spot,
+    Promo,
+    Talk,
+    StationId,
+}
+
+impl ContentClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContentClass::Song => "song",
+            ContentClass::Spot => "spot",
+            ContentClass::Promo => "promo",
+            ContentClass::Talk => "talk",
+            ContentClass::StationId => "station_id",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassificationConfig {
+    /// Category or item type values (case-insensitive) and the class they mean.
+    #[serde(default)]
+    pub category_map: BTreeMap<String, ContentClass>,
+    /// Exact (case-insensitive) artist or title values that are station imaging.
+    #[serde(default)]
+    pub slogans: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<ClassificationRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_duration_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassificationRule {
+    pub class: ContentClass,
+    /// Case-insensitive regexes; `{callsign}` and `{station_name}` stand for the station's own.
+    pub patterns: Vec<String>,
+    /// Text fields to match; defaults to artist and title.
+    #[serde(default)]
+    pub fields: Vec<EventField>,
+}
+
+pub fn validate_classification(value: &serde_json::Value) -> Result<ClassificationConfig, Vec<String>> {
+    let config: ClassificationConfig =
+        serde_json::from_value(value.clone()).map_err(|e| vec![format!("classification: {}", e)])?;
+    let mut errors = Vec::new();
+    if config.short_duration_seconds.is_some_and(|s| s <= 0) {
+        errors.push("short_duration_seconds: must be positive".to_string());
+    }
+    if config.slogans.iter().any(|s| s.trim().is_empty()) {
+        errors.push("slogans: must not be empty".to_string());
+    }
+    for (index, rule) in config.rules.iter().enumerate() {
+        if rule.patterns.is_empty() {
+            errors.push(format!("rules[{}]: needs at least one pattern", index));
+        }
+        if let Some(field) = rule.fields.iter().find(|f| !f.is_text()) {
+            errors.push(format!("rules[{}]: `{}` isn't a text field", index, field.name()));
+        }
+        for pattern in &rule.patterns {
+            let expanded = expand_block_pattern(pattern, Some("KXYZ"), Some("Station")).unwrap_or_default();
+            if let Err(e) = Regex::new(&expanded) {
+                errors.push(format!("rules[{}]: invalid pattern {:?}: {}", index, pattern, e));
+            }
+        }
+    }
+    if errors.is_empty() { Ok(config) } else { Err(errors) }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Classification {
+    pub class: ContentClass,
+    pub reason: String,
+}
+
+struct CompiledRule {
+    class: ContentClass,
+    fields: Vec<EventField>,
+    patterns: Vec<(String, Regex)>,
+}
+
+pub struct Classifier {
+    category_map: BTreeMap<String, ContentClass>,
+    // Lowercased station name and slogans.
+    station_names: Vec<String>,
+    callsign: Option<Regex>,
+    rules: Vec<CompiledRule>,
+    short_duration_seconds: i64,
+}
+
+impl Classifier {
+    pub fn new(config: ClassificationConfig, station: Option<&stations::Model>) -> Self {
+        let callsign = station
+            .and_then(|s| s.callsign.as_deref())
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+        let station_name = station.map(|s| s.name.as_str());
+        let station_names = station_name
+            .into_iter()
+            .chain(config.slogans.iter().map(String::as_str))
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let rules = config
+            .rules
+            .into_iter()
+            .map(|rule| CompiledRule {
+                class: rule.class,
+                fields: if rule.fields.is_empty() { DEFAULT_TEXT_FIELDS.to_vec() } else { rule.fields },
+                patterns: rule
+                    .patterns
+                    .iter()
+                    .filter_map(|pattern| {
+                        let expanded = expand_block_pattern(pattern, callsign, station_name)?;
+                        Regex::new(&expanded).ok().map(|re| (pattern.clone(), re))
+                    })
+                    .collect(),
+            })
+            .collect();
+        Classifier {
+            category_map: config
+                .category_map
+                .into_iter()
+                .map(|(key, class)| (key.trim().to_lowercase(), class))
+                .collect(),
+            station_names,
+            // "KXYZ", "KXYZ-FM", "kxyz 101.5" but not "KXYZZY".
+            callsign: callsign.and_then(|c| Regex::new(&format!(r"(?i)^{}\b", regex::escape(c))).ok()),
+            rules,
+            short_duration_seconds: config.short_duration_seconds.unwrap_or(DEFAULT_SHORT_DURATION_SECONDS),
+        }
+    }
+
+    pub fn classify(&self, fields: &ParsedFields) -> Classification {
+        self.by_feed_fields(fields)
+            .or_else(|| self.by_station_rules(fields))
+            .or_else(|| self.by_heuristics(fields))
+            .unwrap_or_else(|| Classification {
+                class: ContentClass::Song,
+                reason: "default".to_string(),
+            })
+    }
+
+    fn by_feed_fields(&self, fields: &ParsedFields) -> Option<Classification> {
+        for field in [EventField::Category, EventField::ItemType] {
+            let Some(value) = field_value(fields, field) else {
+                continue;
+            };
+            let class = self
+                .category_map
+                .get(&value.to_lowercase())
+                .copied()
+                .or_else(|| feed_keyword_class(&value));
+            if let Some(class) = class {
+                return Some(Classification {
+                    class,
+                    reason: format!("{} `{}`", field.name(), value),
+                });
+            }
+        }
+        None
+    }
+
+    fn by_station_rules(&self, fields: &ParsedFields) -> Option<Classification> {
+        for field in DEFAULT_TEXT_FIELDS {
+            let Some(value) = field_value(fields, field) else {
+                continue;
+            };
+            let is_callsign = self.callsign.as_ref().is_some_and(|re| re.is_match(&value));
+            if is_callsign || self.station_names.contains(&value.to_lowercase()) {
+                return Some(Classification {
+                    class: ContentClass::StationId,
+                    reason: format!("{} is the station's own name", field.name()),
+                });
+            }
+        }
+        for rule in &self.rules {
+            for field in &rule.fields {
+                let Some(value) = field_value(fields, *field) else {
+                    continue;
+                };
+                if let Some((pattern, _)) = rule.patterns.iter().find(|(_, re)| re.is_match(&value)) {
+                    return Some(Classification {
+                        class: rule.class,
+                        reason: format!("{} matches `{}`", field.name(), pattern),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn by_heuristics(&self, fields: &ParsedFields) -> Option<Classification> {
+        if let Some(duration) = fields.duration_seconds
+            && duration > 0
+            && duration < self.short_duration_seconds
+        {
+            return Some(Classification {
+                class: ContentClass::StationId,
+                reason: format!("duration {}s is under {}s", duration, self.short_duration_seconds),
+            });
+        }
+        if field_value(fields, EventField::Title).is_none() {
+            return Some(Classification {
+                class: ContentClass::Talk,
+                reason: "no title".to_string(),
+            });
+        }
+        None
+    }
+}
+
+// Words automation systems commonly use for cart types and categories. The
+// whole value is tried first, then each word in order.
+fn feed_keyword_class(value: &str) -> Option<ContentClass> {
+    let lookup = |word: &str| match word {
+        "song" | "music" | "mus" | "track" | "sng" => Some(ContentClass::Song),
+        "spot" | "spots" | "commercial" | "commercials" | "com" | "ad" | "ads" | "advert" | "advertisement"
+        | "sponsor" | "sponsorship" => Some(ContentClass::Spot),
+        "promo" | "promos" | "promotion" | "pro" => Some(ContentClass::Promo),
+        "talk" | "news" | "weather" | "traffic" | "sports" | "dj" | "voice" | "voicetrack" | "vt" | "speech"
+        | "interview" => Some(ContentClass::Talk),
+        "id" | "stationid" | "station_id" | "legal" | "imaging" | "img" | "jingle" | "jingles" | "sweeper"
+        | "liner" | "stinger" | "bumper" => Some(ContentClass::StationId),
+        _ => None,
+    };
+    let normalized = value.trim().to_lowercase();
+    lookup(&normalized).or_else(|| {
+        normalized
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .find_map(lookup)
+    })
+}
+
+pub async fn load_classifier<C: ConnectionTrait>(db: &C, station_id: Uuid) -> Result<Classifier, DbErr> {
+    let station = stations::Entity::find_by_id(station_id).one(db).await?;
+    let config = station
+        .as_ref()
+        .and_then(|s| s.classification_json.as_ref())
+        .and_then(|value| match serde_json::from_value(value.clone()) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                tracing::warn!(station_id = %station_id, "Ignoring invalid classification_json: {}", e);
+                None
+            }
+        })
+        .unwrap_or_default();
+    Ok(Classifier::new(config, station.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_station;
+
+    fn classifier(config: serde_json::Value) -> Classifier {
+        let station = test_station(Some("KXYZ"));
+        Classifier::new(validate_classification(&config).unwrap(), Some(&station))
+    }
+
+    fn event(artist: Option<&str>, title: Option<&str>, duration: Option<i64>, category: Option<&str>) -> ParsedFields {
+        let mut fields = ParsedFields {
+            artist: artist.map(str::to_string),
+            title: title.map(str::to_string),
+            duration_seconds: duration,
+            ..Default::default()
+        };
+        fields.extended.category = category.map(str::to_string);
+        fields
+    }
+
+    #[test]
+    fn feed_fields_win_over_station_rules_and_heuristics() {
+        let classifier = classifier(serde_json::json!({ "category_map": { "C9": "promo" } }));
+        let class = |fields: ParsedFields| classifier.classify(&fields).class;
+
+        assert_eq!(class(event(Some("KXYZ"), Some("Station ID"), Some(5), Some("MUS"))), ContentClass::Song);
+        assert_eq!(class(event(Some("Acme Motors"), None, None, Some("Commercial Break"))), ContentClass::Spot);
+        assert_eq!(class(event(Some("Morning Show"), Some("Contest"), None, Some("c9"))), ContentClass::Promo);
+        assert_eq!(class(event(None, None, None, Some("Legal ID"))), ContentClass::StationId);
+
+        let classification = classifier.classify(&event(None, Some("Traffic"), None, Some("NEWS")));
+        assert_eq!(classification.class, ContentClass::Talk);
+        assert_eq!(classification.reason, "category `NEWS`");
+    }
+
+    #[test]
+    fn station_rules_then_heuristics_then_song() {
+        let classifier = classifier(serde_json::json!({
+            "slogans": ["The Hits"],
+            "rules": [{ "class": "promo", "patterns": ["^win .* from {callsign}$"], "fields": ["title"] }],
+            "short_duration_seconds": 20
+        }));
+        let class = |fields: ParsedFields| classifier.classify(&fields).class;
+
+        assert_eq!(class(event(Some("KXYZ-FM"), Some("The Hits"), Some(200), None)), ContentClass::StationId);
+        assert_eq!(class(event(Some("the hits"), Some("Now"), None, None)), ContentClass::StationId);
+        assert_eq!(class(event(Some("Hits 101"), Some("Now"), None, None)), ContentClass::StationId);
+        assert_eq!(class(event(Some("KXYZZY"), Some("Glow"), None, None)), ContentClass::Song);
+        assert_eq!(class(event(Some("DJ Sam"), Some("Win tickets from KXYZ"), None, None)), ContentClass::Promo);
+        assert_eq!(class(event(Some("Heart"), Some("Barracuda"), Some(12), None)), ContentClass::StationId);
+        assert_eq!(class(event(Some("Morning Show"), None, Some(600), None)), ContentClass::Talk);
+        assert_eq!(class(event(Some("Heart"), Some("Barracuda"), Some(262), None)), ContentClass::Song);
+
+        assert!(validate_classification(&serde_json::json!({ "rules": [{ "class": "ad", "patterns": ["x"] }] })).is_err());
+        assert_eq!(
+            validate_classification(&serde_json::json!({
+                "short_duration_seconds": 0,
+                "rules": [{ "class": "spot", "patterns": ["("], "fields": ["duration"] }]
+            }))
+            .unwrap_err()
+            .len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderValue;
+use sea_orm::{prelude::*, sea_query::Expr};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::RwLock;
+use utoipa::ToSchema;
+use crate::entities::now_playing_connections;
+
+// Opt-in cookie store for connections whose endpoint only answers once a
+// landing page (or a first response) has set a session cookie. Cookies are
+// kept in `cookies_json` so they survive restarts, and the jar can be primed
+// from `cookie_warmup_url`.
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    #[serde(default)]
+    pub host_only: bool,
+    #[serde(default)]
+    pub secure: bool,
+    #[schema(value_type = Option<String>)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.map(|exp| exp <= now).unwrap_or(false)
+    }
+
+    fn matches(&self, url: &reqwest::Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            host == self.domain || host.ends_with(&format!(".{}", self.domain))
+        };
+        let path = url.path();
+        let path_ok = path == self.path
+            || (path.starts_with(&self.path)
+                && (self.path.ends_with('/') || path[self.path.len()..].starts_with('/')));
+        domain_ok && path_ok && (!self.secure || url.scheme() == "https")
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: RwLock<Vec<StoredCookie>>,
+}
+
+impl CookieJar {
+    pub fn from_value(value: Option<&Value>) -> Self {
+        let cookies = value
+            .and_then(|v| serde_json::from_value::<Vec<StoredCookie>>(v.clone()).ok())
+            .unwrap_or_default();
+        Self { cookies: RwLock::new(cookies) }
+    }
+
+    pub fn snapshot(&self) -> Vec<StoredCookie> {
+        let now = Utc::now();
+        self.cookies
+            .read()
+            .map(|c| c.iter().filter(|c| !c.is_expired(now)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut cookies) = self.cookies.write() {
+            cookies.clear();
+        }
+    }
+
+    pub fn to_value(&self) -> Option<Value> {
+        let cookies = self.snapshot();
+        if cookies.is_empty() {
+            None
+        } else {
+            serde_json::to_value(cookies).ok()
+        }
+    }
+
+    fn store(&self, header: &str, url: &reqwest::Url) {
+        let Ok(parsed) = cookie::Cookie::parse(header) else {
+            return;
+        };
+        let Some(host) = url.host_str().map(|h| h.to_ascii_lowercase()) else {
+            return;
+        };
+
+        let (domain, host_only) = match parsed.domain().map(|d| d.trim_start_matches('.').to_ascii_lowercase()) {
+            Some(d) if !d.is_empty() => (d, false),
+            _ => (host.clone(), true),
+        };
+        // Refuse cookies for domains the response did not come from.
+        if host != domain && !host.ends_with(&format!(".{}", domain)) {
+            return;
+        }
+
+        let path = parsed
+            .path()
+            .filter(|p| p.starts_with('/'))
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| default_path(url.path()));
+        let now = Utc::now();
+        let expires_at = match parsed.max_age() {
+            Some(max_age) => Some(now + chrono::Duration::seconds(max_age.whole_seconds())),
+            None => parsed
+                .expires_datetime()
+                .and_then(|e| DateTime::<Utc>::from_timestamp(e.unix_timestamp(), 0)),
+        };
+
+        let cookie = StoredCookie {
+            name: parsed.name().to_string(),
+            value: parsed.value().to_string(),
+            domain,
+            path,
+            host_only,
+            secure: parsed.secure().unwrap_or(false),
+            expires_at,
+        };
+
+        if let Ok(mut cookies) = self.cookies.write() {
+            cookies.retain(|c| {
+                let replaced = c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path;
+                !replaced && !c.is_expired(now)
+            });
+            if !cookie.is_expired(now) {
+                cookies.push(cookie);
+            }
+        }
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &reqwest::Url) {
+        for header in cookie_headers {
+            if let Ok(header) = header.to_str() {
+                self.store(header, url);
+            }
+        }
+    }
+
+    fn cookies(&self, url: &reqwest::Url) -> Option<HeaderValue> {
+        let header = self
+            .snapshot()
+            .iter()
+            .filter(|c| c.matches(url))
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&header).ok()
+        }
+    }
+}
+
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(idx) => request_path[..idx].to_string(),
+    }
+}
+
+pub async fn warm_up(client: &reqwest::Client, url: &str) {
+    match client.get(url).send().await {
+        Ok(resp) if !resp.status().is_success() => {
+            tracing::warn!("Cookie warm-up {} returned {}", url, resp.status());
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Cookie warm-up {} failed: {}", url, e),
+    }
+}
+
+pub async fn save_cookies(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    jar: &CookieJar,
+) -> Result<(), DbErr> {
+    let cookies = jar.to_value();
+    if cookies == conn.cookies_json {
+        return Ok(());
+    }
+    now_playing_connections::Entity::update_many()
+        .col_expr(now_playing_connections::Column::CookiesJson, Expr::value(cookies))
+        .filter(now_playing_connections::Column::Id.eq(conn.id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::cookie::CookieStore;
+
+    fn url(s: &str) -> reqwest::Url {
+        reqwest::Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn stores_and_sends_matching_cookies() {
+        let jar = CookieJar::default();
+        let headers = [
+            HeaderValue::from_static("cf_clearance=abc; Domain=.example.com; Path=/; Secure"),
+            HeaderValue::from_static("sid=1; Path=/api"),
+            HeaderValue::from_static("other=x; Domain=evil.test"),
+        ];
+        jar.set_cookies(&mut headers.iter(), &url("https://www.example.com/api/np"));
+
+        assert_eq!(jar.snapshot().len(), 2);
+        let sent = jar.cookies(&url("https://player.example.com/api/np")).unwrap();
+        assert_eq!(sent.to_str().unwrap(), "cf_clearance=abc");
+        let sent = jar.cookies(&url("https://www.example.com/api/np")).unwrap();
+        assert_eq!(sent.to_str().unwrap(), "cf_clearance=abc; sid=1");
+        assert!(jar.cookies(&url("http://www.example.com/")).is_none());
+    }
+
+    #[test]
+    fn round_trips_through_json_and_drops_expired_cookies() {
+        let jar = CookieJar::default();
+        let set = [HeaderValue::from_static("sid=1"), HeaderValue::from_static("old=1; Max-Age=60")];
+        jar.set_cookies(&mut set.iter(), &url("https://example.com/np"));
+        let expire = [HeaderValue::from_static("old=; Max-Age=0")];
+        jar.set_cookies(&mut expire.iter(), &url("https://example.com/np"));
+
+        let restored = CookieJar::from_value(jar.to_value().as_ref());
+        let cookies = restored.snapshot();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name, "sid");
+        assert!(cookies[0].host_only);
+
+        restored.clear();
+        assert!(restored.to_value().is_none());
+    }
+}
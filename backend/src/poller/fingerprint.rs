@@ -0,0 +1,284 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use sea_orm::{prelude::*, sea_query::Expr, QueryOrder};
+use std::cmp::Reverse;
+use crate::entities::{now_playing_connections, payload_mappings};
+use crate::mapping_spec::is_xpath_path;
+use super::jsonpath::select;
+use super::text::{extract_text_values, text_lookup};
+use super::utils::{
+    extract_xml_values,
+    fill_station_timezone,
+    is_text_connection_type,
+    is_xml_connection_type,
+    normalize_xml_for_parse,
+    payload_candidates,
+    station_timezone,
+    value_paths,
+    xml_local_name,
+    xml_lookup,
+};
+use super::xpath::XPathDocument;
+
+// Structural fingerprints for payload mappings:
+//
+// "fingerprint": { "root_element": "nowplaying", "required_paths": ["song.artist", "@station"] }
+//
+// A connection with a fixed mapping checks every payload against that
+// mapping's fingerprint, so a feed that changes shape is reported as
+// MAPPING_MISMATCH (and the payload kept in `rejected_events`) instead of
+// producing events with empty fields. Connections
+// with `auto_mapping` pick, per payload, the most specific library mapping
+// whose fingerprint matches.
+
+pub enum MappingResolution {
+    // The connection's own mapping (or none) applies.
+    Fixed,
+    // An auto connection's pick from the library.
+    Selected(payload_mappings::Model),
+    Mismatch(String),
+}
+
+pub async fn resolve_payload_mapping<C: ConnectionTrait>(
+    db: &C,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    payload: &serde_json::Value,
+    connection_type: &str,
+) -> Result<MappingResolution, DbErr> {
+    if conn.auto_mapping {
+        let library = load_mapping_library(db, conn.station_id).await?;
+        return Ok(match select_mapping(&library, payload, connection_type) {
+            Some(selected) => MappingResolution::Selected(selected.clone()),
+            None => MappingResolution::Mismatch(library_mismatch(&library, payload, connection_type)),
+        });
+    }
+
+    let problems = fingerprint_mismatches(mapping.and_then(|m| m.mapping_json.as_object()), payload, connection_type);
+    Ok(if problems.is_empty() {
+        MappingResolution::Fixed
+    } else {
+        MappingResolution::Mismatch(problems.join("; "))
+    })
+}
+
+// Every mapping with a fingerprint, oldest first, with the station's timezone
+// filled in the same way `load_connection_mapping` does.
+pub async fn load_mapping_library<C: ConnectionTrait>(
+    db: &C,
+    station_id: Uuid,
+) -> Result<Vec<payload_mappings::Model>, DbErr> {
+    let mut library = payload_mappings::Entity::find()
+        .filter(Expr::cust("jsonb_typeof(mapping_json -> 'fingerprint') = 'object'"))
+        .order_by_asc(payload_mappings::Column::CreatedAt)
+        .order_by_asc(payload_mappings::Column::Id)
+        .all(db)
+        .await?;
+    let timezone = station_timezone(db, station_id).await?;
+    for mapping in &mut library {
+        fill_station_timezone(&mut mapping.mapping_json, timezone.as_deref());
+    }
+    Ok(library)
+}
+
+// Why no library mapping fits, naming each fingerprint and what it missed.
+fn library_mismatch(library: &[payload_mappings::Model], payload: &serde_json::Value, connection_type: &str) -> String {
+    if library.is_empty() {
+        return "No mapping in the library has a fingerprint".to_string();
+    }
+    let failures: Vec<String> = library
+        .iter()
+        .map(|mapping| {
+            let problems = fingerprint_mismatches(mapping.mapping_json.as_object(), payload, connection_type);
+            format!("{} ({})", mapping.name, problems.join(", "))
+        })
+        .collect();
+    format!(
+        "None of the {} fingerprinted mappings match the payload: {}",
+        library.len(),
+        failures.join("; ")
+    )
+}
+
+// The matching mapping with the most checks; ties go to the older mapping.
+pub fn select_mapping<'a>(
+    library: &'a [payload_mappings::Model],
+    payload: &serde_json::Value,
+    connection_type: &str,
+) -> Option<&'a payload_mappings::Model> {
+    library
+        .iter()
+        .filter_map(|mapping| {
+            let mapping_obj = mapping.mapping_json.as_object();
+            let specificity = fingerprint_checks(mapping_obj)?;
+            fingerprint_mismatches(mapping_obj, payload, connection_type)
+                .is_empty()
+                .then_some((mapping, specificity))
+        })
+        .min_by_key(|(_, specificity)| Reverse(*specificity))
+        .map(|(mapping, _)| mapping)
+}
+
+fn fingerprint_checks(mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>) -> Option<usize> {
+    let fingerprint = mapping_obj?.get("fingerprint")?.as_object()?;
+    let required = fingerprint.get("required_paths").map(value_paths).unwrap_or_default().len();
+    let root = usize::from(fingerprint.get("root_element").is_some_and(|v| v.is_string()));
+    Some(required + root)
+}
+
+// Why `payload` doesn't fit the mapping's fingerprint; empty when it fits or
+// the mapping has none.
+pub fn fingerprint_mismatches(
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+    payload: &serde_json::Value,
+    connection_type: &str,
+) -> Vec<String> {
+    let Some(fingerprint) = mapping_obj
+        .and_then(|o| o.get("fingerprint"))
+        .and_then(|v| v.as_object())
+    else {
+        return Vec::new();
+    };
+    let required_paths = fingerprint.get("required_paths").map(value_paths).unwrap_or_default();
+    let root_element = fingerprint.get("root_element").and_then(|v| v.as_str());
+    let mut problems = Vec::new();
+
+    let xml = payload.as_str().filter(|_| is_xml_connection_type(connection_type));
+    if let Some(expected) = root_element {
+        match xml.and_then(xml_root_element) {
+            Some(root) if root == expected => {}
+            Some(root) => problems.push(format!("root element is `{}`, expected `{}`", root, expected)),
+            None => problems.push(format!("payload has no `{}` root element", expected)),
+        }
+    }
+    if required_paths.is_empty() {
+        return problems;
+    }
+
+    let present: Box<dyn Fn(&str) -> bool> = match (xml, payload.as_str()) {
+        (Some(xml), _) => {
+            let values = extract_xml_values(xml);
+            let document = required_paths
+                .iter()
+                .any(|p| is_xpath_path(p))
+                .then(|| XPathDocument::parse(xml).ok())
+                .flatten();
+            Box::new(move |path: &str| {
+                if is_xpath_path(path) {
+                    document
+                        .as_ref()
+                        .is_some_and(|d| d.evaluate(path).ok().flatten().is_some())
+                } else {
+                    xml_lookup(&values, None, path).is_some()
+                }
+            })
+        }
+        (None, Some(text)) if is_text_connection_type(connection_type) => {
+            let values = extract_text_values(text, mapping_obj);
+            Box::new(move |path: &str| text_lookup(&values, path).is_some())
+        }
+        _ => Box::new(|path: &str| {
+            payload_candidates(payload)
+                .into_iter()
+                .any(|base| select(base, path).into_iter().any(|v| !v.is_null()))
+        }),
+    };
+    for path in required_paths {
+        if !present(path) {
+            problems.push(format!("missing `{}`", path));
+        }
+    }
+    problems
+}
+
+fn xml_root_element(xml: &str) -> Option<String> {
+    let normalized = normalize_xml_for_parse(xml);
+    let mut reader = Reader::from_str(&normalized);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => return Some(xml_local_name(e.local_name().as_ref())),
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_mapping;
+
+    #[test]
+    fn reports_why_payloads_do_not_match() {
+        let spec = serde_json::json!({
+            "fingerprint": { "root_element": "nowplaying", "required_paths": ["song.artist", "song@id"] }
+        });
+        let xml = serde_json::json!("<ns:nowplaying xmlns:ns=\"urn:np\"><song id=\"7\"><artist>Heart</artist></song></ns:nowplaying>");
+        assert!(fingerprint_mismatches(spec.as_object(), &xml, "http_xml").is_empty());
+
+        let changed = serde_json::json!("<playlist><song><title>Barracuda</title></song></playlist>");
+        assert_eq!(
+            fingerprint_mismatches(spec.as_object(), &changed, "http_xml"),
+            vec![
+                "root element is `playlist`, expected `nowplaying`".to_string(),
+                "missing `song.artist`".to_string(),
+                "missing `song@id`".to_string(),
+            ]
+        );
+
+        let json_spec = serde_json::json!({ "fingerprint": { "required_paths": ["now.artist"] } });
+        let wrapped = serde_json::json!({ "data": { "now": { "artist": "Heart" } } });
+        assert!(fingerprint_mismatches(json_spec.as_object(), &wrapped, "http_json").is_empty());
+        assert_eq!(
+            fingerprint_mismatches(json_spec.as_object(), &serde_json::json!({ "now": null }), "http_json"),
+            vec!["missing `now.artist`".to_string()]
+        );
+        assert!(fingerprint_mismatches(None, &wrapped, "http_json").is_empty());
+    }
+
+    #[test]
+    fn selects_the_most_specific_matching_mapping() {
+        let named = |name: &str, mapping_json| payload_mappings::Model {
+            name: name.to_string(),
+            ..test_mapping(mapping_json)
+        };
+        let library = vec![
+            named("generic", serde_json::json!({ "fingerprint": { "required_paths": ["artist"] } })),
+            named("unrelated", serde_json::json!({ "fingerprint": { "required_paths": ["now.song"] } })),
+            named("specific", serde_json::json!({ "fingerprint": { "required_paths": ["artist", "cart"] } })),
+            named("no fingerprint", serde_json::json!({ "artist_path": "artist" })),
+        ];
+        let pick = |payload: serde_json::Value| {
+            select_mapping(&library, &payload, "http_json").map(|m| m.name.as_str())
+        };
+        assert_eq!(pick(serde_json::json!({ "artist": "Heart", "cart": "0420" })), Some("specific"));
+        assert_eq!(pick(serde_json::json!({ "artist": "Heart" })), Some("generic"));
+        assert_eq!(pick(serde_json::json!({ "title": "Barracuda" })), None);
+
+        let fingerprinted = &library[..3];
+        assert_eq!(
+            library_mismatch(fingerprinted, &serde_json::json!({ "title": "Barracuda" }), "http_json"),
+            "None of the 3 fingerprinted mappings match the payload: \
+             generic (missing `artist`); unrelated (missing `now.song`); specific (missing `artist`, missing `cart`)"
+        );
+        assert_eq!(
+            library_mismatch(&[], &serde_json::json!({}), "http_json"),
+            "No mapping in the library has a fingerprint"
+        );
+    }
+
+    #[test]
+    fn station_timezone_does_not_override_the_mapping() {
+        let mut plain = serde_json::json!({ "artist_path": "artist" });
+        fill_station_timezone(&mut plain, Some("America/Chicago"));
+        assert_eq!(plain["reported_at_timezone"], "America/Chicago");
+
+        let mut own = serde_json::json!({ "reported_at_timezone": "UTC" });
+        fill_station_timezone(&mut own, Some("America/Chicago"));
+        assert_eq!(own["reported_at_timezone"], "UTC");
+
+        let mut untouched = serde_json::json!({});
+        fill_station_timezone(&mut untouched, None);
+        assert_eq!(untouched, serde_json::json!({}));
+    }
+}
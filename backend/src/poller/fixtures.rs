@@ -0,0 +1,123 @@
+use chrono::Utc;
+use uuid::Uuid;
+use crate::entities::{credentials, now_playing_connections, payload_mappings, raw_now_playing_events, stations};
+
+// Entity builders for poller tests. Tests override what they care about with
+// struct update syntax, so new columns only need adding here.
+
+pub fn test_station(callsign: Option<&str>) -> stations::Model {
+    let now = Utc::now().fixed_offset();
+    stations::Model {
+        id: Uuid::new_v4(),
+        name: "Hits 101".to_string(),
+        callsign: callsign.map(str::to_string),
+        website_url: None,
+        timezone: None,
+        validity_rules_json: None,
+        classification_json: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+pub fn test_conn(connection_type: &str) -> now_playing_connections::Model {
+    let now = Utc::now().fixed_offset();
+    now_playing_connections::Model {
+        id: Uuid::new_v4(),
+        station_id: Uuid::new_v4(),
+        payload_mapping_id: None,
+        auto_mapping: false,
+        credential_id: None,
+        name: "test".to_string(),
+        connection_type: connection_type.to_string(),
+        url: "http://example.com".to_string(),
+        poll_interval_seconds: 60,
+        headers_json: None,
+        enabled: true,
+        use_duration_polling: false,
+        last_polled_at: None,
+        next_poll_at: None,
+        same_song_backoff_seconds: 0,
+        error_backoff_seconds: 0,
+        last_status: None,
+        last_error: None,
+        ingest_token: None,
+        ingest_hmac_secret: None,
+        hls_last_media_sequence: None,
+        request_chain_json: None,
+        response_charset: None,
+        cookie_jar_enabled: false,
+        cookie_warmup_url: None,
+        cookies_json: None,
+        proxy_url: None,
+        proxy_pool_id: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+pub fn test_mapping(mapping_json: serde_json::Value) -> payload_mappings::Model {
+    let now = Utc::now().fixed_offset();
+    payload_mappings::Model {
+        id: Uuid::new_v4(),
+        name: "test".to_string(),
+        description: None,
+        mapping_json,
+        current_version_id: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+pub fn test_event(
+    conn: &now_playing_connections::Model,
+    raw_payload: serde_json::Value,
+) -> raw_now_playing_events::Model {
+    let now = Utc::now().fixed_offset();
+    raw_now_playing_events::Model {
+        id: Uuid::new_v4(),
+        station_id: conn.station_id,
+        connection_id: conn.id,
+        observed_at: now,
+        reported_at: None,
+        reported_at_error: None,
+        reported_artist: None,
+        reported_title: None,
+        reported_album: None,
+        raw_payload,
+        payload_hash: String::new(),
+        http_status: Some(200),
+        content_type: None,
+        mapping_version_id: None,
+        reported_duration_seconds: None,
+        expected_end_at: None,
+        isrc: None,
+        upc: None,
+        artwork_url: None,
+        label: None,
+        composer: None,
+        category: None,
+        item_type: None,
+        extra_fields: None,
+        validity_status: "VALID".to_string(),
+        validity_reasons: None,
+        content_class: None,
+        content_class_reason: None,
+        created_at: now,
+    }
+}
+
+pub fn test_credential(kind: &str, config_json: serde_json::Value) -> credentials::Model {
+    let now = Utc::now().fixed_offset();
+    credentials::Model {
+        id: Uuid::new_v4(),
+        name: kind.to_string(),
+        kind: kind.to_string(),
+        config_json,
+        access_token: None,
+        access_token_expires_at: None,
+        last_error: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
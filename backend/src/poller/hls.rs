@@ -0,0 +1,750 @@
+use sea_orm::{prelude::*, Set};
+use chrono::{DateTime, FixedOffset};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use crate::entities::{now_playing_connections, payload_mappings};
+use super::auth::{load_credential, resolve_auth};
+use super::proxy::{classify_proxy_error, resolve_proxy};
+use super::utils::{
+    build_connection_client,
+    extract_fields,
+    process_fetch_result,
+    record_fetch_error,
+    record_idle_poll,
+    resolve_headers_for_request,
+    send_request,
+    FetchResult,
+};
+
+// `hls_id3` connections follow an HLS media playlist and read song metadata
+// from ID3 frames (TS timed-metadata streams or packed-audio segment headers),
+// `#EXTINF` titles and `#EXT-X-DATERANGE` attributes. Each segment with
+// metadata becomes a JSON payload, so mappings use ordinary JSON paths such as
+// `id3.TPE1` or `daterange.X-TITLE`; top-level `artist`/`title` are filled in
+// for connections without a mapping.
+
+// Segments are only downloaded for their ID3 tags, so cap the work per poll
+// and how much of each segment we read.
+const MAX_NEW_SEGMENTS: usize = 10;
+const SEGMENT_RANGE: &str = "bytes=0-524287";
+const TS_PACKET_SIZE: usize = 188;
+const TS_STREAM_TYPE_METADATA: u8 = 0x15;
+
+#[derive(Debug, Default)]
+pub struct Playlist {
+    pub segments: Vec<Segment>,
+    pub variants: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Segment {
+    pub sequence: i64,
+    pub uri: String,
+    pub duration: f64,
+    pub title: Option<String>,
+    pub program_date_time: Option<DateTime<FixedOffset>>,
+    pub dateranges: Vec<HashMap<String, String>>,
+}
+
+pub struct HlsPoll {
+    pub results: Vec<FetchResult>,
+    pub last_sequence: Option<i64>,
+}
+
+pub async fn poll_hls_connection(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    now: DateTime<FixedOffset>,
+) -> Result<(), DbErr> {
+    let poll = match fetch_hls_results(db, conn, mapping, conn.hls_last_media_sequence).await {
+        Ok(poll) => poll,
+        Err(e) => return record_fetch_error(db, conn, now, e.as_ref()).await,
+    };
+
+    if poll.results.is_empty() {
+        record_idle_poll(db, conn, now).await?;
+    }
+    for result in poll.results {
+        process_fetch_result(db, conn, mapping, result, now).await?;
+    }
+
+    if poll.last_sequence != conn.hls_last_media_sequence {
+        let mut active_conn: now_playing_connections::ActiveModel = conn.clone().into();
+        active_conn.hls_last_media_sequence = Set(poll.last_sequence);
+        active_conn.update(db).await?;
+    }
+
+    Ok(())
+}
+
+// Used by the connection test endpoint: returns the metadata of the newest
+// segment without advancing the stored playlist position.
+pub async fn fetch_latest_hls_result(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+) -> Result<FetchResult, Box<dyn std::error::Error + Send + Sync>> {
+    fetch_hls_results(db, conn, mapping, None)
+        .await?
+        .results
+        .pop()
+        .ok_or_else(|| "No timed metadata found in the latest HLS segment".into())
+}
+
+pub async fn fetch_hls_results(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    after_sequence: Option<i64>,
+) -> Result<HlsPoll, Box<dyn std::error::Error + Send + Sync>> {
+    let proxy_url = resolve_proxy(db, conn).await?;
+    fetch_hls_segments(db, conn, mapping, after_sequence, proxy_url.as_deref())
+        .await
+        .map_err(|e| classify_proxy_error(e, proxy_url.as_deref()))
+}
+
+async fn fetch_hls_segments(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    after_sequence: Option<i64>,
+    proxy_url: Option<&str>,
+) -> Result<HlsPoll, Box<dyn std::error::Error + Send + Sync>> {
+    let client = build_connection_client(proxy_url, None)?;
+    let (headers, _) = resolve_headers_for_request(conn);
+    // Signed playlists usually accept the same credential on segment requests;
+    // query-string keys are only added to the playlist URL.
+    let (headers, url) = match load_credential(db, conn).await? {
+        Some(c) => {
+            let auth = resolve_auth(db, &client, &c, false).await?;
+            let (url, headers) = auth.apply(&conn.url, &headers);
+            (headers, url)
+        }
+        None => (headers, conn.url.clone()),
+    };
+
+    let mut playlist_url = reqwest::Url::parse(&url)?;
+    let resp = send_request(&client, playlist_url.as_str(), &headers).await?.error_for_status()?;
+    let status = resp.status().as_u16() as i32;
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+    let mut playlist = parse_playlist(&resp.text().await?);
+
+    // Master playlists are followed to their first variant.
+    if playlist.segments.is_empty()
+        && let Some(variant) = playlist.variants.first()
+    {
+        playlist_url = playlist_url.join(variant)?;
+        let resp = send_request(&client, playlist_url.as_str(), &headers).await?.error_for_status()?;
+        playlist = parse_playlist(&resp.text().await?);
+    }
+
+    let last_sequence = playlist.segments.last().map(|s| s.sequence);
+    let new_segments = select_new_segments(&playlist.segments, after_sequence);
+
+    let mut results = Vec::new();
+    for segment in new_segments {
+        let mut id3 = Map::new();
+        if let Ok(segment_url) = playlist_url.join(&segment.uri) {
+            let mut segment_headers = headers.clone();
+            segment_headers.insert("Range".to_string(), SEGMENT_RANGE.to_string());
+            match send_request(&client, segment_url.as_str(), &segment_headers).await {
+                Ok(resp) if resp.status().is_success() => {
+                    let bytes = resp.bytes().await?;
+                    for tag in segment_id3_tags(&bytes) {
+                        id3.extend(parse_id3_frames(&tag));
+                    }
+                }
+                Ok(resp) => {
+                    tracing::warn!(connection_id = %conn.id, "HLS segment {} returned {}", segment.uri, resp.status());
+                }
+                Err(e) => {
+                    tracing::warn!(connection_id = %conn.id, "HLS segment {} failed: {:?}", segment.uri, e);
+                }
+            }
+        }
+
+        let Some(raw_payload) = segment_payload(segment, id3) else {
+            continue;
+        };
+        let fields = extract_fields(&raw_payload, mapping, &conn.connection_type);
+        results.push(FetchResult {
+            status,
+            content_type: content_type.clone(),
+            raw_payload,
+            reported_artist: fields.artist,
+            reported_title: fields.title,
+            reported_album: fields.album,
+            reported_at: segment.program_date_time.or(fields.reported_at),
+            reported_at_error: fields.reported_at_error.filter(|_| segment.program_date_time.is_none()),
+            reported_duration_seconds: fields.duration_seconds,
+            extended: fields.extended,
+            mapping_version_id: mapping.and_then(|m| m.current_version_id),
+        });
+    }
+
+    Ok(HlsPoll {
+        results,
+        last_sequence,
+    })
+}
+
+// Only segments after the stored position are new. On the first poll, or when
+// the sequence has reset (stream restart), start from the newest segment rather
+// than replaying the whole window.
+fn select_new_segments(segments: &[Segment], after_sequence: Option<i64>) -> Vec<&Segment> {
+    let last = segments.last().map(|s| s.sequence);
+    let new: Vec<&Segment> = match (after_sequence, last) {
+        (Some(after), Some(last)) if last >= after => segments.iter().filter(|s| s.sequence > after).collect(),
+        _ => segments.last().into_iter().collect(),
+    };
+    let skip = new.len().saturating_sub(MAX_NEW_SEGMENTS);
+    new.into_iter().skip(skip).collect()
+}
+
+pub fn parse_playlist(text: &str) -> Playlist {
+    let mut playlist = Playlist::default();
+    let mut sequence = 0i64;
+    let mut duration: Option<f64> = None;
+    let mut title: Option<String> = None;
+    let mut program_date_time: Option<DateTime<FixedOffset>> = None;
+    let mut dateranges: Vec<HashMap<String, String>> = Vec::new();
+    let mut next_is_variant = false;
+    // Segments after the last explicit PROGRAM-DATE-TIME are timed by adding
+    // the preceding segment durations.
+    let mut running_time: Option<DateTime<FixedOffset>> = None;
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            sequence = value.trim().parse().unwrap_or(0);
+        } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+            let (dur, rest) = value.split_once(',').unwrap_or((value, ""));
+            duration = dur.trim().parse().ok();
+            title = Some(rest.trim().to_string()).filter(|t| !t.is_empty());
+        } else if let Some(value) = line.strip_prefix("#EXT-X-PROGRAM-DATE-TIME:") {
+            program_date_time = parse_program_date_time(value.trim());
+        } else if let Some(value) = line.strip_prefix("#EXT-X-DATERANGE:") {
+            dateranges.push(parse_attribute_list(value));
+        } else if line.starts_with("#EXT-X-STREAM-INF") {
+            next_is_variant = true;
+        } else if line.starts_with('#') {
+            continue;
+        } else if next_is_variant {
+            playlist.variants.push(line.to_string());
+            next_is_variant = false;
+        } else {
+            let segment_duration = duration.take().unwrap_or(0.0);
+            let segment_time = program_date_time.take().or(running_time);
+            running_time = segment_time
+                .map(|t| t + chrono::Duration::milliseconds((segment_duration * 1000.0).round() as i64));
+            playlist.segments.push(Segment {
+                sequence,
+                uri: line.to_string(),
+                duration: segment_duration,
+                title: title.take(),
+                program_date_time: segment_time,
+                dateranges: std::mem::take(&mut dateranges),
+            });
+            sequence += 1;
+        }
+    }
+
+    playlist
+}
+
+fn parse_program_date_time(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .or_else(|| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z").ok())
+}
+
+// HLS attribute lists: `KEY=VALUE,KEY="quoted, value"`.
+pub fn parse_attribute_list(input: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        let Some((key, after_key)) = rest.split_once('=') else {
+            break;
+        };
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let (value, remaining) = if let Some(quoted) = after_key.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match after_key.find(',') {
+                Some(end) => (&after_key[..end], &after_key[end..]),
+                None => (after_key, ""),
+            }
+        };
+        if !key.is_empty() {
+            attrs.insert(key, value.trim().to_string());
+        }
+        rest = remaining.trim_start_matches(',').trim_start();
+    }
+
+    attrs
+}
+
+fn segment_payload(segment: &Segment, id3: Map<String, Value>) -> Option<Value> {
+    // EXTINF titles are either free text ("Artist - Title") or attribute lists
+    // (`title="Song",artist="Artist",url="..."`) depending on the vendor.
+    let extinf_attrs: HashMap<String, String> = segment
+        .title
+        .as_deref()
+        .filter(|t| t.contains("=\""))
+        .map(parse_attribute_list)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| (k.to_ascii_lowercase(), v))
+        .collect();
+
+    if id3.is_empty() && segment.title.is_none() && segment.dateranges.is_empty() {
+        return None;
+    }
+
+    let daterange_value = |key: &str| {
+        segment
+            .dateranges
+            .iter()
+            .find_map(|d| d.get(key).filter(|v| !v.is_empty()).cloned())
+    };
+    let id3_value = |key: &str| id3.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let split_title = segment
+        .title
+        .as_deref()
+        .filter(|_| extinf_attrs.is_empty())
+        .and_then(|t| t.split_once(" - "))
+        .map(|(a, t)| (a.trim().to_string(), t.trim().to_string()));
+
+    let artist = id3_value("TPE1")
+        .or_else(|| extinf_attrs.get("artist").cloned())
+        .or_else(|| daterange_value("X-ARTIST"))
+        .or_else(|| split_title.as_ref().map(|(a, _)| a.clone()));
+    let title = id3_value("TIT2")
+        .or_else(|| extinf_attrs.get("title").cloned())
+        .or_else(|| daterange_value("X-TITLE"))
+        .or_else(|| split_title.as_ref().map(|(_, t)| t.clone()))
+        .or_else(|| segment.title.clone().filter(|_| extinf_attrs.is_empty()));
+    let album = id3_value("TALB");
+
+    let mut payload = json!({
+        "media_sequence": segment.sequence,
+        "segment": segment.uri,
+        "segment_duration": segment.duration,
+        "program_date_time": segment.program_date_time.map(|t| t.to_rfc3339()),
+        "extinf": {
+            "title": segment.title,
+        },
+        "daterange": segment.dateranges,
+        "id3": id3,
+        "artist": artist,
+        "title": title,
+        "album": album,
+    });
+    if let Some(extinf) = payload.get_mut("extinf").and_then(|v| v.as_object_mut()) {
+        for (k, v) in extinf_attrs {
+            extinf.entry(k).or_insert(Value::String(v));
+        }
+    }
+
+    Some(payload)
+}
+
+// Returns raw ID3 tags found in a segment: the timed-metadata PES packets of an
+// MPEG-TS segment, or the leading tag of a packed-audio (AAC/MP3) segment.
+pub fn segment_id3_tags(data: &[u8]) -> Vec<Vec<u8>> {
+    if data.starts_with(b"ID3") {
+        return id3_tag_len(data)
+            .map(|len| vec![data[..len.min(data.len())].to_vec()])
+            .unwrap_or_default();
+    }
+    if data.first() != Some(&0x47) {
+        return Vec::new();
+    }
+
+    let mut pmt_pids: HashSet<u16> = HashSet::new();
+    let mut metadata_pids: HashSet<u16> = HashSet::new();
+    let mut pes: HashMap<u16, Vec<u8>> = HashMap::new();
+    let mut tags = Vec::new();
+
+    for packet in data.chunks_exact(TS_PACKET_SIZE) {
+        if packet[0] != 0x47 {
+            continue;
+        }
+        let payload_start = packet[1] & 0x40 != 0;
+        let pid = (u16::from(packet[1] & 0x1f) << 8) | u16::from(packet[2]);
+        let adaptation = (packet[3] >> 4) & 0x03;
+        if adaptation & 0x01 == 0 {
+            continue;
+        }
+        let offset = if adaptation == 0x03 { 5 + packet[4] as usize } else { 4 };
+        let Some(payload) = packet.get(offset..) else {
+            continue;
+        };
+
+        if pid == 0 && payload_start {
+            pmt_pids.extend(parse_pat(payload));
+        } else if pmt_pids.contains(&pid) && payload_start {
+            metadata_pids.extend(parse_pmt(payload));
+        } else if metadata_pids.contains(&pid) {
+            if payload_start {
+                if let Some(prev) = pes.remove(&pid) {
+                    tags.extend(pes_payload(&prev));
+                }
+                pes.insert(pid, payload.to_vec());
+            } else if let Some(buf) = pes.get_mut(&pid) {
+                buf.extend_from_slice(payload);
+            }
+        }
+    }
+
+    for buf in pes.into_values() {
+        tags.extend(pes_payload(&buf));
+    }
+
+    tags.into_iter().filter(|t| t.starts_with(b"ID3")).collect()
+}
+
+fn psi_section(payload: &[u8]) -> Option<&[u8]> {
+    let pointer = *payload.first()? as usize;
+    let section = payload.get(1 + pointer..)?;
+    let length = ((usize::from(*section.get(1)? & 0x0f)) << 8) | usize::from(*section.get(2)?);
+    // Drop the trailing CRC32.
+    section.get(..(3 + length).checked_sub(4)?)
+}
+
+fn parse_pat(payload: &[u8]) -> Vec<u16> {
+    let Some(section) = psi_section(payload) else {
+        return Vec::new();
+    };
+    section
+        .get(8..)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .filter(|entry| entry[0] != 0 || entry[1] != 0)
+        .map(|entry| (u16::from(entry[2] & 0x1f) << 8) | u16::from(entry[3]))
+        .collect()
+}
+
+fn parse_pmt(payload: &[u8]) -> Vec<u16> {
+    let mut pids = Vec::new();
+    let Some(section) = psi_section(payload) else {
+        return pids;
+    };
+    let Some(info_len) = section
+        .get(10..12)
+        .map(|b| (usize::from(b[0] & 0x0f) << 8) | usize::from(b[1]))
+    else {
+        return pids;
+    };
+
+    let mut i = 12 + info_len;
+    while let Some(es) = section.get(i..i + 5) {
+        let stream_type = es[0];
+        let pid = (u16::from(es[1] & 0x1f) << 8) | u16::from(es[2]);
+        let es_info_len = (usize::from(es[3] & 0x0f) << 8) | usize::from(es[4]);
+        if stream_type == TS_STREAM_TYPE_METADATA {
+            pids.push(pid);
+        }
+        i += 5 + es_info_len;
+    }
+    pids
+}
+
+fn pes_payload(pes: &[u8]) -> Option<Vec<u8>> {
+    if pes.get(..3)? != [0, 0, 1] {
+        return None;
+    }
+    let header_len = *pes.get(8)? as usize;
+    pes.get(9 + header_len..).map(|p| p.to_vec())
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0usize, |acc, b| (acc << 7) | usize::from(b & 0x7f))
+}
+
+fn id3_tag_len(data: &[u8]) -> Option<usize> {
+    let header = data.get(..10)?;
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + syncsafe(&header[6..10]) + footer)
+}
+
+// ID3v2.3/v2.4 text frames (`T***`) keyed by frame id, and `TXXX` frames keyed
+// as `TXXX:<description>`. Binary frames such as PRIV and APIC are skipped.
+pub fn parse_id3_frames(tag: &[u8]) -> Map<String, Value> {
+    let mut frames = Map::new();
+    let Some(header) = tag.get(..10) else {
+        return frames;
+    };
+    let version = header[3];
+    if &header[..3] != b"ID3" || !(3..=4).contains(&version) {
+        return frames;
+    }
+
+    let end = id3_tag_len(tag).unwrap_or(0).min(tag.len());
+    let mut pos = 10;
+    if header[5] & 0x40 != 0 {
+        let Some(ext) = tag.get(10..14) else {
+            return frames;
+        };
+        pos += if version == 4 {
+            syncsafe(ext)
+        } else {
+            4 + u32::from_be_bytes([ext[0], ext[1], ext[2], ext[3]]) as usize
+        };
+    }
+
+    while pos + 10 <= end {
+        let frame_header = &tag[pos..pos + 10];
+        if frame_header[0] == 0 {
+            break;
+        }
+        let id = String::from_utf8_lossy(&frame_header[..4]).to_string();
+        let size = if version == 4 {
+            syncsafe(&frame_header[4..8])
+        } else {
+            u32::from_be_bytes([frame_header[4], frame_header[5], frame_header[6], frame_header[7]]) as usize
+        };
+        let Some(body) = tag.get(pos + 10..pos + 10 + size) else {
+            break;
+        };
+        pos += 10 + size;
+
+        let Some((&encoding, text)) = body.split_first() else {
+            continue;
+        };
+        if id == "TXXX" {
+            let (description, value) = split_id3_terminated(encoding, text);
+            frames.insert(
+                format!("TXXX:{}", decode_id3_text(encoding, description)),
+                Value::String(decode_id3_text(encoding, value)),
+            );
+        } else if id.starts_with('T') {
+            frames.insert(id, Value::String(decode_id3_text(encoding, text)));
+        }
+    }
+
+    frames
+}
+
+fn split_id3_terminated(encoding: u8, data: &[u8]) -> (&[u8], &[u8]) {
+    let wide = matches!(encoding, 1 | 2);
+    let terminator = if wide {
+        data.chunks_exact(2).position(|c| c == [0, 0]).map(|i| (i * 2, 2))
+    } else {
+        data.iter().position(|b| *b == 0).map(|i| (i, 1))
+    };
+    match terminator {
+        Some((idx, len)) => (&data[..idx], &data[idx + len..]),
+        None => (data, &[]),
+    }
+}
+
+fn decode_id3_text(encoding: u8, data: &[u8]) -> String {
+    let text = match encoding {
+        0 => data.iter().map(|&b| b as char).collect(),
+        1 | 2 => {
+            let (data, big_endian) = match data {
+                [0xfe, 0xff, rest @ ..] => (rest, true),
+                [0xff, 0xfe, rest @ ..] => (rest, false),
+                _ => (data, encoding == 2),
+            };
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|c| if big_endian { u16::from_be_bytes([c[0], c[1]]) } else { u16::from_le_bytes([c[0], c[1]]) })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(data).to_string(),
+    };
+    // v2.4 separates multiple values with NULs.
+    text.split('\0')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_conn;
+
+    fn id3_tag(frames: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (id, data) in frames {
+            body.extend_from_slice(id.as_bytes());
+            body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            body.extend_from_slice(&[0, 0]);
+            body.extend_from_slice(data);
+        }
+        let size = body.len();
+        let mut tag = b"ID3\x03\x00\x00".to_vec();
+        tag.extend_from_slice(&[
+            ((size >> 21) & 0x7f) as u8,
+            ((size >> 14) & 0x7f) as u8,
+            ((size >> 7) & 0x7f) as u8,
+            (size & 0x7f) as u8,
+        ]);
+        tag.extend(body);
+        tag
+    }
+
+    fn ts_packet(pid: u16, payload_start: bool, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![
+            0x47,
+            (if payload_start { 0x40 } else { 0 }) | ((pid >> 8) as u8 & 0x1f),
+            (pid & 0xff) as u8,
+            0x10,
+        ];
+        packet.extend_from_slice(payload);
+        packet.resize(TS_PACKET_SIZE, 0xff);
+        packet
+    }
+
+    #[test]
+    fn parses_media_playlist_with_program_date_time_and_dateranges() {
+        let playlist = parse_playlist(
+            "#EXTM3U\n\
+             #EXT-X-MEDIA-SEQUENCE:1200\n\
+             #EXT-X-PROGRAM-DATE-TIME:2026-10-17T14:03:20.000Z\n\
+             #EXTINF:10.0,title=\"Free Fallin'\",artist=\"Tom Petty\"\n\
+             seg1200.ts\n\
+             #EXT-X-DATERANGE:ID=\"song-1\",START-DATE=\"2026-10-17T14:03:30Z\",X-TITLE=\"T.N.T.\",X-ARTIST=\"AC/DC\"\n\
+             #EXTINF:10.0,\n\
+             seg1201.ts\n",
+        );
+
+        assert_eq!(playlist.segments.len(), 2);
+        let first = &playlist.segments[0];
+        assert_eq!(first.sequence, 1200);
+        assert_eq!(first.program_date_time.unwrap().to_rfc3339(), "2026-10-17T14:03:20+00:00");
+
+        let second = &playlist.segments[1];
+        assert_eq!(second.sequence, 1201);
+        assert_eq!(second.program_date_time.unwrap().to_rfc3339(), "2026-10-17T14:03:30+00:00");
+        assert_eq!(second.dateranges[0].get("X-ARTIST").map(String::as_str), Some("AC/DC"));
+
+        let payload = segment_payload(first, Map::new()).unwrap();
+        assert_eq!(payload["artist"], "Tom Petty");
+        assert_eq!(payload["title"], "Free Fallin'");
+        let payload = segment_payload(second, Map::new()).unwrap();
+        assert_eq!(payload["title"], "T.N.T.");
+    }
+
+    #[test]
+    fn selects_only_segments_after_stored_sequence() {
+        let segments: Vec<Segment> = (10..15)
+            .map(|sequence| Segment { sequence, ..Default::default() })
+            .collect();
+        let seqs = |after| select_new_segments(&segments, after).iter().map(|s| s.sequence).collect::<Vec<_>>();
+
+        assert_eq!(seqs(Some(12)), vec![13, 14]);
+        assert_eq!(seqs(Some(14)), Vec::<i64>::new());
+        assert_eq!(seqs(None), vec![14]);
+        // Sequence reset after a stream restart.
+        assert_eq!(seqs(Some(500)), vec![14]);
+    }
+
+    #[test]
+    fn parses_id3_text_and_txxx_frames() {
+        let mut utf16_title = vec![1, 0xff, 0xfe];
+        for unit in "Café".encode_utf16() {
+            utf16_title.extend_from_slice(&unit.to_le_bytes());
+        }
+        let tag = id3_tag(&[
+            ("TPE1", b"\x03Tom Petty".to_vec()),
+            ("TIT2", utf16_title),
+            ("TXXX", b"\x00ISRC\x00USMC18900011".to_vec()),
+            ("PRIV", b"com.apple.streaming.transportStreamTimestamp\x00\x00".to_vec()),
+        ]);
+
+        let frames = parse_id3_frames(&tag);
+        assert_eq!(frames["TPE1"], "Tom Petty");
+        assert_eq!(frames["TIT2"], "Café");
+        assert_eq!(frames["TXXX:ISRC"], "USMC18900011");
+        assert!(!frames.contains_key("PRIV"));
+    }
+
+    #[test]
+    fn extracts_id3_from_ts_metadata_stream_and_skips_audio() {
+        let tag = id3_tag(&[("TPE1", b"\x03AC/DC".to_vec()), ("TIT2", b"\x03T.N.T.".to_vec())]);
+
+        let pat = [0x00, 0x00, 0xb0, 0x0d, 0x00, 0x01, 0xc1, 0x00, 0x00, 0x00, 0x01, 0xf0, 0x00, 0, 0, 0, 0];
+        let pmt = [
+            0x00, 0x02, 0xb0, 0x17, 0x00, 0x01, 0xc1, 0x00, 0x00, 0xe1, 0x00, 0xf0, 0x00,
+            0x0f, 0xe1, 0x00, 0xf0, 0x00, // AAC audio on PID 0x100
+            0x15, 0xe1, 0x02, 0xf0, 0x00, // timed ID3 on PID 0x102
+            0, 0, 0, 0,
+        ];
+        let mut pes = vec![0x00, 0x00, 0x01, 0xbd, 0x00, 0x00, 0x84, 0x80, 0x05, 0x21, 0x00, 0x01, 0x00, 0x01];
+        pes.extend_from_slice(&tag);
+
+        let mut segment = Vec::new();
+        segment.extend(ts_packet(0, true, &pat));
+        segment.extend(ts_packet(0x1000, true, &pmt));
+        segment.extend(ts_packet(0x100, true, b"\x00\x00\x01\xc0audio-not-id3"));
+        segment.extend(ts_packet(0x102, true, &pes));
+
+        let tags = segment_id3_tags(&segment);
+        assert_eq!(tags.len(), 1);
+        let frames = parse_id3_frames(&tags[0]);
+        assert_eq!(frames["TPE1"], "AC/DC");
+        assert_eq!(frames["TIT2"], "T.N.T.");
+    }
+
+    #[tokio::test]
+    async fn follows_master_playlist_from_local_server() {
+        use axum::{routing::get, Router};
+
+        let mut segment = id3_tag(&[("TPE1", b"\x03Tom Petty".to_vec()), ("TIT2", b"\x03Free Fallin'".to_vec())]);
+        segment.extend_from_slice(&[0xff, 0xf1, 0x50, 0x80]);
+        let app = Router::new()
+            .route("/live/master.m3u8", get(|| async { "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=64000\naudio/index.m3u8\n" }))
+            .route(
+                "/live/audio/index.m3u8",
+                get(|| async {
+                    "#EXTM3U\n#EXT-X-MEDIA-SEQUENCE:7\n#EXT-X-PROGRAM-DATE-TIME:2026-10-17T14:03:20Z\n\
+                     #EXTINF:6.0,\nseg7.aac\n#EXTINF:6.0,\nseg8.aac\n"
+                }),
+            )
+            .route("/live/audio/seg7.aac", get(|| async { Vec::<u8>::new() }))
+            .route("/live/audio/seg8.aac", get(move || async move { segment }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let conn = now_playing_connections::Model {
+            name: "hls".to_string(),
+            url: format!("http://{}/live/master.m3u8", addr),
+            poll_interval_seconds: 10,
+            ..test_conn("hls_id3")
+        };
+
+        let db = DatabaseConnection::default();
+        let poll = fetch_hls_results(&db, &conn, None, Some(6)).await.unwrap();
+        assert_eq!(poll.last_sequence, Some(8));
+        assert_eq!(poll.results.len(), 1);
+        let result = &poll.results[0];
+        assert_eq!(result.reported_artist.as_deref(), Some("Tom Petty"));
+        assert_eq!(result.reported_title.as_deref(), Some("Free Fallin'"));
+        assert_eq!(result.reported_at.unwrap().to_rfc3339(), "2026-10-17T14:03:26+00:00");
+        assert_eq!(result.raw_payload["media_sequence"], 8);
+    }
+
+    #[test]
+    fn reads_leading_id3_tag_from_packed_audio_segment() {
+        let mut segment = id3_tag(&[("TIT2", b"\x03Free Fallin'".to_vec())]);
+        segment.extend_from_slice(&[0xff, 0xf1, 0x50, 0x80]);
+        let tags = segment_id3_tags(&segment);
+        assert_eq!(parse_id3_frames(&tags[0])["TIT2"], "Free Fallin'");
+    }
+}
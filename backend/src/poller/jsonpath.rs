@@ -0,0 +1,395 @@
+use serde_json::Value;
+
+// Path expressions used by payload mappings. Plain dot-paths (`now.artist`)
+// keep their meaning; on top of that the usual JSONPath forms are accepted:
+//
+//   $.data.tracks[0].artist      array index (`tracks.0.artist` works too)
+//   items[-1].title              negative index from the end
+//   items[1:3], artists[*].name  slices and wildcards
+//   items[?(@.type=='song')]     filters with ==, !=, <, <=, >, >=, && and ||
+//   $..title                     recursive descent
+//   ['key.with.dots']            quoted keys
+//
+// A leading `$` is only treated as the root when followed by `.`, `[` or
+// nothing, so XML-derived keys such as `$value` still resolve.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Key(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+    Filter(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    recursive: bool,
+    selector: Selector,
+}
+
+pub fn select<'a>(value: &'a Value, expr: &str) -> Vec<&'a Value> {
+    let Some(segments) = parse(expr) else {
+        return Vec::new();
+    };
+
+    let mut current = vec![value];
+    for segment in &segments {
+        let mut next = Vec::new();
+        for node in current {
+            if segment.recursive {
+                let mut nodes = Vec::new();
+                descendants(node, &mut nodes);
+                for n in nodes {
+                    apply(n, &segment.selector, &mut next);
+                }
+            } else {
+                apply(node, &segment.selector, &mut next);
+            }
+        }
+        if next.is_empty() {
+            return next;
+        }
+        current = next;
+    }
+    current
+}
+
+pub fn select_first<'a>(value: &'a Value, expr: &str) -> Option<&'a Value> {
+    select(value, expr).into_iter().next()
+}
+
+fn apply<'a>(node: &'a Value, selector: &Selector, out: &mut Vec<&'a Value>) {
+    match selector {
+        Selector::Key(key) => match node {
+            Value::Object(obj) => out.extend(obj.get(key)),
+            // Dot-path style indexes: `items.0.title`.
+            Value::Array(arr) => {
+                if let Ok(index) = key.parse::<i64>() {
+                    out.extend(index_of(arr, index));
+                }
+            }
+            _ => {}
+        },
+        Selector::Index(index) => {
+            if let Value::Array(arr) = node {
+                out.extend(index_of(arr, *index));
+            }
+        }
+        Selector::Slice(start, end) => {
+            if let Value::Array(arr) = node {
+                let len = arr.len() as i64;
+                let clamp = |i: i64| if i < 0 { (len + i).max(0) } else { i.min(len) } as usize;
+                let start = clamp(start.unwrap_or(0));
+                let end = clamp(end.unwrap_or(len));
+                if start < end {
+                    out.extend(arr[start..end].iter());
+                }
+            }
+        }
+        Selector::Wildcard => out.extend(children(node)),
+        Selector::Filter(predicate) => {
+            out.extend(children(node).into_iter().filter(|child| matches_predicate(child, predicate)));
+        }
+    }
+}
+
+fn index_of(arr: &[Value], index: i64) -> Option<&Value> {
+    let len = arr.len() as i64;
+    let resolved = if index < 0 { len + index } else { index };
+    if (0..len).contains(&resolved) {
+        arr.get(resolved as usize)
+    } else {
+        None
+    }
+}
+
+fn children(node: &Value) -> Vec<&Value> {
+    match node {
+        Value::Object(obj) => obj.values().collect(),
+        Value::Array(arr) => arr.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn descendants<'a>(node: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(node);
+    for child in children(node) {
+        descendants(child, out);
+    }
+}
+
+fn parse(expr: &str) -> Option<Vec<Segment>> {
+    let expr = expr.trim();
+    let chars: Vec<char> = expr.chars().collect();
+    let mut i = 0;
+    if chars.first() == Some(&'$') && matches!(chars.get(1), None | Some('.') | Some('[')) {
+        i = 1;
+    }
+
+    let mut segments = Vec::new();
+    let mut recursive = false;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                if chars.get(i + 1) == Some(&'.') {
+                    recursive = true;
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+            }
+            '[' => {
+                let end = closing_bracket(&chars, i)?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                segments.push(Segment {
+                    recursive,
+                    selector: parse_bracket(inner.trim())?,
+                });
+                recursive = false;
+                i = end + 1;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                    i += 1;
+                }
+                let key: String = chars[start..i].iter().collect();
+                let selector = if key == "*" { Selector::Wildcard } else { Selector::Key(key) };
+                segments.push(Segment { recursive, selector });
+                recursive = false;
+            }
+        }
+    }
+    Some(segments)
+}
+
+fn closing_bracket(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    for (offset, c) in chars[open..].iter().enumerate() {
+        match (quote, c) {
+            (Some(q), c) if *c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(*c),
+            (None, '[') => depth += 1,
+            (None, ']') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + offset);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_bracket(inner: &str) -> Option<Selector> {
+    if inner == "*" {
+        return Some(Selector::Wildcard);
+    }
+    if let Some(predicate) = inner.strip_prefix('?') {
+        let predicate = predicate.trim();
+        let predicate = predicate
+            .strip_prefix('(')
+            .and_then(|p| p.strip_suffix(')'))
+            .unwrap_or(predicate);
+        return Some(Selector::Filter(predicate.to_string()));
+    }
+    if let Some(key) = unquote(inner) {
+        return Some(Selector::Key(key));
+    }
+    if let Some((start, end)) = inner.split_once(':') {
+        let bound = |s: &str| {
+            let s = s.trim();
+            if s.is_empty() { Some(None) } else { s.parse().ok().map(Some) }
+        };
+        return Some(Selector::Slice(bound(start)?, bound(end)?));
+    }
+    inner.parse().ok().map(Selector::Index)
+}
+
+fn unquote(s: &str) -> Option<String> {
+    let s = s.trim();
+    if s.len() >= 2
+        && ((s.starts_with('\'') && s.ends_with('\'')) || (s.starts_with('"') && s.ends_with('"')))
+    {
+        Some(s[1..s.len() - 1].to_string())
+    } else {
+        None
+    }
+}
+
+fn matches_predicate(node: &Value, predicate: &str) -> bool {
+    split_outside_quotes(predicate, "||")
+        .iter()
+        .any(|any| split_outside_quotes(any, "&&").iter().all(|c| matches_comparison(node, c)))
+}
+
+fn split_outside_quotes<'a>(s: &'a str, sep: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    let mut i = 0;
+    while i < s.len() {
+        let c = s[i..].chars().next().unwrap_or(' ');
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if s[i..].starts_with(sep) => {
+                parts.push(&s[start..i]);
+                i += sep.len();
+                start = i;
+                continue;
+            }
+            None => {}
+        }
+        i += c.len_utf8();
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+const OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+fn matches_comparison(node: &Value, comparison: &str) -> bool {
+    let comparison = comparison.trim();
+    let operator = find_operator(comparison);
+
+    let Some((op, at)) = operator else {
+        // Bare `@.field` tests for presence.
+        return operand(node, comparison).is_some_and(|v| !v.is_null());
+    };
+    let left = operand(node, &comparison[..at]);
+    let right = operand(node, &comparison[at + op.len()..]);
+    let (Some(left), Some(right)) = (left, right) else {
+        return op == "!=";
+    };
+
+    match op {
+        "==" => values_equal(&left, &right),
+        "!=" => !values_equal(&left, &right),
+        _ => match compare(&left, &right) {
+            Some(ordering) => match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            },
+            None => false,
+        },
+    }
+}
+
+fn find_operator(comparison: &str) -> Option<(&'static str, usize)> {
+    let mut quote: Option<char> = None;
+    for (i, c) in comparison.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None => {
+                if let Some(op) = OPERATORS.iter().find(|op| comparison[i..].starts_with(**op)) {
+                    return Some((op, i));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn operand(node: &Value, raw: &str) -> Option<Value> {
+    let raw = raw.trim();
+    if let Some(path) = raw.strip_prefix('@') {
+        return if path.is_empty() {
+            Some(node.clone())
+        } else {
+            select_first(node, &format!("${}", path)).cloned()
+        };
+    }
+    if let Some(text) = unquote(raw) {
+        return Some(Value::String(text));
+    }
+    serde_json::from_str(raw).ok()
+}
+
+// Feeds are inconsistent about quoting numbers, so "5" and 5 compare equal.
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (as_number(left), as_number(right)) {
+        (Some(l), Some(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<std::cmp::Ordering> {
+    match (as_number(left), as_number(right)) {
+        (Some(l), Some(r)) => l.partial_cmp(&r),
+        _ => match (left.as_str(), right.as_str()) {
+            (Some(l), Some(r)) => Some(l.cmp(r)),
+            _ => None,
+        },
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload() -> Value {
+        json!({
+            "data": {
+                "tracks": [
+                    { "type": "spot", "title": "Station ID", "duration": 10 },
+                    { "type": "song", "title": "Free Fallin'", "artist": "Tom Petty", "duration": 256 },
+                    { "type": "song", "title": "Runnin' Down a Dream", "performer": "Tom Petty" }
+                ]
+            },
+            "song": { "$value": "Hello", "key.with.dots": 1 }
+        })
+    }
+
+    #[test]
+    fn keeps_plain_dot_paths_and_xml_style_keys() {
+        let p = payload();
+        assert_eq!(select_first(&p, "song.$value"), Some(&json!("Hello")));
+        assert_eq!(select_first(&p, "data.tracks.1.title"), Some(&json!("Free Fallin'")));
+        assert_eq!(select_first(&p, "song['key.with.dots']"), Some(&json!(1)));
+        assert!(select_first(&p, "data.missing").is_none());
+    }
+
+    #[test]
+    fn supports_indexes_slices_and_wildcards() {
+        let p = payload();
+        assert_eq!(select_first(&p, "$.data.tracks[0].title"), Some(&json!("Station ID")));
+        assert_eq!(select_first(&p, "data.tracks[-1].performer"), Some(&json!("Tom Petty")));
+        assert_eq!(select(&p, "data.tracks[*].title").len(), 3);
+        assert_eq!(select(&p, "data.tracks[1:].type"), vec![&json!("song"), &json!("song")]);
+        assert_eq!(select(&p, "$..duration"), vec![&json!(10), &json!(256)]);
+    }
+
+    #[test]
+    fn filters_with_predicates() {
+        let p = payload();
+        assert_eq!(
+            select_first(&p, "data.tracks[?(@.type=='song')].title"),
+            Some(&json!("Free Fallin'"))
+        );
+        assert_eq!(
+            select(&p, "data.tracks[?(@.type == 'song' && @.duration > 100)].title"),
+            vec![&json!("Free Fallin'")]
+        );
+        assert_eq!(select(&p, "data.tracks[?(@.performer || @.duration < 20)]").len(), 2);
+        assert_eq!(select(&p, "data.tracks[?(@.duration == '10')].type"), vec![&json!("spot")]);
+    }
+}
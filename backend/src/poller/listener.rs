@@ -0,0 +1,346 @@
+use sea_orm::prelude::*;
+use crate::entities::{now_playing_connections, payload_mappings};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::task::JoinSet;
+use super::utils::{handle_raw_message, is_connection_enabled, load_connection_mapping, update_connection_status};
+
+// Playout systems push now-playing metadata to a fixed IP:port, either as a TCP
+// stream or as UDP datagrams. Framing comes from `headers_json.framing`
+// (`newline`, `null` or `xml`), the same place `ws_json` keeps its subscribe
+// settings.
+
+const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+const UDP_DATAGRAM_BYTES: usize = 65_535;
+// A feed that stays silent this long is dropped; playout systems reconnect.
+const READ_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+const MAX_TCP_STREAMS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    Newline,
+    Null,
+    Xml,
+}
+
+impl Framing {
+    fn from_connection(conn: &now_playing_connections::Model) -> Framing {
+        let framing = conn
+            .headers_json
+            .as_ref()
+            .and_then(|h| h.get("framing"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_ascii_lowercase());
+
+        match framing.as_deref() {
+            Some("null") | Some("nul") => Framing::Null,
+            Some("xml") => Framing::Xml,
+            _ => Framing::Newline,
+        }
+    }
+}
+
+pub struct MessageFramer {
+    framing: Framing,
+    buf: Vec<u8>,
+}
+
+impl MessageFramer {
+    pub fn new(framing: Framing) -> Self {
+        Self {
+            framing,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.buf.extend_from_slice(data);
+        let mut messages = Vec::new();
+
+        loop {
+            let boundary = match self.framing {
+                Framing::Newline => self.buf.iter().position(|b| *b == b'\n').map(|i| (i, i + 1)),
+                Framing::Null => self.buf.iter().position(|b| *b == 0).map(|i| (i, i + 1)),
+                Framing::Xml => xml_document_end(&self.buf).map(|i| (i, i)),
+            };
+
+            let Some((end, next)) = boundary else {
+                break;
+            };
+            let message: Vec<u8> = self.buf.drain(..next).take(end).collect();
+            if let Some(message) = clean_message(message) {
+                messages.push(message);
+            }
+        }
+
+        if self.buf.len() > MAX_MESSAGE_BYTES {
+            tracing::warn!("Discarding {} unframed bytes from socket listener", self.buf.len());
+            self.buf.clear();
+        }
+
+        messages
+    }
+
+    // Whatever is left when the peer closes (or a datagram ends) is treated as a
+    // final message, since many senders omit the trailing delimiter.
+    pub fn finish(&mut self) -> Option<Vec<u8>> {
+        clean_message(std::mem::take(&mut self.buf))
+    }
+}
+
+// Trims bytes rather than text so non-UTF-8 payloads reach charset detection intact.
+fn clean_message(message: Vec<u8>) -> Option<Vec<u8>> {
+    let is_padding = |b: &u8| b.is_ascii_whitespace() || *b == 0;
+    let start = message.iter().position(|b| !is_padding(b))?;
+    let end = message.iter().rposition(|b| !is_padding(b))? + 1;
+    Some(message[start..end].to_vec())
+}
+
+// Returns the byte offset just past the first complete root element, skipping
+// any XML declaration or leading whitespace/garbage before it.
+fn xml_document_end(buf: &[u8]) -> Option<usize> {
+    let start = buf.iter().position(|b| *b == b'<')?;
+    let mut reader = Reader::from_reader(&buf[start..]);
+    let mut depth = 0usize;
+    let mut event_buf = Vec::new();
+
+    loop {
+        let event = reader.read_event_into(&mut event_buf).ok()?;
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(start + reader.buffer_position() as usize);
+                }
+            }
+            Event::Empty(_) if depth == 0 => {
+                return Some(start + reader.buffer_position() as usize);
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+        event_buf.clear();
+    }
+}
+
+fn bind_address(url: &str) -> &str {
+    let addr = url.trim();
+    addr.strip_prefix("tcp://")
+        .or_else(|| addr.strip_prefix("udp://"))
+        .unwrap_or(addr)
+        .trim_end_matches('/')
+}
+
+pub async fn run_socket_listener(
+    db: DatabaseConnection,
+    conn: now_playing_connections::Model,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mapping = load_connection_mapping(&db, &conn).await?;
+
+    let framing = Framing::from_connection(&conn);
+    let addr = bind_address(&conn.url).to_string();
+    let is_udp = conn.connection_type.eq_ignore_ascii_case("udp_listen");
+    let mut backoff_seconds = 1u64;
+
+    loop {
+        if !is_connection_enabled(&db, conn.id).await? {
+            update_connection_status(&db, &conn, Some("DISABLED".to_string()), None).await?;
+            return Ok(());
+        }
+
+        let result = if is_udp {
+            listen_udp(&db, &conn, mapping.as_ref(), framing, &addr).await
+        } else {
+            listen_tcp(&db, &conn, mapping.clone(), framing, &addr).await
+        };
+
+        match result {
+            Ok(()) => {
+                update_connection_status(&db, &conn, Some("DISABLED".to_string()), None).await?;
+                return Ok(());
+            }
+            Err(e) => {
+                update_connection_status(&db, &conn, Some("LISTEN_ERROR".to_string()), Some(e.to_string())).await?;
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(backoff_seconds)).await;
+        backoff_seconds = (backoff_seconds * 2).min(60);
+    }
+}
+
+// Both listeners return Ok(()) once the connection is disabled and Err on
+// socket failures so the caller can report the error and rebind.
+async fn listen_tcp(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<payload_mappings::Model>,
+    framing: Framing,
+    addr: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let listener = TcpListener::bind(addr).await?;
+    update_connection_status(db, conn, Some("LISTENING".to_string()), None).await?;
+
+    let shared = Arc::new((db.clone(), conn.clone(), mapping));
+    let mut health_check = tokio::time::interval(Duration::from_secs(30));
+    // Dropping the set when the listener returns (disabled or failed) aborts
+    // every stream still being read.
+    let mut streams = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = accepted?;
+                if streams.len() >= MAX_TCP_STREAMS {
+                    tracing::warn!(connection_id = %conn.id, %peer, "Refusing metadata feed connection: {} streams open", streams.len());
+                    continue;
+                }
+                tracing::debug!(connection_id = %conn.id, %peer, "Accepted metadata feed connection");
+                streams.spawn(read_tcp_stream(shared.clone(), stream, peer, framing));
+            }
+            Some(_) = streams.join_next(), if !streams.is_empty() => {}
+            _ = health_check.tick() => {
+                if !is_connection_enabled(db, conn.id).await? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn read_tcp_stream(
+    shared: Arc<(DatabaseConnection, now_playing_connections::Model, Option<payload_mappings::Model>)>,
+    mut stream: TcpStream,
+    peer: std::net::SocketAddr,
+    framing: Framing,
+) {
+    let (db, conn, mapping) = &*shared;
+    let mut framer = MessageFramer::new(framing);
+    let mut buf = vec![0u8; 8192];
+    while let Some(n) = read_chunk(&mut stream, &mut buf, READ_IDLE_TIMEOUT).await {
+        for message in framer.push(&buf[..n]) {
+            if let Err(e) = handle_raw_message(db, conn, mapping.as_ref(), None, &message).await {
+                tracing::error!("Failed to store TCP message for {}: {:?}", conn.id, e);
+            }
+        }
+    }
+    tracing::debug!(connection_id = %conn.id, %peer, "Metadata feed connection closed");
+    if let Some(message) = framer.finish()
+        && let Err(e) = handle_raw_message(db, conn, mapping.as_ref(), None, &message).await
+    {
+        tracing::error!("Failed to store TCP message for {}: {:?}", conn.id, e);
+    }
+}
+
+// The next bytes from a feed; None once it closes, fails or goes quiet.
+async fn read_chunk<R: AsyncRead + Unpin>(stream: &mut R, buf: &mut [u8], idle_timeout: Duration) -> Option<usize> {
+    match tokio::time::timeout(idle_timeout, stream.read(buf)).await {
+        Ok(Ok(0)) => None,
+        Ok(Ok(n)) => Some(n),
+        Ok(Err(e)) => {
+            tracing::warn!("Metadata feed read failed: {:?}", e);
+            None
+        }
+        Err(_) => {
+            tracing::warn!("Metadata feed idle for {:?}, closing", idle_timeout);
+            None
+        }
+    }
+}
+
+async fn listen_udp(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    framing: Framing,
+    addr: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let socket = UdpSocket::bind(addr).await?;
+    update_connection_status(db, conn, Some("LISTENING".to_string()), None).await?;
+
+    let mut buf = vec![0u8; UDP_DATAGRAM_BYTES];
+    let mut health_check = tokio::time::interval(Duration::from_secs(30));
+
+    loop {
+        tokio::select! {
+            received = socket.recv_from(&mut buf) => {
+                let (n, _peer) = received?;
+                // Each datagram is self-contained; it may still carry several
+                // delimited messages.
+                let mut framer = MessageFramer::new(framing);
+                let mut messages = framer.push(&buf[..n]);
+                messages.extend(framer.finish());
+                for message in messages {
+                    if let Err(e) = handle_raw_message(db, conn, mapping, None, &message).await {
+                        tracing::warn!("Failed to store UDP message for {}: {:?}", conn.id, e);
+                    }
+                }
+            }
+            _ = health_check.tick() => {
+                if !is_connection_enabled(db, conn.id).await? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_newline_and_null_delimited_messages_across_reads() {
+        let mut framer = MessageFramer::new(Framing::Newline);
+        assert!(framer.push(b"Tom Petty - Free").is_empty());
+        let messages = framer.push(b" Fallin'\r\n\r\nAC/DC - T.N.T.\n");
+        assert_eq!(messages, vec![b"Tom Petty - Free Fallin'".to_vec(), b"AC/DC - T.N.T.".to_vec()]);
+
+        let mut framer = MessageFramer::new(Framing::Null);
+        let messages = framer.push(b"ARTIST=A|TITLE=B\0ARTIST=C");
+        assert_eq!(messages, vec![b"ARTIST=A|TITLE=B".to_vec()]);
+        assert_eq!(framer.finish(), Some(b"ARTIST=C".to_vec()));
+    }
+
+    #[test]
+    fn frames_xml_documents_on_root_element_boundary() {
+        let mut framer = MessageFramer::new(Framing::Xml);
+        let first = br#"<?xml version="1.0"?><NowPlaying><Artist>A</Artist>"#;
+        assert!(framer.push(first).is_empty());
+
+        let messages = framer.push(br#"<Title>B</Title></NowPlaying><Song artist="C"/><NowPl"#);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with(b"<?xml"));
+        assert!(messages[0].ends_with(b"</NowPlaying>"));
+        assert_eq!(messages[1], br#"<Song artist="C"/>"#.to_vec());
+
+        let messages = framer.push(b"aying><Artist>D</Artist></NowPlaying>");
+        assert_eq!(messages, vec![b"<NowPlaying><Artist>D</Artist></NowPlaying>".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn stops_reading_closed_or_silent_feeds() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let mut buf = [0u8; 16];
+        tokio::io::AsyncWriteExt::write_all(&mut client, b"ARTIST=A").await.unwrap();
+        assert_eq!(read_chunk(&mut server, &mut buf, Duration::from_secs(5)).await, Some(8));
+
+        // Nothing arrives within the idle timeout.
+        assert_eq!(read_chunk(&mut server, &mut buf, Duration::from_millis(20)).await, None);
+
+        drop(client);
+        assert_eq!(read_chunk(&mut server, &mut buf, Duration::from_secs(5)).await, None);
+    }
+
+    #[test]
+    fn strips_scheme_from_bind_address() {
+        assert_eq!(bind_address("tcp://0.0.0.0:5010"), "0.0.0.0:5010");
+        assert_eq!(bind_address("udp://127.0.0.1:5011/"), "127.0.0.1:5011");
+        assert_eq!(bind_address("0.0.0.0:5012"), "0.0.0.0:5012");
+    }
+}
@@ -6,15 +6,44 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+pub mod auth;
+pub mod chain;
+pub mod charset;
+pub mod classify;
+pub mod cookies;
+#[cfg(test)]
+pub mod fixtures;
+pub mod fingerprint;
+pub mod hls;
+pub mod jsonpath;
+pub mod listener;
+pub mod proxy;
+pub mod reextract;
+pub mod suggest;
+pub mod text;
+pub mod timestamps;
+pub mod transform;
 pub mod utils;
+pub mod validity;
+pub mod xpath;
 
 pub fn start_poller(db: DatabaseConnection) -> JoinHandle<()> {
     let active_ws_connections: Arc<Mutex<HashSet<Uuid>>> = Arc::new(Mutex::new(HashSet::new()));
     let active_ws_connections_clone = active_ws_connections.clone();
+    let active_socket_listeners: Arc<Mutex<HashSet<Uuid>>> = Arc::new(Mutex::new(HashSet::new()));
     tokio::spawn(async move {
         tracing::info!("Starting poller scheduler loop");
+        if let Err(e) = reextract::fail_interrupted_jobs(&db).await {
+            tracing::error!("Could not clean up re-extraction jobs: {:?}", e);
+        }
         loop {
-            if let Err(e) = poll_all_enabled(&db, active_ws_connections_clone.clone()).await {
+            if let Err(e) = poll_all_enabled(
+                &db,
+                active_ws_connections_clone.clone(),
+                active_socket_listeners.clone(),
+            )
+            .await
+            {
                 tracing::error!("Error in poller loop: {:?}", e);
             }
             tokio::time::sleep(Duration::from_secs(10)).await;
@@ -25,6 +54,7 @@ pub fn start_poller(db: DatabaseConnection) -> JoinHandle<()> {
 async fn poll_all_enabled(
     db: &DatabaseConnection,
     active_ws_connections: Arc<Mutex<HashSet<Uuid>>>,
+    active_socket_listeners: Arc<Mutex<HashSet<Uuid>>>,
 ) -> Result<(), DbErr> {
     let connections = now_playing_connections::Entity::find()
         .filter(now_playing_connections::Column::Enabled.eq(true))
@@ -37,6 +67,15 @@ async fn poll_all_enabled(
             continue;
         }
 
+        if utils::is_push_connection_type(&conn.connection_type) {
+            continue;
+        }
+
+        if utils::is_listener_connection_type(&conn.connection_type) {
+            ensure_socket_listener(db.clone(), conn.clone(), active_socket_listeners.clone()).await;
+            continue;
+        }
+
         let db = db.clone();
         tokio::spawn(async move {
             if should_poll(&conn) {
@@ -91,40 +130,35 @@ async fn ensure_ws_listener(
     });
 }
 
+async fn ensure_socket_listener(
+    db: DatabaseConnection,
+    conn: now_playing_connections::Model,
+    active_socket_listeners: Arc<Mutex<HashSet<Uuid>>>,
+) {
+    let mut active = active_socket_listeners.lock().await;
+    if active.contains(&conn.id) {
+        return;
+    }
+    active.insert(conn.id);
+    drop(active);
+
+    tokio::spawn(async move {
+        if let Err(e) = listener::run_socket_listener(db.clone(), conn.clone()).await {
+            tracing::error!("Socket listener {} failed: {:?}", conn.id, e);
+        }
+        let mut active = active_socket_listeners.lock().await;
+        active.remove(&conn.id);
+    });
+}
+
 #[cfg(test)]
 mod tests {
+    use super::fixtures::test_conn;
     use super::should_poll;
-    use crate::entities::now_playing_connections;
-    use chrono::{DateTime, FixedOffset};
-    use uuid::Uuid;
-
-    fn base_conn() -> now_playing_connections::Model {
-        let now: DateTime<FixedOffset> = chrono::Utc::now().fixed_offset();
-        now_playing_connections::Model {
-            id: Uuid::new_v4(),
-            station_id: Uuid::new_v4(),
-            payload_mapping_id: None,
-            name: "test".to_string(),
-            connection_type: "http_json".to_string(),
-            url: "http://example.com".to_string(),
-            poll_interval_seconds: 60,
-            headers_json: None,
-            enabled: true,
-            use_duration_polling: false,
-            last_polled_at: Some(now),
-            next_poll_at: None,
-            same_song_backoff_seconds: 0,
-            error_backoff_seconds: 0,
-            last_status: None,
-            last_error: None,
-            created_at: now,
-            updated_at: now,
-        }
-    }
 
     #[test]
     fn next_poll_at_takes_precedence_over_last_polled_at() {
-        let mut conn = base_conn();
+        let mut conn = test_conn("http_json");
         let now = chrono::Utc::now().fixed_offset();
 
         conn.last_polled_at = Some(now - chrono::Duration::seconds(10_000));
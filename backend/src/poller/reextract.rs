@@ -0,0 +1,433 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{prelude::*, PaginatorTrait, QueryOrder, Set};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use crate::entities::{now_playing_connections, payload_mappings, raw_now_playing_events, reextraction_jobs};
+use super::classify::{load_classifier, ContentClass};
+use super::fingerprint::{load_mapping_library, select_mapping};
+use super::utils::{
+    expected_end_at,
+    extract_fields,
+    ExtendedFields,
+    is_hls_connection_type,
+    is_listener_connection_type,
+    is_push_connection_type,
+    load_connection_mapping,
+    push_body_connection_type,
+    ParsedFields,
+};
+
+// Re-runs `extract_fields` over stored `raw_payload`s so a fixed mapping can
+// repair history. Each event is re-extracted with its connection's current
+// mapping version. Dry runs only produce the diff report; real runs also
+// update the derived columns and `mapping_version_id`.
+
+pub const JOB_PENDING: &str = "PENDING";
+pub const JOB_RUNNING: &str = "RUNNING";
+pub const JOB_COMPLETED: &str = "COMPLETED";
+pub const JOB_FAILED: &str = "FAILED";
+
+const BATCH_SIZE: u64 = 200;
+const MAX_REPORTED_CHANGES: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DerivedColumns {
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub reported_at: Option<DateTime<FixedOffset>>,
+    pub reported_at_error: Option<String>,
+    pub duration_seconds: Option<i64>,
+    pub expected_end_at: Option<DateTime<FixedOffset>>,
+    #[serde(flatten)]
+    pub extended: ExtendedFields,
+}
+
+impl DerivedColumns {
+    pub fn from_event(event: &raw_now_playing_events::Model) -> Self {
+        Self {
+            artist: event.reported_artist.clone(),
+            title: event.reported_title.clone(),
+            album: event.reported_album.clone(),
+            reported_at: event.reported_at,
+            reported_at_error: event.reported_at_error.clone(),
+            duration_seconds: event.reported_duration_seconds,
+            expected_end_at: event.expected_end_at,
+            extended: ExtendedFields {
+                isrc: event.isrc.clone(),
+                upc: event.upc.clone(),
+                artwork_url: event.artwork_url.clone(),
+                label: event.label.clone(),
+                composer: event.composer.clone(),
+                category: event.category.clone(),
+                item_type: event.item_type.clone(),
+                extra_fields: event.extra_fields.as_ref().and_then(|v| v.as_object().cloned()),
+            },
+        }
+    }
+
+    pub fn parsed_fields(&self) -> ParsedFields {
+        ParsedFields {
+            artist: self.artist.clone(),
+            title: self.title.clone(),
+            album: self.album.clone(),
+            reported_at: self.reported_at,
+            reported_at_error: self.reported_at_error.clone(),
+            duration_seconds: self.duration_seconds,
+            extended: self.extended.clone(),
+        }
+    }
+
+    // Fields with a value; extra fields count individually.
+    fn populated_count(&self) -> usize {
+        let extended = &self.extended;
+        [
+            self.artist.is_some(),
+            self.title.is_some(),
+            self.album.is_some(),
+            self.reported_at.is_some(),
+            self.duration_seconds.is_some(),
+            extended.isrc.is_some(),
+            extended.upc.is_some(),
+            extended.artwork_url.is_some(),
+            extended.label.is_some(),
+            extended.composer.is_some(),
+            extended.category.is_some(),
+            extended.item_type.is_some(),
+        ]
+        .into_iter()
+        .filter(|populated| *populated)
+        .count()
+            + extended.extra_fields.as_ref().map_or(0, |extras| extras.len())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionOutcome {
+    Improved,
+    Regressed,
+    Changed,
+    Unchanged,
+}
+
+// More populated fields is an improvement, fewer a regression; the same number
+// with different values is just a change. A new reported_at parse error
+// counts against the candidate.
+pub fn compare_extraction(stored: &DerivedColumns, candidate: &DerivedColumns) -> ExtractionOutcome {
+    if stored == candidate {
+        return ExtractionOutcome::Unchanged;
+    }
+    let score = |columns: &DerivedColumns| {
+        columns.populated_count() as i64 - i64::from(columns.reported_at_error.is_some())
+    };
+    match score(candidate).cmp(&score(stored)) {
+        std::cmp::Ordering::Greater => ExtractionOutcome::Improved,
+        std::cmp::Ordering::Less => ExtractionOutcome::Regressed,
+        std::cmp::Ordering::Equal => ExtractionOutcome::Changed,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventChange {
+    pub event_id: Uuid,
+    pub observed_at: DateTime<FixedOffset>,
+    pub before: DerivedColumns,
+    pub after: DerivedColumns,
+}
+
+type Source = Option<(now_playing_connections::Model, Option<payload_mappings::Model>)>;
+
+pub fn spawn_reextraction(db: DatabaseConnection, job: reextraction_jobs::Model) {
+    tokio::spawn(async move {
+        if let Err(e) = run_reextraction(&db, &job).await {
+            tracing::error!("Re-extraction job {} failed: {:?}", job.id, e);
+            let failed = reextraction_jobs::ActiveModel {
+                id: Set(job.id),
+                status: Set(JOB_FAILED.to_string()),
+                error: Set(Some(e.to_string())),
+                finished_at: Set(Some(Utc::now().fixed_offset())),
+                ..Default::default()
+            };
+            if let Err(e) = failed.update(&db).await {
+                tracing::error!("Could not mark re-extraction job {} failed: {:?}", job.id, e);
+            }
+        }
+    });
+}
+
+// Jobs run in-process, so anything still pending or running at startup was
+// cut off by a restart.
+pub async fn fail_interrupted_jobs(db: &DatabaseConnection) -> Result<(), DbErr> {
+    reextraction_jobs::Entity::update_many()
+        .col_expr(reextraction_jobs::Column::Status, Expr::value(JOB_FAILED))
+        .col_expr(reextraction_jobs::Column::Error, Expr::value("Interrupted by restart"))
+        .filter(reextraction_jobs::Column::Status.is_in([JOB_PENDING, JOB_RUNNING]))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+fn events_query(job: &reextraction_jobs::Model) -> Select<raw_now_playing_events::Entity> {
+    let mut query = raw_now_playing_events::Entity::find()
+        .filter(raw_now_playing_events::Column::StationId.eq(job.station_id));
+    if let Some(connection_id) = job.connection_id {
+        query = query.filter(raw_now_playing_events::Column::ConnectionId.eq(connection_id));
+    }
+    if let Some(from) = job.observed_from {
+        query = query.filter(raw_now_playing_events::Column::ObservedAt.gte(from));
+    }
+    if let Some(to) = job.observed_to {
+        query = query.filter(raw_now_playing_events::Column::ObservedAt.lt(to));
+    }
+    query
+        .order_by_asc(raw_now_playing_events::Column::ObservedAt)
+        .order_by_asc(raw_now_playing_events::Column::Id)
+}
+
+pub async fn run_reextraction(db: &DatabaseConnection, job: &reextraction_jobs::Model) -> Result<(), DbErr> {
+    let total = events_query(job).count(db).await?;
+    let mut progress: reextraction_jobs::ActiveModel = job.clone().into();
+    progress.status = Set(JOB_RUNNING.to_string());
+    progress.total_events = Set(total as i32);
+    progress.started_at = Set(Some(Utc::now().fixed_offset()));
+    let mut progress: reextraction_jobs::ActiveModel = progress.update(db).await?.into();
+
+    let mut sources: HashMap<Uuid, Source> = HashMap::new();
+    // Auto connections pick per event, so the library is loaded once per job.
+    let mut library: Option<Vec<payload_mappings::Model>> = None;
+    let classifier = load_classifier(db, job.station_id).await?;
+    let mut changes: Vec<EventChange> = Vec::new();
+    let mut processed = 0;
+    let mut changed = 0;
+
+    // Updates never touch the filter or ordering columns, so offset pages stay
+    // stable while rows are rewritten.
+    let mut pages = events_query(job).paginate(db, BATCH_SIZE);
+    while let Some(events) = pages.fetch_and_next().await? {
+        for event in events {
+            processed += 1;
+            if let Entry::Vacant(slot) = sources.entry(event.connection_id) {
+                slot.insert(load_source(db, event.connection_id).await?);
+            }
+            let Some((conn, mapping)) = sources.get(&event.connection_id).and_then(|s| s.as_ref()) else {
+                continue;
+            };
+            let mapping = if conn.auto_mapping {
+                if library.is_none() {
+                    library = Some(load_mapping_library(db, job.station_id).await?);
+                }
+                let library = library.as_deref().unwrap_or_default();
+                // Payloads no library mapping matches were never recorded
+                // with one, so they're left alone.
+                let Some(selected) = select_mapping(library, &event.raw_payload, event_connection_type(&event, conn))
+                else {
+                    continue;
+                };
+                Some(selected)
+            } else {
+                mapping.as_ref()
+            };
+
+            let before = DerivedColumns::from_event(&event);
+            let after = reextract_event(&event, conn, mapping);
+            let version_id = mapping.and_then(|m| m.current_version_id);
+            let is_changed = before != after;
+
+            if is_changed {
+                changed += 1;
+                if changes.len() < MAX_REPORTED_CHANGES {
+                    changes.push(EventChange {
+                        event_id: event.id,
+                        observed_at: event.observed_at,
+                        before,
+                        after: after.clone(),
+                    });
+                }
+            }
+            let classification = classifier.classify(&after.parsed_fields());
+            if should_rewrite(job.dry_run, &event, is_changed, classification.class, version_id) {
+                let mut active: raw_now_playing_events::ActiveModel = event.into();
+                active.reported_artist = Set(after.artist);
+                active.reported_title = Set(after.title);
+                active.reported_album = Set(after.album);
+                active.reported_at = Set(after.reported_at);
+                active.reported_at_error = Set(after.reported_at_error);
+                active.reported_duration_seconds = Set(after.duration_seconds);
+                active.expected_end_at = Set(after.expected_end_at);
+                active.isrc = Set(after.extended.isrc);
+                active.upc = Set(after.extended.upc);
+                active.artwork_url = Set(after.extended.artwork_url);
+                active.label = Set(after.extended.label);
+                active.composer = Set(after.extended.composer);
+                active.category = Set(after.extended.category);
+                active.item_type = Set(after.extended.item_type);
+                active.extra_fields = Set(after.extended.extra_fields.map(serde_json::Value::Object));
+                active.mapping_version_id = Set(version_id);
+                active.content_class = Set(Some(classification.class.as_str().to_string()));
+                active.content_class_reason = Set(Some(classification.reason));
+                active.update(db).await?;
+            }
+        }
+
+        progress.processed_events = Set(processed);
+        progress.changed_events = Set(changed);
+        progress = progress.update(db).await?.into();
+    }
+
+    progress.status = Set(JOB_COMPLETED.to_string());
+    progress.report_json = Set(Some(serde_json::json!({
+        "changes": changes,
+        "truncated": (changed as usize) > changes.len(),
+    })));
+    progress.finished_at = Set(Some(Utc::now().fixed_offset()));
+    progress.update(db).await?;
+    Ok(())
+}
+
+// Dry runs only report. Otherwise an event is rewritten when its fields or
+// class change, or to record the mapping version it now matches.
+fn should_rewrite(
+    dry_run: bool,
+    event: &raw_now_playing_events::Model,
+    is_changed: bool,
+    class: ContentClass,
+    version_id: Option<Uuid>,
+) -> bool {
+    let is_reclassified = event.content_class.as_deref() != Some(class.as_str());
+    !dry_run && (is_changed || is_reclassified || event.mapping_version_id != version_id)
+}
+
+async fn load_source(db: &DatabaseConnection, connection_id: Uuid) -> Result<Source, DbErr> {
+    let Some(conn) = now_playing_connections::Entity::find_by_id(connection_id).one(db).await? else {
+        return Ok(None);
+    };
+    let mapping = load_connection_mapping(db, &conn).await?;
+    Ok(Some((conn, mapping)))
+}
+
+// Pushed and listener bodies were sniffed per message at ingest.
+fn event_connection_type<'a>(
+    event: &raw_now_playing_events::Model,
+    conn: &'a now_playing_connections::Model,
+) -> &'a str {
+    match event.raw_payload.as_str() {
+        Some(body)
+            if is_push_connection_type(&conn.connection_type)
+                || is_listener_connection_type(&conn.connection_type) =>
+        {
+            push_body_connection_type(event.content_type.as_deref(), body.as_bytes())
+        }
+        _ => conn.connection_type.as_str(),
+    }
+}
+
+pub fn reextract_event(
+    event: &raw_now_playing_events::Model,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+) -> DerivedColumns {
+    let fields = extract_fields(&event.raw_payload, mapping, event_connection_type(event, conn));
+
+    // HLS start times come from the playlist, not the payload.
+    let (reported_at, reported_at_error) = match fields.reported_at {
+        None if is_hls_connection_type(&conn.connection_type) && event.reported_at.is_some() => {
+            (event.reported_at, None)
+        }
+        reported_at => (reported_at, fields.reported_at_error),
+    };
+
+    DerivedColumns {
+        artist: fields.artist,
+        title: fields.title,
+        album: fields.album,
+        reported_at,
+        reported_at_error,
+        duration_seconds: fields.duration_seconds,
+        expected_end_at: expected_end_at(reported_at, fields.duration_seconds),
+        extended: fields.extended,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::{test_conn, test_event, test_mapping};
+
+    fn event(conn: &now_playing_connections::Model, raw_payload: serde_json::Value) -> raw_now_playing_events::Model {
+        raw_now_playing_events::Model {
+            reported_artist: Some("HEART".to_string()),
+            ..test_event(conn, raw_payload)
+        }
+    }
+
+    fn mapping(mapping_json: serde_json::Value) -> payload_mappings::Model {
+        payload_mappings::Model {
+            current_version_id: Some(Uuid::new_v4()),
+            ..test_mapping(mapping_json)
+        }
+    }
+
+    #[test]
+    fn reextracts_with_the_current_mapping() {
+        let json_conn = test_conn("http_json");
+        let stored = event(&json_conn, serde_json::json!({ "artist": "HEART", "song": "Barracuda" }));
+        let fixed = mapping(serde_json::json!({
+            "artist_path": "artist",
+            "artist_transforms": [{ "op": "case", "mode": "title" }],
+            "title_path": "song"
+        }));
+        let after = reextract_event(&stored, &json_conn, Some(&fixed));
+        assert_eq!(after.artist.as_deref(), Some("Heart"));
+        assert_eq!(after.title.as_deref(), Some("Barracuda"));
+        assert_ne!(after, DerivedColumns::from_event(&stored));
+
+        // Pushed bodies are sniffed again rather than read as the `push` type.
+        let push_conn = test_conn("push");
+        let pushed = event(&push_conn, serde_json::json!("<np><artist>Boston</artist><title>Foreplay</title></np>"));
+        let xml = mapping(serde_json::json!({ "artist_path": "artist", "title_path": "title" }));
+        let after = reextract_event(&pushed, &push_conn, Some(&xml));
+        assert_eq!(after.artist.as_deref(), Some("Boston"));
+        assert_eq!(after.title.as_deref(), Some("Foreplay"));
+    }
+
+    #[test]
+    fn classifies_extraction_outcomes() {
+        let json_conn = test_conn("http_json");
+        let stored = event(&json_conn, serde_json::json!({ "artist": "HEART", "song": "Barracuda" }));
+        let before = DerivedColumns::from_event(&stored);
+        let outcome = |mapping_json: serde_json::Value| {
+            compare_extraction(&before, &reextract_event(&stored, &json_conn, Some(&mapping(mapping_json))))
+        };
+
+        assert_eq!(outcome(serde_json::json!({ "artist_path": "artist" })), ExtractionOutcome::Unchanged);
+        assert_eq!(
+            outcome(serde_json::json!({ "artist_path": "artist", "title_path": "song" })),
+            ExtractionOutcome::Improved
+        );
+        assert_eq!(outcome(serde_json::json!({ "artist_path": "song" })), ExtractionOutcome::Changed);
+        assert_eq!(outcome(serde_json::json!({ "artist_path": "missing" })), ExtractionOutcome::Regressed);
+    }
+
+    #[test]
+    fn rewrites_only_outside_dry_runs_and_when_something_changed() {
+        let conn = test_conn("http_json");
+        let version = Some(Uuid::new_v4());
+        let stored = raw_now_playing_events::Model {
+            mapping_version_id: version,
+            content_class: Some("song".to_string()),
+            ..test_event(&conn, serde_json::json!({}))
+        };
+
+        assert!(!should_rewrite(false, &stored, false, ContentClass::Song, version));
+        assert!(should_rewrite(false, &stored, true, ContentClass::Song, version));
+        assert!(should_rewrite(false, &stored, false, ContentClass::Spot, version));
+        assert!(should_rewrite(false, &stored, false, ContentClass::Song, Some(Uuid::new_v4())));
+        assert!(!should_rewrite(true, &stored, true, ContentClass::Spot, None));
+
+        // Events recorded before classification existed get a class.
+        let unclassified = raw_now_playing_events::Model { content_class: None, ..stored };
+        assert!(should_rewrite(false, &unclassified, false, ContentClass::Song, version));
+    }
+}
@@ -0,0 +1,495 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use crate::mapping_spec::MAPPING_SPEC_VERSION;
+use super::utils::{
+    extract_xml_values,
+    normalize_xml_for_parse,
+    parse_duration_seconds_str,
+    parse_duration_seconds_value,
+    parse_reported_at,
+    xml_local_name,
+    xml_stack_path,
+};
+
+// Drafts a payload mapping from a sample payload. Every leaf is scored for
+// each field by its key name (and its parent's, for `artist.name` style
+// nesting) plus the shape of its value; `list_path` falls out of where the best
+// artist/title candidates live. Only the first element of each array is
+// walked, which is also the item `extract_fields` reads.
+
+const MIN_CONFIDENCE: f64 = 0.3;
+const MAX_CANDIDATES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Artist,
+    Title,
+    Album,
+    ReportedAt,
+    Duration,
+}
+
+const FIELDS: [Field; 5] = [Field::Artist, Field::Title, Field::Album, Field::ReportedAt, Field::Duration];
+
+impl Field {
+    fn mapping_key(self) -> &'static str {
+        match self {
+            Field::Artist => "artist_path",
+            Field::Title => "title_path",
+            Field::Album => "album_path",
+            Field::ReportedAt => "reported_at_path",
+            Field::Duration => "duration_path",
+        }
+    }
+
+    // (exact key names, substrings), compared with `_`/`-` stripped.
+    fn keywords(self) -> (&'static [&'static str], &'static [&'static str]) {
+        match self {
+            Field::Artist => (
+                &["artist", "artistname", "artists", "performer", "singer", "band"],
+                &["artist", "performer"],
+            ),
+            Field::Title => (
+                &["title", "song", "songtitle", "songname", "track", "trackname", "tracktitle"],
+                &["title", "song", "track"],
+            ),
+            Field::Album => (
+                &["album", "albumname", "albumtitle", "release", "collectionname", "record"],
+                &["album", "release"],
+            ),
+            Field::ReportedAt => (
+                &["start", "starttime", "startedat", "startdate", "playedat", "playtime", "timestamp", "time", "airtime", "date"],
+                &["start", "played", "time", "date"],
+            ),
+            Field::Duration => (
+                &["duration", "durationseconds", "durationms", "length", "runtime", "seconds", "secs"],
+                &["duration", "length"],
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathCandidate {
+    pub path: String,
+    pub confidence: f64,
+    pub sample: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MappingSuggestion {
+    pub format: &'static str,
+    pub confidence: f64,
+    pub mapping_json: Value,
+    pub candidates: BTreeMap<String, Vec<PathCandidate>>,
+}
+
+#[derive(Debug, Clone)]
+struct Leaf {
+    // Path segments; `None` marks an array index (always the first element).
+    segments: Vec<Option<String>>,
+    value: Value,
+}
+
+#[derive(Debug, Clone)]
+struct Scored {
+    leaf: usize,
+    confidence: f64,
+}
+
+// Returns `None` when the sample is neither JSON nor XML.
+pub fn suggest_mapping(payload: &Value) -> Option<MappingSuggestion> {
+    match payload {
+        Value::String(text) if text.trim_start().starts_with('<') => Some(suggest_xml(text)),
+        Value::String(text) => serde_json::from_str::<Value>(text.trim()).ok().map(|v| suggest_json(&v)),
+        Value::Object(_) | Value::Array(_) => Some(suggest_json(payload)),
+        _ => None,
+    }
+}
+
+fn suggest_json(payload: &Value) -> MappingSuggestion {
+    let mut leaves = Vec::new();
+    walk_json(payload, &mut Vec::new(), &mut leaves);
+
+    let scored = score_leaves(&leaves);
+    let list_prefix = best_list_prefix(&leaves, &scored, |segments| {
+        segments.iter().position(|s| s.is_none())
+    });
+
+    build_suggestion("json", &leaves, &scored, list_prefix, json_path)
+}
+
+fn suggest_xml(xml: &str) -> MappingSuggestion {
+    // Only the first-occurrence keys; attributes become their own `@name`
+    // segment so they're scored by the attribute name.
+    let mut values: Vec<(String, String)> = extract_xml_values(xml)
+        .into_iter()
+        .filter(|(path, _)| !path.contains('['))
+        .collect();
+    values.sort();
+    let leaves: Vec<Leaf> = values
+        .into_iter()
+        .map(|(path, value)| Leaf {
+            segments: path.replace('@', ".@").split('.').map(|s| Some(s.to_string())).collect(),
+            value: Value::String(value),
+        })
+        .collect();
+
+    // The list element is the deepest repeated element above the field.
+    let counts = xml_element_counts(xml);
+    let scored = score_leaves(&leaves);
+    let list_prefix = best_list_prefix(&leaves, &scored, |segments| {
+        (1..segments.len()).rev().find(|&len| {
+            let path = xml_path(&segments[..len]);
+            counts.get(&path).copied().unwrap_or(0) > 1
+        })
+    });
+
+    build_suggestion("xml", &leaves, &scored, list_prefix, xml_path)
+}
+
+fn walk_json(value: &Value, path: &mut Vec<Option<String>>, leaves: &mut Vec<Leaf>) {
+    match value {
+        Value::Object(obj) => {
+            for (key, child) in obj {
+                path.push(Some(key.clone()));
+                walk_json(child, path, leaves);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            if let Some(first) = items.first() {
+                path.push(None);
+                walk_json(first, path, leaves);
+                path.pop();
+            }
+        }
+        _ => leaves.push(Leaf {
+            segments: path.clone(),
+            value: value.clone(),
+        }),
+    }
+}
+
+fn xml_element_counts(xml: &str) -> HashMap<String, usize> {
+    let normalized = normalize_xml_for_parse(xml);
+    let mut reader = Reader::from_str(&normalized);
+    let mut stack: Vec<String> = Vec::new();
+    let mut counts = HashMap::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                stack.push(xml_local_name(e.local_name().as_ref()));
+                if let Some(path) = xml_stack_path(&stack) {
+                    *counts.entry(path).or_insert(0) += 1;
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                stack.push(xml_local_name(e.local_name().as_ref()));
+                if let Some(path) = xml_stack_path(&stack) {
+                    *counts.entry(path).or_insert(0) += 1;
+                }
+                stack.pop();
+            }
+            Ok(Event::End(_)) => {
+                stack.pop();
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    counts
+}
+
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_' && *c != '-' && *c != '@')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn name_score(field: Field, key: &str) -> f64 {
+    let (exact, partial) = field.keywords();
+    if exact.contains(&key) {
+        0.6
+    } else if partial.iter().any(|p| key.contains(p)) {
+        0.4
+    } else {
+        0.0
+    }
+}
+
+fn shape_score(field: Field, value: &Value) -> f64 {
+    match field {
+        Field::Artist | Field::Title | Field::Album => match value.as_str().map(|s| s.trim()) {
+            Some(s) if !s.is_empty() && s.parse::<f64>().is_err() && parse_reported_at(s).is_none() => 0.3,
+            _ => -0.3,
+        },
+        Field::ReportedAt => {
+            let parsed = match value {
+                Value::String(s) => parse_reported_at(s).is_some(),
+                Value::Number(n) => n.as_i64().map(|ts| ts >= 1_000_000_000).unwrap_or(false),
+                _ => false,
+            };
+            if parsed { 0.4 } else { -0.3 }
+        }
+        Field::Duration => {
+            let parsed = match value {
+                Value::String(s) => parse_duration_seconds_str(s),
+                _ => parse_duration_seconds_value(value),
+            };
+            match parsed {
+                Some(seconds) if seconds < 24 * 3600 => 0.3,
+                _ => -0.3,
+            }
+        }
+    }
+}
+
+fn score_leaf(field: Field, leaf: &Leaf) -> f64 {
+    let names: Vec<String> = leaf.segments.iter().flatten().map(|s| normalize_key(s)).collect();
+    let Some(key) = names.last() else {
+        return 0.0;
+    };
+
+    let mut score = name_score(field, key);
+    // `artist.name`, `song.title.#text` and the like take their meaning from the parent.
+    if score == 0.0
+        && ["name", "title", "text", "value", "#text", "$value"].contains(&key.as_str())
+        && let Some(parent) = names.iter().rev().nth(1)
+    {
+        score = name_score(field, parent) - 0.1;
+    }
+    if score <= 0.0 {
+        return 0.0;
+    }
+
+    let path_text = names.join(".");
+    score += match field {
+        Field::Title if path_text.contains("artist") || path_text.contains("album") => -0.3,
+        Field::Artist if path_text.contains("album") => -0.2,
+        Field::ReportedAt if key.contains("end") || key.contains("stop") => -0.3,
+        _ => 0.0,
+    };
+    score += shape_score(field, &leaf.value);
+    score += 0.1 - 0.02 * names.len().min(5) as f64;
+
+    (score.clamp(0.0, 1.0) * 100.0).round() / 100.0
+}
+
+fn score_leaves(leaves: &[Leaf]) -> Vec<Vec<Scored>> {
+    FIELDS
+        .iter()
+        .map(|field| {
+            let mut scored: Vec<Scored> = leaves
+                .iter()
+                .enumerate()
+                .map(|(leaf, l)| Scored {
+                    leaf,
+                    confidence: score_leaf(*field, l),
+                })
+                .filter(|s| s.confidence > 0.0)
+                .collect();
+            scored.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+            scored
+        })
+        .collect()
+}
+
+// Picks the list (as a segment prefix length) holding the best artist or
+// title candidate, if that candidate sits inside one.
+fn best_list_prefix(
+    leaves: &[Leaf],
+    scored: &[Vec<Scored>],
+    list_len: impl Fn(&[Option<String>]) -> Option<usize>,
+) -> Option<Vec<Option<String>>> {
+    [Field::Artist, Field::Title]
+        .iter()
+        .filter_map(|field| {
+            let index = FIELDS.iter().position(|f| f == field)?;
+            scored[index].first()
+        })
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+        .and_then(|best| {
+            let segments = &leaves[best.leaf].segments;
+            list_len(segments).map(|len| segments[..len].to_vec())
+        })
+}
+
+fn build_suggestion(
+    format: &'static str,
+    leaves: &[Leaf],
+    scored: &[Vec<Scored>],
+    list_prefix: Option<Vec<Option<String>>>,
+    render: fn(&[Option<String>]) -> String,
+) -> MappingSuggestion {
+    let mut mapping = Map::new();
+    mapping.insert("version".to_string(), Value::from(MAPPING_SPEC_VERSION));
+    let mut candidates = BTreeMap::new();
+    let mut chosen = Vec::new();
+
+    // JSON list items start after the `[0]` marker; XML items at the element.
+    let item_start = list_prefix.as_ref().map(|prefix| {
+        if format == "json" { prefix.len() + 1 } else { prefix.len() }
+    });
+    if let Some(prefix) = &list_prefix {
+        mapping.insert("list_path".to_string(), Value::String(render(prefix)));
+    }
+
+    for (index, field) in FIELDS.iter().enumerate() {
+        let ranked: Vec<PathCandidate> = scored[index]
+            .iter()
+            .filter_map(|s| {
+                let segments = &leaves[s.leaf].segments;
+                let path = match (&list_prefix, item_start) {
+                    (Some(prefix), Some(start)) => {
+                        if !segments.starts_with(prefix) || segments.len() <= start {
+                            return None;
+                        }
+                        render(&segments[start..])
+                    }
+                    _ => render(segments),
+                };
+                Some(PathCandidate {
+                    path,
+                    confidence: s.confidence,
+                    sample: leaves[s.leaf].value.clone(),
+                })
+            })
+            .take(MAX_CANDIDATES)
+            .collect();
+
+        if let Some(best) = ranked.first().filter(|c| c.confidence >= MIN_CONFIDENCE) {
+            mapping.insert(field.mapping_key().to_string(), Value::String(best.path.clone()));
+            chosen.push(best.confidence);
+        }
+        candidates.insert(field.mapping_key().to_string(), ranked);
+    }
+
+    // Artist and title are required for a usable event, so a missing one
+    // counts as zero.
+    let required = [Field::Artist, Field::Title]
+        .iter()
+        .filter(|f| mapping.contains_key(f.mapping_key()))
+        .count();
+    let confidence = if chosen.is_empty() {
+        0.0
+    } else {
+        let mean = chosen.iter().sum::<f64>() / chosen.len() as f64;
+        (mean * required as f64 / 2.0 * 100.0).round() / 100.0
+    };
+
+    MappingSuggestion {
+        format,
+        confidence,
+        mapping_json: Value::Object(mapping),
+        candidates,
+    }
+}
+
+fn json_path(segments: &[Option<String>]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            None => path.push_str("[0]"),
+            Some(key) if key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+            }
+            Some(key) => path.push_str(&format!("['{}']", key.replace('\'', "\\'"))),
+        }
+    }
+    if path.is_empty() || path.starts_with('[') {
+        format!("${}", path)
+    } else {
+        path
+    }
+}
+
+fn xml_path(segments: &[Option<String>]) -> String {
+    segments.iter().flatten().cloned().collect::<Vec<_>>().join(".").replace(".@", "@")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_json_list_mapping() {
+        let payload = serde_json::json!({
+            "station": "KXYZ",
+            "history": [
+                {
+                    "artist": { "name": "Heart" },
+                    "song_title": "Barracuda",
+                    "album": { "title": "Little Queen" },
+                    "started_at": "2026-10-18T10:04:00Z",
+                    "length": 261
+                },
+                { "artist": { "name": "Boston" }, "song_title": "Foreplay" }
+            ]
+        });
+        let suggestion = suggest_mapping(&payload).unwrap();
+        assert_eq!(suggestion.format, "json");
+        assert_eq!(
+            suggestion.mapping_json,
+            serde_json::json!({
+                "version": 1,
+                "list_path": "history",
+                "artist_path": "artist.name",
+                "title_path": "song_title",
+                "album_path": "album.title",
+                "reported_at_path": "started_at",
+                "duration_path": "length"
+            })
+        );
+        assert!(suggestion.confidence > 0.6);
+        assert_eq!(suggestion.candidates["artist_path"][0].sample, "Heart");
+        assert!(crate::mapping_spec::validate_mapping(&suggestion.mapping_json).is_ok());
+    }
+
+    #[test]
+    fn suggests_xml_mapping_for_repeated_elements() {
+        let xml = "<?xml version=\"1.0\"?><playlist><station>KXYZ</station>\
+            <song><artist>Heart</artist><title>Barracuda</title><duration>261</duration></song>\
+            <song><artist>Boston</artist><title>Foreplay</title><duration>170</duration></song></playlist>";
+        let suggestion = suggest_mapping(&Value::String(xml.to_string())).unwrap();
+        assert_eq!(suggestion.format, "xml");
+        assert_eq!(suggestion.mapping_json["list_path"], "playlist.song");
+        assert_eq!(suggestion.mapping_json["artist_path"], "artist");
+        assert_eq!(suggestion.mapping_json["title_path"], "title");
+        assert_eq!(suggestion.mapping_json["duration_path"], "duration");
+        assert!(suggestion.mapping_json.get("reported_at_path").is_none());
+
+        let attributes = "<ns:history xmlns:ns=\"urn:x\"><ns:song artist=\"Heart\" title=\"Barracuda\"/>\
+            <ns:song artist=\"Boston\" title=\"Foreplay\"/></ns:history>";
+        let suggestion = suggest_mapping(&Value::String(attributes.to_string())).unwrap();
+        assert_eq!(suggestion.mapping_json["list_path"], "history.song");
+        assert_eq!(suggestion.mapping_json["artist_path"], "@artist");
+        assert_eq!(suggestion.mapping_json["title_path"], "@title");
+    }
+
+    #[test]
+    fn handles_flat_payloads_and_rejects_plain_text() {
+        let suggestion = suggest_mapping(&serde_json::json!({
+            "now_playing": { "performer": "Tom Petty", "track": "Free Fallin'", "timestamp": 1760781840 }
+        }))
+        .unwrap();
+        assert_eq!(suggestion.mapping_json["artist_path"], "now_playing.performer");
+        assert_eq!(suggestion.mapping_json["title_path"], "now_playing.track");
+        assert_eq!(suggestion.mapping_json["reported_at_path"], "now_playing.timestamp");
+        assert!(suggestion.mapping_json.get("list_path").is_none());
+
+        let root_list = suggest_mapping(&serde_json::json!([{ "artist": "Heart", "title": "Barracuda" }])).unwrap();
+        assert_eq!(root_list.mapping_json["list_path"], "$");
+
+        assert!(suggest_mapping(&Value::String("Heart - Barracuda".to_string())).is_none());
+    }
+}
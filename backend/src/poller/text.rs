@@ -0,0 +1,214 @@
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// Plain-text feeds (`http_text`) such as the `nowplaying.txt` files written by
+// automation systems. The body is parsed into a flat map of lower-cased keys so
+// mappings can address fields by name, the same way XML values are looked up.
+//
+// Supported `text_format` values:
+// - `regex`: named captures from `text_pattern` become keys
+// - `delimited`: the first non-empty line is split on `text_delimiter` into `text_fields`
+// - `key_value`: `KEY=value` pairs separated by newlines or `text_line_separator`
+// When `text_format` is absent the format is guessed from the body.
+
+const DEFAULT_DELIMITER: &str = " - ";
+const DEFAULT_PAIR_SEPARATOR: &str = "=";
+const DEFAULT_FIELDS: [&str; 2] = ["artist", "title"];
+
+pub fn extract_text_values(
+    body: &str,
+    mapping: Option<&Map<String, Value>>,
+) -> HashMap<String, String> {
+    let body = body.trim_start_matches('\u{feff}');
+    let format = mapping_str(mapping, "text_format").map(|s| s.to_ascii_lowercase());
+    let pair_separator = mapping_str(mapping, "text_pair_separator").unwrap_or(DEFAULT_PAIR_SEPARATOR);
+    let line_separator = mapping_str(mapping, "text_line_separator");
+
+    match format.as_deref() {
+        Some("regex") => match mapping_str(mapping, "text_pattern") {
+            Some(pattern) => regex_values(body, pattern),
+            None => HashMap::new(),
+        },
+        Some("key_value") => key_value_values(body, pair_separator, line_separator),
+        Some("delimited") => delimited_values(body, mapping),
+        _ => {
+            if looks_like_key_value(body, pair_separator, line_separator) {
+                key_value_values(body, pair_separator, line_separator)
+            } else {
+                delimited_values(body, mapping)
+            }
+        }
+    }
+}
+
+pub fn text_lookup(values: &HashMap<String, String>, key: &str) -> Option<String> {
+    values
+        .get(&key.trim().to_ascii_lowercase())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn mapping_str<'a>(mapping: Option<&'a Map<String, Value>>, key: &str) -> Option<&'a str> {
+    mapping
+        .and_then(|m| m.get(key))
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+}
+
+fn regex_values(body: &str, pattern: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let re = match Regex::new(pattern) {
+        Ok(re) => re,
+        Err(e) => {
+            tracing::warn!("Invalid text_pattern {:?}: {}", pattern, e);
+            return values;
+        }
+    };
+
+    if let Some(caps) = re.captures(body) {
+        for name in re.capture_names().flatten() {
+            if let Some(m) = caps.name(name) {
+                values.insert(name.to_ascii_lowercase(), m.as_str().to_string());
+            }
+        }
+    }
+
+    values
+}
+
+fn delimited_values(body: &str, mapping: Option<&Map<String, Value>>) -> HashMap<String, String> {
+    let delimiter = mapping_str(mapping, "text_delimiter").unwrap_or(DEFAULT_DELIMITER);
+    let fields: Vec<String> = mapping
+        .and_then(|m| m.get("text_fields"))
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_ascii_lowercase())
+                .collect()
+        })
+        .filter(|fields: &Vec<String>| !fields.is_empty())
+        .unwrap_or_else(|| DEFAULT_FIELDS.iter().map(|s| s.to_string()).collect());
+
+    let mut values = HashMap::new();
+    let Some(line) = body.lines().map(str::trim).find(|l| !l.is_empty()) else {
+        return values;
+    };
+
+    // The last field keeps any remaining delimiters, so "A - B - C" with
+    // [artist, title] yields a title of "B - C".
+    for (field, part) in fields.iter().zip(line.splitn(fields.len(), delimiter)) {
+        values.insert(field.clone(), part.trim().to_string());
+    }
+
+    values
+}
+
+fn key_value_values(
+    body: &str,
+    pair_separator: &str,
+    line_separator: Option<&str>,
+) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    for line in split_lines(body, line_separator) {
+        if let Some((key, value)) = line.split_once(pair_separator) {
+            let key = key.trim().to_ascii_lowercase();
+            if !key.is_empty() {
+                values.entry(key).or_insert_with(|| value.trim().to_string());
+            }
+        }
+    }
+    values
+}
+
+fn looks_like_key_value(body: &str, pair_separator: &str, line_separator: Option<&str>) -> bool {
+    let lines: Vec<&str> = split_lines(body, line_separator)
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    !lines.is_empty()
+        && lines.iter().all(|l| {
+            l.split_once(pair_separator)
+                .map(|(k, _)| is_key_like(k.trim()))
+                .unwrap_or(false)
+        })
+}
+
+fn is_key_like(key: &str) -> bool {
+    !key.is_empty()
+        && !key.contains(DEFAULT_DELIMITER)
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ' '))
+}
+
+fn split_lines<'a>(body: &'a str, line_separator: Option<&'a str>) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+    match line_separator {
+        Some(sep) => Box::new(body.lines().flat_map(move |l| l.split(sep))),
+        None => {
+            // Single-line `TITLE=..|ARTIST=..` feeds are common enough to detect.
+            let first = body.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+            if body.trim().lines().count() <= 1 && first.contains('|') {
+                Box::new(first.split('|'))
+            } else {
+                Box::new(body.lines())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mapping(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn splits_artist_dash_title_by_default() {
+        let values = extract_text_values("Tom Petty - Free Fallin' - Live\r\n", None);
+        assert_eq!(text_lookup(&values, "artist").as_deref(), Some("Tom Petty"));
+        assert_eq!(text_lookup(&values, "title").as_deref(), Some("Free Fallin' - Live"));
+    }
+
+    #[test]
+    fn detects_pipe_separated_key_values() {
+        let values = extract_text_values("TITLE=Free Fallin'|ARTIST=Tom Petty|DURATION=256", None);
+        assert_eq!(text_lookup(&values, "ARTIST").as_deref(), Some("Tom Petty"));
+        assert_eq!(text_lookup(&values, "title").as_deref(), Some("Free Fallin'"));
+        assert_eq!(text_lookup(&values, "duration").as_deref(), Some("256"));
+    }
+
+    #[test]
+    fn parses_multi_line_key_values_with_custom_separator() {
+        let m = mapping(json!({ "text_format": "key_value", "text_pair_separator": ":" }));
+        let values = extract_text_values("Artist: Tom Petty\nTitle: Free Fallin'\n", Some(&m));
+        assert_eq!(text_lookup(&values, "artist").as_deref(), Some("Tom Petty"));
+        assert_eq!(text_lookup(&values, "title").as_deref(), Some("Free Fallin'"));
+    }
+
+    #[test]
+    fn uses_named_regex_captures() {
+        let m = mapping(json!({
+            "text_format": "regex",
+            "text_pattern": r#"^"(?P<title>[^"]+)" by (?P<artist>.+)$"#
+        }));
+        let values = extract_text_values("\"Free Fallin'\" by Tom Petty", Some(&m));
+        assert_eq!(text_lookup(&values, "artist").as_deref(), Some("Tom Petty"));
+        assert_eq!(text_lookup(&values, "title").as_deref(), Some("Free Fallin'"));
+    }
+
+    #[test]
+    fn delimited_fields_follow_mapping_order() {
+        let m = mapping(json!({
+            "text_format": "delimited",
+            "text_delimiter": "~",
+            "text_fields": ["title", "artist", "album"]
+        }));
+        let values = extract_text_values("Free Fallin'~Tom Petty~Full Moon Fever", Some(&m));
+        assert_eq!(text_lookup(&values, "album").as_deref(), Some("Full Moon Fever"));
+        assert_eq!(text_lookup(&values, "artist").as_deref(), Some("Tom Petty"));
+    }
+}
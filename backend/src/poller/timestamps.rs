@@ -0,0 +1,182 @@
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use serde_json::{Map, Value};
+use super::utils::mapping_paths;
+
+// Parsing for `reported_at` values. Values that carry an offset (RFC 3339,
+// RFC 2822, epoch seconds or millis) are taken as-is. Naive local times such
+// as `2026-10-17 14:03:22` or `10/17/2026 2:03 PM` are read in the mapping's
+// `reported_at_timezone` (an IANA name, defaulting to the station's timezone)
+// or UTC. `reported_at_format` takes one chrono format string or a list, tried
+// before the built-in formats.
+
+const NAIVE_FORMATS: [&str; 9] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%m/%d/%Y %I:%M:%S %p",
+    "%m/%d/%Y %I:%M %p",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%d %b %Y %H:%M:%S",
+    "%Y%m%d%H%M%S",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct ReportedAtSettings {
+    pub formats: Vec<String>,
+    pub timezone: Option<Tz>,
+}
+
+impl ReportedAtSettings {
+    pub fn from_mapping(mapping_obj: Option<&Map<String, Value>>) -> Self {
+        Self {
+            formats: mapping_paths(mapping_obj, "reported_at_format")
+                .into_iter()
+                .map(|f| f.to_string())
+                .collect(),
+            timezone: mapping_obj
+                .and_then(|o| o.get("reported_at_timezone"))
+                .and_then(|v| v.as_str())
+                .and_then(|name| name.parse::<Tz>().ok()),
+        }
+    }
+
+    pub fn parse(&self, value: &str) -> Option<DateTime<FixedOffset>> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        if let Some(dt) = self.formats.iter().find_map(|format| self.parse_with_format(value, format)) {
+            return Some(dt);
+        }
+
+        DateTime::parse_from_rfc3339(value)
+            .ok()
+            .or_else(|| DateTime::parse_from_rfc2822(value).ok())
+            .or_else(|| value.parse::<i64>().ok().and_then(parse_epoch_seconds_or_millis))
+            .or_else(|| {
+                NAIVE_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+                    .and_then(|naive| self.localize(naive))
+            })
+    }
+
+    fn parse_with_format(&self, value: &str, format: &str) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_str(value, format).ok().or_else(|| {
+            NaiveDateTime::parse_from_str(value, format)
+                .ok()
+                .and_then(|naive| self.localize(naive))
+        })
+    }
+
+    // A time repeated by the DST fall-back resolves to its first occurrence; a
+    // time skipped by spring-forward is moved past the gap.
+    pub fn localize(&self, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        let tz = self.timezone.unwrap_or(Tz::UTC);
+        match tz.from_local_datetime(&naive) {
+            LocalResult::Single(dt) => Some(dt.fixed_offset()),
+            LocalResult::Ambiguous(earliest, _) => Some(earliest.fixed_offset()),
+            LocalResult::None => tz
+                .from_local_datetime(&(naive + chrono::Duration::hours(1)))
+                .earliest()
+                .map(|dt| dt.fixed_offset()),
+        }
+    }
+
+    // The first value that parses. When values were found but none parsed,
+    // the error names them so it can be stored with the event.
+    pub fn first_parsed(&self, values: &[String]) -> Result<Option<DateTime<FixedOffset>>, String> {
+        if let Some(dt) = values.iter().find_map(|v| self.parse(v)) {
+            return Ok(Some(dt));
+        }
+        match values.first() {
+            None => Ok(None),
+            Some(value) if self.formats.is_empty() => Err(format!("unrecognized reported_at {:?}", value)),
+            Some(value) => Err(format!(
+                "reported_at {:?} matches none of the formats {:?}",
+                value, self.formats
+            )),
+        }
+    }
+}
+
+pub fn parse_epoch_seconds_or_millis(ts: i64) -> Option<DateTime<FixedOffset>> {
+    // Heuristic: epoch millis are ~1.7e12, epoch seconds are ~1.7e9.
+    let millis = if ts.abs() > 100_000_000_000 {
+        ts
+    } else {
+        ts.saturating_mul(1000)
+    };
+    let dt = chrono::DateTime::from_timestamp_millis(millis)?;
+    Some(dt.fixed_offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(mapping: Value) -> ReportedAtSettings {
+        ReportedAtSettings::from_mapping(mapping.as_object())
+    }
+
+    #[test]
+    fn reads_naive_times_in_the_configured_timezone() {
+        let chicago = settings(serde_json::json!({ "reported_at_timezone": "America/Chicago" }));
+        assert_eq!(
+            chicago.parse("2026-10-17 14:03:22").unwrap().to_rfc3339(),
+            "2026-10-17T14:03:22-05:00"
+        );
+        assert_eq!(
+            chicago.parse("01/15/2026 2:03 PM").unwrap().to_rfc3339(),
+            "2026-01-15T14:03:00-06:00"
+        );
+        // Explicit offsets win over the timezone.
+        assert_eq!(
+            chicago.parse("2026-10-17T14:03:22Z").unwrap().to_rfc3339(),
+            "2026-10-17T14:03:22+00:00"
+        );
+        assert_eq!(
+            ReportedAtSettings::default().parse("17 Oct 2026 14:03:22").unwrap().to_rfc3339(),
+            "2026-10-17T14:03:22+00:00"
+        );
+    }
+
+    #[test]
+    fn handles_dst_transitions() {
+        let chicago = settings(serde_json::json!({ "reported_at_timezone": "America/Chicago" }));
+        // 01:30 happens twice on 2026-11-01; the first (CDT) is used.
+        assert_eq!(
+            chicago.parse("2026-11-01 01:30:00").unwrap().to_rfc3339(),
+            "2026-11-01T01:30:00-05:00"
+        );
+        // 02:30 doesn't exist on 2026-03-08.
+        assert_eq!(
+            chicago.parse("2026-03-08 02:30:00").unwrap().to_rfc3339(),
+            "2026-03-08T03:30:00-05:00"
+        );
+    }
+
+    #[test]
+    fn uses_custom_formats_and_reports_failures() {
+        let custom = settings(serde_json::json!({
+            "reported_at_format": ["%d.%m.%Y %H:%M", "%H:%M:%S %d/%m/%y"],
+            "reported_at_timezone": "Europe/Berlin"
+        }));
+        assert_eq!(
+            custom.parse("17.10.2026 14:03").unwrap().to_rfc3339(),
+            "2026-10-17T14:03:00+02:00"
+        );
+        assert_eq!(
+            custom.parse("14:03:22 17/12/26").unwrap().to_rfc3339(),
+            "2026-12-17T14:03:22+01:00"
+        );
+
+        assert_eq!(custom.first_parsed(&[]), Ok(None));
+        let values = vec!["soon".to_string(), "17.10.2026 14:03".to_string()];
+        assert!(custom.first_parsed(&values).unwrap().is_some());
+        let err = custom.first_parsed(&["soon".to_string()]).unwrap_err();
+        assert!(err.starts_with("reported_at \"soon\" matches none of the formats"));
+    }
+}
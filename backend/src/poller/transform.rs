@@ -0,0 +1,341 @@
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::mapping_spec::{CaseMode, Transform};
+use super::utils::ParsedFields;
+
+// Cleanup applied to extracted strings before validation and dedup. Each
+// mapped field can carry an ordered `<field>_transforms` list in the mapping:
+//
+// "title_transforms": [
+//   { "op": "html_unescape" },
+//   { "op": "split", "delimiter": " - ", "index": 1 },
+//   { "op": "regex_replace", "pattern": "\\s*\\[Clean\\]$", "replacement": "" },
+//   { "op": "case", "mode": "title" },
+//   { "op": "null_if", "values": ["Station ID", "Commercial Break"] }
+// ]
+//
+// `regex_capture` keeps the `value` group (else group 1, else the whole match)
+// and leaves the value alone when nothing matches. `split` yields nothing when
+// the part doesn't exist. A value that ends up empty becomes null.
+//
+// `reported_at` and `duration` values are transformed as text before they're
+// parsed; `extra_fields` values take theirs from `extra_field_transforms`,
+// keyed like `extra_fields`.
+
+pub const TRANSFORM_FIELDS: [&str; 10] = [
+    "artist",
+    "title",
+    "album",
+    "isrc",
+    "upc",
+    "artwork_url",
+    "label",
+    "composer",
+    "category",
+    "item_type",
+];
+
+impl Transform {
+    fn name(&self) -> &'static str {
+        match self {
+            Transform::RegexReplace { .. } => "regex_replace",
+            Transform::RegexCapture { .. } => "regex_capture",
+            Transform::Split { .. } => "split",
+            Transform::Trim => "trim",
+            Transform::Case { .. } => "case",
+            Transform::HtmlUnescape => "html_unescape",
+            Transform::NullIf { .. } => "null_if",
+        }
+    }
+
+    fn apply(&self, value: &str) -> Result<Option<String>, String> {
+        let output = match self {
+            Transform::RegexReplace { pattern, replacement } => {
+                let re = compile(pattern)?;
+                re.replace_all(value, replacement.as_str()).into_owned()
+            }
+            Transform::RegexCapture { pattern, group } => {
+                let re = compile(pattern)?;
+                let Some(caps) = re.captures(value) else {
+                    return Ok(Some(value.to_string()));
+                };
+                let captured = match group.as_deref() {
+                    Some(g) => match g.parse::<usize>() {
+                        Ok(index) => caps.get(index),
+                        Err(_) => caps.name(g),
+                    },
+                    None => caps.name("value").or_else(|| caps.get(1)).or_else(|| caps.get(0)),
+                };
+                captured.map(|m| m.as_str().to_string()).unwrap_or_default()
+            }
+            Transform::Split { delimiter, index } => {
+                if delimiter.is_empty() {
+                    return Err("split delimiter must not be empty".to_string());
+                }
+                let parts: Vec<&str> = value.split(delimiter.as_str()).collect();
+                let position = if *index < 0 {
+                    parts.len() as i64 + index
+                } else {
+                    *index
+                };
+                match usize::try_from(position).ok().and_then(|i| parts.get(i)) {
+                    Some(part) => part.trim().to_string(),
+                    None => return Ok(None),
+                }
+            }
+            Transform::Trim => value.trim().to_string(),
+            Transform::Case { mode } => match mode {
+                CaseMode::Lower => value.to_lowercase(),
+                CaseMode::Upper => value.to_uppercase(),
+                CaseMode::Title => title_case(value),
+            },
+            Transform::HtmlUnescape => html_escape::decode_html_entities(value).into_owned(),
+            Transform::NullIf { values, case_sensitive } => {
+                let trimmed = value.trim();
+                let matched = values.iter().any(|v| {
+                    if *case_sensitive {
+                        v.trim() == trimmed
+                    } else {
+                        v.trim().to_lowercase() == trimmed.to_lowercase()
+                    }
+                });
+                if matched {
+                    return Ok(None);
+                }
+                value.to_string()
+            }
+        };
+        Ok(Some(output).filter(|s| !s.trim().is_empty()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransformStep {
+    pub op: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldTrace {
+    pub field: String,
+    pub steps: Vec<TransformStep>,
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("invalid pattern {:?}: {}", pattern, e))
+}
+
+fn title_case(value: &str) -> String {
+    value
+        .split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn field_transforms(mapping: Option<&Map<String, Value>>, field: &str) -> Result<Vec<Transform>, String> {
+    match mapping.and_then(|m| m.get(&format!("{}_transforms", field))) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(list) => serde_json::from_value(list.clone())
+            .map_err(|e| format!("{}_transforms: {}", field, e)),
+    }
+}
+
+fn configured_transforms(mapping: Option<&Map<String, Value>>, field: &str) -> Vec<Transform> {
+    field_transforms(mapping, field).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring transforms: {}", e);
+        Vec::new()
+    })
+}
+
+// Runs every configured transform over the text fields, recording each step.
+// A broken transform is skipped (and reported in the trace) rather than
+// dropping the field.
+pub fn apply_field_transforms(fields: &mut ParsedFields, mapping: Option<&Map<String, Value>>) -> Vec<FieldTrace> {
+    let mut traces = Vec::new();
+    for field in TRANSFORM_FIELDS {
+        let transforms = configured_transforms(mapping, field);
+        if transforms.is_empty() {
+            continue;
+        }
+
+        let slot = match field {
+            "artist" => &mut fields.artist,
+            "title" => &mut fields.title,
+            "album" => &mut fields.album,
+            other => match fields.extended.slot_mut(other) {
+                Some(slot) => slot,
+                None => continue,
+            },
+        };
+        traces.push(FieldTrace {
+            field: field.to_string(),
+            steps: run_transforms(slot, &transforms),
+        });
+    }
+
+    let extra_transforms = mapping
+        .and_then(|m| m.get("extra_field_transforms"))
+        .and_then(|v| v.as_object());
+    if let (Some(extra_transforms), Some(extras)) = (extra_transforms, fields.extended.extra_fields.as_mut()) {
+        for (key, list) in extra_transforms {
+            let Some(Value::String(value)) = extras.get(key) else {
+                continue;
+            };
+            let transforms: Vec<Transform> = match serde_json::from_value(list.clone()) {
+                Ok(transforms) => transforms,
+                Err(e) => {
+                    tracing::warn!("Ignoring transforms: extra_field_transforms.{}: {}", key, e);
+                    continue;
+                }
+            };
+            let mut slot = Some(value.clone());
+            let steps = run_transforms(&mut slot, &transforms);
+            match slot {
+                Some(value) => extras.insert(key.clone(), Value::String(value)),
+                None => extras.remove(key),
+            };
+            traces.push(FieldTrace {
+                field: format!("extra_fields.{}", key),
+                steps,
+            });
+        }
+        if extras.is_empty() {
+            fields.extended.extra_fields = None;
+        }
+    }
+    traces
+}
+
+// The `<field>_transforms` of a value that's parsed after cleanup
+// (`reported_at`, `duration`).
+pub fn transform_raw_value(mapping: Option<&Map<String, Value>>, field: &str, value: String) -> Option<String> {
+    let mut slot = Some(value);
+    run_transforms(&mut slot, &configured_transforms(mapping, field));
+    slot
+}
+
+fn run_transforms(slot: &mut Option<String>, transforms: &[Transform]) -> Vec<TransformStep> {
+    let mut steps = Vec::new();
+    for transform in transforms {
+        let before = slot.clone();
+        let Some(input) = before.as_deref() else {
+            break;
+        };
+        let (after, error) = match transform.apply(input) {
+            Ok(after) => (after, None),
+            Err(e) => (before.clone(), Some(e)),
+        };
+        *slot = after.clone();
+        steps.push(TransformStep {
+            op: transform.name().to_string(),
+            before,
+            after,
+            error,
+        });
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::utils::ExtendedFields;
+
+    fn run(transforms: Value, input: &str) -> Option<String> {
+        let mapping = serde_json::json!({ "title_transforms": transforms });
+        let mut fields = ParsedFields {
+            title: Some(input.to_string()),
+            ..Default::default()
+        };
+        apply_field_transforms(&mut fields, mapping.as_object());
+        fields.title
+    }
+
+    #[test]
+    fn cleans_up_common_title_noise() {
+        let transforms = serde_json::json!([
+            { "op": "html_unescape" },
+            { "op": "regex_replace", "pattern": "^\\d+\\.\\s*" },
+            { "op": "regex_replace", "pattern": "\\s*\\[Clean\\]$" },
+            { "op": "regex_capture", "pattern": "^(?P<value>.+?)\\s*\\(feat\\." },
+            { "op": "case", "mode": "title" }
+        ]);
+        assert_eq!(
+            run(transforms.clone(), "07. DON&#39;T STOP BELIEVIN&apos; (feat. Someone) [Clean]").as_deref(),
+            Some("Don't Stop Believin'")
+        );
+        assert_eq!(run(transforms, "HOLD ON LOOSELY").as_deref(), Some("Hold On Loosely"));
+    }
+
+    #[test]
+    fn splits_and_nulls_values() {
+        let second = serde_json::json!([{ "op": "split", "delimiter": " - ", "index": 1 }]);
+        assert_eq!(run(second.clone(), "Journey - Separate Ways").as_deref(), Some("Separate Ways"));
+        assert_eq!(run(second, "Journey"), None);
+
+        let last = serde_json::json!([{ "op": "split", "delimiter": "/", "index": -1 }, { "op": "case", "mode": "lower" }]);
+        assert_eq!(run(last, "A/B/C").as_deref(), Some("c"));
+
+        let station_ids = serde_json::json!([{ "op": "trim" }, { "op": "null_if", "values": ["KXYZ Legal ID"] }]);
+        assert_eq!(run(station_ids, "  kxyz legal id "), None);
+    }
+
+    #[test]
+    fn traces_each_step() {
+        let mapping = serde_json::json!({
+            "artist_transforms": [{ "op": "trim" }, { "op": "case", "mode": "upper" }]
+        });
+        let mut fields = ParsedFields {
+            artist: Some(" Heart ".to_string()),
+            ..Default::default()
+        };
+        let traces = apply_field_transforms(&mut fields, mapping.as_object());
+        assert_eq!(fields.artist.as_deref(), Some("HEART"));
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].steps[0].before.as_deref(), Some(" Heart "));
+        assert_eq!(traces[0].steps[1].after.as_deref(), Some("HEART"));
+    }
+
+    #[test]
+    fn transforms_extended_and_extra_fields() {
+        let mapping = serde_json::json!({
+            "isrc_transforms": [{ "op": "regex_replace", "pattern": "-", "replacement": "" }],
+            "category_transforms": [{ "op": "null_if", "values": ["n/a"] }],
+            "extra_field_transforms": { "cart": [{ "op": "split", "delimiter": "/", "index": 0 }] },
+            "duration_transforms": [{ "op": "regex_capture", "pattern": "(\\d+)s" }]
+        });
+        let mut fields = ParsedFields {
+            extended: ExtendedFields {
+                isrc: Some("US-RC1-76-07839".to_string()),
+                category: Some("N/A".to_string()),
+                extra_fields: Some(serde_json::from_value(serde_json::json!({ "cart": "0420/B", "slot": 3 })).unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let traces = apply_field_transforms(&mut fields, mapping.as_object());
+        assert_eq!(fields.extended.isrc.as_deref(), Some("USRC17607839"));
+        assert_eq!(fields.extended.category, None);
+        assert_eq!(
+            fields.extended.extra_fields,
+            serde_json::from_value(serde_json::json!({ "cart": "0420", "slot": 3 })).unwrap()
+        );
+        assert_eq!(traces.len(), 3);
+        assert_eq!(traces[2].field, "extra_fields.cart");
+
+        let raw = transform_raw_value(mapping.as_object(), "duration", "length: 245s".to_string());
+        assert_eq!(raw.as_deref(), Some("245"));
+        assert_eq!(transform_raw_value(None, "reported_at", "10:04".to_string()).as_deref(), Some("10:04"));
+    }
+}
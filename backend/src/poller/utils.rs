@@ -1,14 +1,33 @@
 use sea_orm::{prelude::*, Set, QueryOrder};
 use chrono::{Utc, DateTime, FixedOffset};
 use sha2::{Sha256, Digest};
-use crate::entities::{now_playing_connections, raw_now_playing_events, payload_mappings};
+use serde::Serialize;
+use crate::entities::{now_playing_connections, raw_now_playing_events, payload_mappings, stations};
 use quick_xml::Reader;
 use quick_xml::events::Event;
-use quick_xml::escape::unescape;
-use std::collections::HashMap;
+use quick_xml::escape::{resolve_predefined_entity, unescape};
+use quick_xml::events::BytesStart;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
 use futures::{StreamExt, SinkExt};
 use tokio_tungstenite::tungstenite::Message;
+use std::sync::Arc;
 use std::time::Duration;
+use super::auth::{load_credential, resolve_auth, should_refresh_after, AuthError, AuthParams};
+use super::chain::{clear_chain_cache, run_request_chain, ChainError};
+use super::charset::decode_body;
+use super::classify::load_classifier;
+use super::cookies::{save_cookies, warm_up, CookieJar};
+use super::fingerprint::{resolve_payload_mapping, MappingResolution};
+use super::proxy::{apply_proxy, classify_proxy_error, connect_ws_via_proxy, resolve_proxy, ProxyError};
+use super::jsonpath::{select, select_first};
+use super::text::{extract_text_values, text_lookup};
+use super::transform::{apply_field_transforms, transform_raw_value, FieldTrace};
+use super::timestamps::ReportedAtSettings;
+use super::validity::{load_validity_rules, retain_rejected_event, RetainedKey, ValidityStatus};
+use super::xpath::XPathDocument;
+use crate::mapping_spec::is_xpath_path;
+use crate::script_mapping::{run_script, ScriptInput};
 use crate::http_headers::{
     browser_headers_value,
     default_headers_value,
@@ -16,6 +35,157 @@ use crate::http_headers::{
     should_default_headers,
 };
 
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParsedFields {
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub reported_at: Option<DateTime<FixedOffset>>,
+    // Set when a reported_at value was found but couldn't be parsed.
+    pub reported_at_error: Option<String>,
+    pub duration_seconds: Option<i64>,
+    pub extended: ExtendedFields,
+}
+
+impl ParsedFields {
+    fn is_empty(&self) -> bool {
+        self.artist.is_none()
+            && self.title.is_none()
+            && self.album.is_none()
+            && self.reported_at.is_none()
+            && self.duration_seconds.is_none()
+            && self.extended.is_empty()
+    }
+}
+
+// Metadata beyond artist/title/album. Each first-class field is read from
+// `<field>_path` like the others; `extra_fields` maps custom keys to a path
+// (or list of fallback paths) and is stored as a JSON object:
+//
+// "extra_fields": { "cart": "cut.cart", "bpm": ["bpm", "tempo"] }
+pub const EXTENDED_FIELDS: [&str; 7] = [
+    "isrc",
+    "upc",
+    "artwork_url",
+    "label",
+    "composer",
+    "category",
+    "item_type",
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ExtendedFields {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isrc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artwork_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub composer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_fields: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl ExtendedFields {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn slot_mut(&mut self, field: &str) -> Option<&mut Option<String>> {
+        match field {
+            "isrc" => Some(&mut self.isrc),
+            "upc" => Some(&mut self.upc),
+            "artwork_url" => Some(&mut self.artwork_url),
+            "label" => Some(&mut self.label),
+            "composer" => Some(&mut self.composer),
+            "category" => Some(&mut self.category),
+            "item_type" => Some(&mut self.item_type),
+            _ => None,
+        }
+    }
+
+    // `lookup` resolves a single path against the payload. Text payloads fall
+    // back to the field name as the key, the same as artist/title do there.
+    fn extract(
+        mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+        name_as_default_key: bool,
+        lookup: impl Fn(&str) -> Option<serde_json::Value>,
+    ) -> Self {
+        let mut extended = Self::default();
+        for field in EXTENDED_FIELDS {
+            let mut paths = mapping_paths(mapping_obj, &format!("{}_path", field));
+            if paths.is_empty() && name_as_default_key {
+                paths.push(field);
+            }
+            let value = paths
+                .into_iter()
+                .find_map(|p| lookup(p).as_ref().and_then(scalar_string));
+            if let Some(slot) = extended.slot_mut(field) {
+                *slot = value;
+            }
+        }
+
+        let extras: serde_json::Map<String, serde_json::Value> = mapping_obj
+            .and_then(|o| o.get("extra_fields"))
+            .and_then(|v| v.as_object())
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|(key, paths)| {
+                        let value = value_paths(paths).into_iter().find_map(&lookup)?;
+                        Some((key.clone(), value))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !extras.is_empty() {
+            extended.extra_fields = Some(extras);
+        }
+        extended
+    }
+}
+
+fn reported_at_field(
+    values: &[String],
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> (Option<DateTime<FixedOffset>>, Option<String>) {
+    let values: Vec<String> = values
+        .iter()
+        .filter_map(|v| transform_raw_value(mapping_obj, "reported_at", v.clone()))
+        .collect();
+    match ReportedAtSettings::from_mapping(mapping_obj).first_parsed(&values) {
+        Ok(reported_at) => (reported_at, None),
+        Err(e) => (None, Some(e)),
+    }
+}
+
+// Durations pass through `duration_transforms` as text before they're parsed.
+fn duration_field(
+    value: &serde_json::Value,
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> Option<i64> {
+    if mapping_obj.is_some_and(|o| o.contains_key("duration_transforms")) {
+        let text = transform_raw_value(mapping_obj, "duration", scalar_string(value)?)?;
+        return parse_duration_seconds_str(&text);
+    }
+    parse_duration_seconds_value(value)
+}
+
+fn scalar_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
 pub struct FetchResult {
     pub status: i32,
     pub content_type: Option<String>,
@@ -24,72 +194,322 @@ pub struct FetchResult {
     pub reported_title: Option<String>,
     pub reported_album: Option<String>,
     pub reported_at: Option<DateTime<FixedOffset>>,
+    pub reported_at_error: Option<String>,
     pub reported_duration_seconds: Option<i64>,
+    pub extended: ExtendedFields,
+    pub mapping_version_id: Option<Uuid>,
+}
+
+impl FetchResult {
+    pub fn apply_fields(&mut self, fields: ParsedFields, mapping_version_id: Option<Uuid>) {
+        self.reported_artist = fields.artist;
+        self.reported_title = fields.title;
+        self.reported_album = fields.album;
+        self.reported_at = fields.reported_at;
+        self.reported_at_error = fields.reported_at_error;
+        self.reported_duration_seconds = fields.duration_seconds;
+        self.extended = fields.extended;
+        self.mapping_version_id = mapping_version_id;
+    }
+}
+
+// The connection's mapping, with the station's timezone filled in as the
+// default `reported_at_timezone` so naive feed times are read as station local
+// time. Connections without a mapping read naive times as UTC.
+pub async fn load_connection_mapping<C: ConnectionTrait>(
+    db: &C,
+    conn: &now_playing_connections::Model,
+) -> Result<Option<payload_mappings::Model>, DbErr> {
+    let Some(mapping_id) = conn.payload_mapping_id else {
+        return Ok(None);
+    };
+    let Some(mut mapping) = payload_mappings::Entity::find_by_id(mapping_id).one(db).await? else {
+        return Ok(None);
+    };
+    apply_station_timezone(db, conn.station_id, &mut mapping.mapping_json).await?;
+    Ok(Some(mapping))
+}
+
+pub async fn apply_station_timezone<C: ConnectionTrait>(
+    db: &C,
+    station_id: Uuid,
+    mapping_json: &mut serde_json::Value,
+) -> Result<(), DbErr> {
+    if mapping_json.get("reported_at_timezone").is_none() {
+        let timezone = station_timezone(db, station_id).await?;
+        fill_station_timezone(mapping_json, timezone.as_deref());
+    }
+    Ok(())
+}
+
+pub async fn station_timezone<C: ConnectionTrait>(db: &C, station_id: Uuid) -> Result<Option<String>, DbErr> {
+    Ok(stations::Entity::find_by_id(station_id)
+        .one(db)
+        .await?
+        .and_then(|station| station.timezone))
+}
+
+// Sets `reported_at_timezone` unless the mapping picks its own.
+pub fn fill_station_timezone(mapping_json: &mut serde_json::Value, timezone: Option<&str>) {
+    if let Some(timezone) = timezone
+        && let Some(obj) = mapping_json.as_object_mut()
+    {
+        obj.entry("reported_at_timezone")
+            .or_insert_with(|| serde_json::Value::String(timezone.to_string()));
+    }
 }
 
 pub async fn poll_connection(db: &DatabaseConnection, conn: &now_playing_connections::Model) -> Result<(), DbErr> {
     let now = Utc::now().fixed_offset();
     
-    let mapping = if let Some(mapping_id) = conn.payload_mapping_id {
-        payload_mappings::Entity::find_by_id(mapping_id).one(db).await?
-    } else {
-        None
-    };
+    let mapping = load_connection_mapping(db, conn).await?;
+
+    if is_hls_connection_type(&conn.connection_type) {
+        return super::hls::poll_hls_connection(db, conn, mapping.as_ref(), now).await;
+    }
 
-    let result = match fetch_and_parse(conn, mapping.as_ref()).await {
+    let result = match fetch_and_parse(db, conn, mapping.as_ref()).await {
         Ok(res) => res,
-        Err(e) => {
-            let mut active_conn: now_playing_connections::ActiveModel = conn.clone().into();
-            active_conn.last_polled_at = Set(Some(now));
-            active_conn.last_status = Set(Some("FETCH_ERROR".to_string()));
-            active_conn.last_error = Set(Some(e.to_string()));
-            let next_error_backoff = next_error_backoff_seconds(conn.error_backoff_seconds);
-            active_conn.error_backoff_seconds = Set(next_error_backoff);
-            active_conn.same_song_backoff_seconds = Set(0);
-            active_conn.next_poll_at = Set(Some(schedule_after_seconds(conn.id, now, next_error_backoff as i64, 5)));
-            active_conn.update(db).await?;
-            return Ok(());
+        Err(e) => return record_fetch_error(db, conn, now, e.as_ref()).await,
+    };
+
+    process_payload(db, conn, mapping.as_ref(), &conn.connection_type, result, now).await
+}
+
+// Routes a fetched payload to list mode when the mapping asks for it and the
+// list yields timestamped plays, falling back to single-event handling.
+pub async fn process_payload(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    connection_type: &str,
+    mut result: FetchResult,
+    now: DateTime<FixedOffset>,
+) -> Result<(), DbErr> {
+    let selected;
+    let mapping = match resolve_payload_mapping(db, conn, mapping, &result.raw_payload, connection_type).await? {
+        MappingResolution::Fixed => mapping,
+        MappingResolution::Selected(mapping) => {
+            selected = mapping;
+            let fields = extract_fields(&result.raw_payload, Some(&selected), connection_type);
+            result.apply_fields(fields, selected.current_version_id);
+            Some(&selected)
+        }
+        MappingResolution::Mismatch(reason) => {
+            tracing::warn!(connection_id = %conn.id, "Payload doesn't match the mapping: {}", reason);
+            // Keep the new-shape sample so the mapping can be fixed against it.
+            let payload_str = serde_json::to_string(&result.raw_payload).unwrap_or_default();
+            let payload_hash = calculate_hash(conn.station_id, conn.id, &payload_str);
+            let reasons = [format!("MAPPING_MISMATCH: {}", reason)];
+            let key = RetainedKey::Payload(&payload_hash);
+            retain_rejected_event(db, conn, now, &result.raw_payload, key, &ParsedFields::default(), &reasons).await?;
+            return record_rejected_payload(db, conn, now, "MAPPING_MISMATCH", &reason).await;
         }
     };
 
-    process_fetch_result(db, conn, result, now).await?;
+    if is_list_mode_all(mapping) {
+        let items = extract_list_items(&result.raw_payload, mapping, connection_type);
+        if items.iter().any(ListItem::is_recordable) {
+            process_list_items(
+                db,
+                conn,
+                mapping,
+                result.status,
+                result.content_type,
+                items,
+                now,
+            )
+            .await?;
+            return Ok(());
+        }
+    }
 
+    process_fetch_result(db, conn, mapping, result, now).await
+}
+
+pub async fn record_fetch_error(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    now: DateTime<FixedOffset>,
+    error: &(dyn std::error::Error + Send + Sync + 'static),
+) -> Result<(), DbErr> {
+    let mut active_conn: now_playing_connections::ActiveModel = conn.clone().into();
+    active_conn.last_polled_at = Set(Some(now));
+    active_conn.last_status = Set(Some(fetch_error_status(error).to_string()));
+    active_conn.last_error = Set(Some(error.to_string()));
+    let next_error_backoff = next_error_backoff_seconds(conn.error_backoff_seconds);
+    active_conn.error_backoff_seconds = Set(next_error_backoff);
+    active_conn.same_song_backoff_seconds = Set(0);
+    active_conn.next_poll_at = Set(Some(schedule_after_seconds(conn.id, now, next_error_backoff as i64, 5)));
+    active_conn.update(db).await?;
+    Ok(())
+}
+
+fn fetch_error_status(error: &(dyn std::error::Error + Send + Sync + 'static)) -> &'static str {
+    if error.downcast_ref::<AuthError>().is_some() {
+        "AUTH_ERROR"
+    } else if error.downcast_ref::<ChainError>().is_some() {
+        "CHAIN_ERROR"
+    } else if error.downcast_ref::<ProxyError>().is_some() {
+        "PROXY_ERROR"
+    } else {
+        "FETCH_ERROR"
+    }
+}
+
+// For polls that succeed but have nothing new to record, e.g. an HLS playlist
+// that hasn't advanced.
+pub async fn record_idle_poll(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    now: DateTime<FixedOffset>,
+) -> Result<(), DbErr> {
+    let mut active_conn: now_playing_connections::ActiveModel = conn.clone().into();
+    active_conn.last_polled_at = Set(Some(now));
+    active_conn.last_status = Set(Some("OK".to_string()));
+    active_conn.last_error = Set(None);
+    active_conn.error_backoff_seconds = Set(0);
+    active_conn.next_poll_at = Set(Some(schedule_after_seconds(
+        conn.id,
+        now,
+        conn.poll_interval_seconds as i64,
+        5,
+    )));
+    active_conn.update(db).await?;
     Ok(())
 }
 
+pub fn build_http_client() -> reqwest::Result<reqwest::Client> {
+    http_client_builder().build()
+}
+
+pub fn build_connection_client(
+    proxy_url: Option<&str>,
+    jar: Option<Arc<CookieJar>>,
+) -> Result<reqwest::Client, Box<dyn std::error::Error + Send + Sync>> {
+    let mut builder = apply_proxy(http_client_builder(), proxy_url)?;
+    if let Some(jar) = jar {
+        builder = builder.cookie_provider(jar);
+    }
+    Ok(builder.build()?)
+}
+
+fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
+}
+
 pub async fn fetch_and_parse(
+    db: &DatabaseConnection,
     conn: &now_playing_connections::Model,
     mapping: Option<&payload_mappings::Model>,
 ) -> Result<FetchResult, Box<dyn std::error::Error + Send + Sync>> {
     if is_ws_connection_type(&conn.connection_type) {
         return Err("WebSocket connections are handled by the WS listener".into());
     }
+    if is_push_connection_type(&conn.connection_type) {
+        return Err("Push connections receive data through the ingest endpoint".into());
+    }
+    if is_listener_connection_type(&conn.connection_type) {
+        return Err("Socket listener connections are handled by the listener task".into());
+    }
 
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-        .build()?;
+    if is_hls_connection_type(&conn.connection_type) {
+        return super::hls::fetch_latest_hls_result(db, conn, mapping).await;
+    }
+
+    let proxy_url = resolve_proxy(db, conn).await?;
+    fetch_http(db, conn, mapping, proxy_url.as_deref())
+        .await
+        .map_err(|e| classify_proxy_error(e, proxy_url.as_deref()))
+}
 
+async fn fetch_http(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    proxy_url: Option<&str>,
+) -> Result<FetchResult, Box<dyn std::error::Error + Send + Sync>> {
+    let jar = conn
+        .cookie_jar_enabled
+        .then(|| Arc::new(CookieJar::from_value(conn.cookies_json.as_ref())));
+    let client = build_connection_client(proxy_url, jar.clone())?;
+    let warmup_url = conn.cookie_warmup_url.as_deref().filter(|u| !u.trim().is_empty());
+    let mut warmed_up = false;
+    if let Some(jar) = &jar
+        && let Some(warmup_url) = warmup_url
+        && jar.is_empty()
+    {
+        warm_up(&client, warmup_url).await;
+        warmed_up = true;
+    }
+
+    let credential = load_credential(db, conn).await?;
+    let mut auth = match &credential {
+        Some(c) => resolve_auth(db, &client, c, false).await?,
+        None => AuthParams::default(),
+    };
+
+    let mut chain = run_request_chain(&client, conn, &auth).await?;
     let (headers_map, used_default_headers) = resolve_headers_for_request(conn);
+    let (mut target_url, mut target_headers) = chain.render_request(&conn.url, &headers_map);
+    let (url, request_headers) = auth.apply(&target_url, &target_headers);
 
-    let mut resp = match send_request(&client, &conn.url, &headers_map).await {
+    let mut resp = match send_request(&client, &url, &request_headers).await {
         Ok(response) => response,
         Err(err) => {
             if used_default_headers {
                 let browser_headers = headers_value_to_map(
-                    &browser_headers_value(&conn.connection_type, &conn.url),
+                    &browser_headers_value(&conn.connection_type, &target_url),
                 );
-                send_request(&client, &conn.url, &browser_headers).await?
+                let (url, browser_headers) = auth.apply(&target_url, &browser_headers);
+                send_request(&client, &url, &browser_headers).await?
             } else {
                 return Err(err.into());
             }
         }
     };
 
+    if resp.status() == reqwest::StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+        return Err(ProxyError("proxy authentication required".into()).into());
+    }
+
+    // Expired or revoked tokens: refresh once and retry with the new token.
+    if should_refresh_after(resp.status(), credential.as_ref())
+        && let Some(c) = &credential
+    {
+        auth = resolve_auth(db, &client, c, true).await?;
+        let (url, request_headers) = auth.apply(&target_url, &target_headers);
+        resp = send_request(&client, &url, &request_headers).await?;
+    }
+
+    // Cached lookup values may have gone stale (new stream or session id).
+    if chain.from_cache && !resp.status().is_success() {
+        clear_chain_cache(conn.id);
+        chain = run_request_chain(&client, conn, &auth).await?;
+        (target_url, target_headers) = chain.render_request(&conn.url, &headers_map);
+        let (url, request_headers) = auth.apply(&target_url, &target_headers);
+        resp = send_request(&client, &url, &request_headers).await?;
+    }
+
+    // Stored cookies may have expired server-side; start a fresh session once.
+    if let Some(jar) = &jar
+        && let Some(warmup_url) = warmup_url
+        && !warmed_up
+        && !resp.status().is_success()
+    {
+        jar.clear();
+        warm_up(&client, warmup_url).await;
+        let (url, request_headers) = auth.apply(&target_url, &target_headers);
+        resp = send_request(&client, &url, &request_headers).await?;
+    }
+
     if used_default_headers && !resp.status().is_success() {
         let browser_headers = headers_value_to_map(
-            &browser_headers_value(&conn.connection_type, &conn.url),
+            &browser_headers_value(&conn.connection_type, &target_url),
         );
-        if let Ok(retry_resp) = send_request(&client, &conn.url, &browser_headers).await {
+        let (url, browser_headers) = auth.apply(&target_url, &browser_headers);
+        if let Ok(retry_resp) = send_request(&client, &url, &browser_headers).await {
             resp = retry_resp;
         }
     }
@@ -101,15 +521,49 @@ pub async fn fetch_and_parse(
         .map(|s| s.to_string());
 
     let body_bytes = resp.bytes().await?;
-    let raw_payload: serde_json::Value = if is_xml_connection_type(&conn.connection_type) {
-        let body_str = String::from_utf8_lossy(&body_bytes).to_string();
+    if let Some(jar) = &jar {
+        save_cookies(db, conn, jar).await?;
+    }
+    let raw_payload = payload_from_body(
+        &conn.connection_type,
+        &body_bytes,
+        content_type.as_deref(),
+        conn.response_charset.as_deref(),
+    );
+
+    let fields = extract_fields(&raw_payload, mapping, &conn.connection_type);
+
+    Ok(FetchResult {
+        status,
+        content_type,
+        raw_payload,
+        reported_artist: fields.artist,
+        reported_title: fields.title,
+        reported_album: fields.album,
+        reported_at: fields.reported_at,
+        reported_at_error: fields.reported_at_error,
+        reported_duration_seconds: fields.duration_seconds,
+        extended: fields.extended,
+        mapping_version_id: mapping.and_then(|m| m.current_version_id),
+    })
+}
+
+fn payload_from_body(
+    connection_type: &str,
+    body_bytes: &[u8],
+    content_type: Option<&str>,
+    charset: Option<&str>,
+) -> serde_json::Value {
+    let body_str = decode_body(body_bytes, content_type, charset);
+    if is_text_connection_type(connection_type) {
+        serde_json::Value::String(body_str)
+    } else if is_xml_connection_type(connection_type) {
         let normalized_xml = normalize_xml_storage(&body_str);
         serde_json::Value::String(normalized_xml)
-    } else if let Ok(json) = serde_json::from_slice(&body_bytes) {
+    } else if let Ok(json) = serde_json::from_str(body_str.trim_start_matches('\u{feff}')) {
         json
     } else {
         // Try XML if it looks like XML or if content-type suggests it
-        let body_str = String::from_utf8_lossy(&body_bytes).to_string();
         if body_str.trim_start().starts_with('<') {
             let normalized_xml = normalize_xml_storage(&body_str);
             let parse_xml = normalize_xml_for_parse(&normalized_xml);
@@ -118,29 +572,38 @@ pub async fn fetch_and_parse(
                 Err(_) => serde_json::Value::String(normalized_xml),
             }
         } else {
-            serde_json::Value::String(body_str.to_string())
+            serde_json::Value::String(body_str)
         }
-    };
+    }
+}
 
-    let (artist, title, album, reported_at, duration_seconds) = extract_fields(
-        &raw_payload,
-        mapping,
-        &conn.connection_type,
-    );
+// Picks the connection type whose parsing rules fit a pushed body, since a
+// `push` connection can receive JSON, XML or plain text.
+pub fn push_body_connection_type(content_type: Option<&str>, body_bytes: &[u8]) -> &'static str {
+    let mime = content_type
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_ascii_lowercase())
+        .unwrap_or_default();
 
-    Ok(FetchResult {
-        status,
-        content_type,
-        raw_payload,
-        reported_artist: artist,
-        reported_title: title,
-        reported_album: album,
-        reported_at,
-        reported_duration_seconds: duration_seconds,
-    })
+    if mime.ends_with("json") {
+        return "http_json";
+    }
+    if mime.ends_with("xml") {
+        return "http_xml";
+    }
+
+    let body_str = String::from_utf8_lossy(body_bytes);
+    let trimmed = body_str.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.starts_with('<') {
+        "http_xml"
+    } else if serde_json::from_str::<serde_json::Value>(trimmed).is_ok_and(|v| v.is_object() || v.is_array()) {
+        "http_json"
+    } else {
+        "http_text"
+    }
 }
 
-fn resolve_headers_for_request(
+pub fn resolve_headers_for_request(
     conn: &now_playing_connections::Model,
 ) -> (HashMap<String, String>, bool) {
     if !should_default_headers(&conn.connection_type) {
@@ -150,17 +613,17 @@ fn resolve_headers_for_request(
         return (HashMap::new(), false);
     }
 
-    if let Some(headers) = &conn.headers_json {
-        if headers.as_object().map(|obj| !obj.is_empty()).unwrap_or(false) {
-            return (headers_value_to_map(headers), false);
-        }
+    if let Some(headers) = &conn.headers_json
+        && headers.as_object().map(|obj| !obj.is_empty()).unwrap_or(false)
+    {
+        return (headers_value_to_map(headers), false);
     }
 
     let default_headers = default_headers_value(&conn.connection_type);
     (headers_value_to_map(&default_headers), true)
 }
 
-async fn send_request(
+pub async fn send_request(
     client: &reqwest::Client,
     url: &str,
     headers: &HashMap<String, String>,
@@ -176,11 +639,7 @@ pub async fn run_ws_connection(
     db: DatabaseConnection,
     conn: now_playing_connections::Model,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let mapping = if let Some(mapping_id) = conn.payload_mapping_id {
-        payload_mappings::Entity::find_by_id(mapping_id).one(&db).await?
-    } else {
-        None
-    };
+    let mapping = load_connection_mapping(&db, &conn).await?;
 
     let mut backoff_seconds = 1u64;
 
@@ -190,8 +649,16 @@ pub async fn run_ws_connection(
             return Ok(());
         }
 
-        match tokio_tungstenite::connect_async(&conn.url).await {
-            Ok((ws_stream, _)) => {
+        let connected = match resolve_proxy(&db, &conn).await? {
+            Some(proxy_url) => connect_ws_via_proxy(&proxy_url, &conn.url).await,
+            None => tokio_tungstenite::connect_async(&conn.url)
+                .await
+                .map(|(ws_stream, _)| ws_stream)
+                .map_err(|e| e.into()),
+        };
+
+        match connected {
+            Ok(ws_stream) => {
                 update_connection_status(&db, &conn, Some("WS_CONNECTED".to_string()), None).await?;
                 let (mut write, mut read) = ws_stream.split();
 
@@ -243,7 +710,12 @@ pub async fn run_ws_connection(
                 }
             }
             Err(e) => {
-                update_connection_status(&db, &conn, Some("WS_CONNECT_ERROR".to_string()), Some(e.to_string())).await?;
+                let status = if e.downcast_ref::<ProxyError>().is_some() {
+                    "PROXY_ERROR"
+                } else {
+                    "WS_CONNECT_ERROR"
+                };
+                update_connection_status(&db, &conn, Some(status.to_string()), Some(e.to_string())).await?;
             }
         }
 
@@ -259,29 +731,76 @@ async fn handle_ws_payload(
     raw_payload: serde_json::Value,
 ) -> Result<(), DbErr> {
     let now = Utc::now().fixed_offset();
-    let (artist, title, album, reported_at, duration_seconds) = extract_fields(
-        &raw_payload,
-        mapping,
-        &conn.connection_type,
-    );
+    let fields = extract_fields(&raw_payload, mapping, &conn.connection_type);
 
     let result = FetchResult {
         status: 200,
         content_type: Some("application/json".to_string()),
         raw_payload,
-        reported_artist: artist,
-        reported_title: title,
-        reported_album: album,
-        reported_at,
-        reported_duration_seconds: duration_seconds,
+        reported_artist: fields.artist,
+        reported_title: fields.title,
+        reported_album: fields.album,
+        reported_at: fields.reported_at,
+        reported_at_error: fields.reported_at_error,
+        reported_duration_seconds: fields.duration_seconds,
+        extended: fields.extended,
+        mapping_version_id: mapping.and_then(|m| m.current_version_id),
     };
 
-    process_fetch_result(db, conn, result, now).await
+    process_payload(db, conn, mapping, &conn.connection_type, result, now).await
+}
+
+pub async fn handle_push_payload(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    content_type: Option<String>,
+    body_bytes: &[u8],
+) -> Result<(), DbErr> {
+    let mapping = load_connection_mapping(db, conn).await?;
+
+    handle_raw_message(db, conn, mapping.as_ref(), content_type, body_bytes).await
 }
 
-async fn process_fetch_result(
+// Shared by push ingest and socket listeners: the body format is sniffed per
+// message because neither has a fixed payload type.
+pub async fn handle_raw_message(
     db: &DatabaseConnection,
     conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    content_type: Option<String>,
+    body_bytes: &[u8],
+) -> Result<(), DbErr> {
+    let now = Utc::now().fixed_offset();
+    let body_type = push_body_connection_type(content_type.as_deref(), body_bytes);
+    let raw_payload = payload_from_body(
+        body_type,
+        body_bytes,
+        content_type.as_deref(),
+        conn.response_charset.as_deref(),
+    );
+    let fields = extract_fields(&raw_payload, mapping, body_type);
+
+    let result = FetchResult {
+        status: 200,
+        content_type,
+        raw_payload,
+        reported_artist: fields.artist,
+        reported_title: fields.title,
+        reported_album: fields.album,
+        reported_at: fields.reported_at,
+        reported_at_error: fields.reported_at_error,
+        reported_duration_seconds: fields.duration_seconds,
+        extended: fields.extended,
+        mapping_version_id: mapping.and_then(|m| m.current_version_id),
+    };
+
+    process_payload(db, conn, mapping, body_type, result, now).await
+}
+
+pub async fn process_fetch_result(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
     result: FetchResult,
     now: DateTime<FixedOffset>,
 ) -> Result<(), DbErr> {
@@ -293,31 +812,43 @@ async fn process_fetch_result(
         reported_title,
         reported_album,
         reported_at,
+        reported_at_error,
         reported_duration_seconds,
+        extended,
+        mapping_version_id,
     } = result;
 
-    let artist_ok = reported_artist
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .is_some();
-
-    if !artist_ok {
-        tracing::error!(
-            connection_id = %conn.id,
-            "Skipping now-playing event: missing/empty artist"
-        );
-        let mut active_conn: now_playing_connections::ActiveModel = conn.clone().into();
-        active_conn.last_polled_at = Set(Some(now));
-        active_conn.last_status = Set(Some("INVALID_EVENT".to_string()));
-        active_conn.last_error = Set(Some("Missing artist".to_string()));
-        let next_error_backoff = next_error_backoff_seconds(conn.error_backoff_seconds);
-        active_conn.error_backoff_seconds = Set(next_error_backoff);
-        active_conn.same_song_backoff_seconds = Set(0);
-        active_conn.next_poll_at = Set(Some(schedule_after_seconds(conn.id, now, next_error_backoff as i64, 5)));
-        active_conn.update(db).await?;
-        return Ok(());
+    let fields = ParsedFields {
+        artist: reported_artist,
+        title: reported_title,
+        album: reported_album,
+        reported_at,
+        reported_at_error,
+        duration_seconds: reported_duration_seconds,
+        extended,
+    };
+    let verdict = load_validity_rules(db, conn.station_id, mapping).await?.evaluate(&fields);
+    if verdict.status == ValidityStatus::Rejected {
+        let reason = verdict.reasons.join("; ");
+        tracing::error!(connection_id = %conn.id, "Skipping now-playing event: {}", reason);
+        if verdict.retain {
+            let payload_str = serde_json::to_string(&raw_payload).unwrap_or_default();
+            let payload_hash = calculate_hash(conn.station_id, conn.id, &payload_str);
+            let key = RetainedKey::Payload(&payload_hash);
+            retain_rejected_event(db, conn, now, &raw_payload, key, &fields, &verdict.reasons).await?;
+        }
+        return record_rejected_payload(db, conn, now, "INVALID_EVENT", &reason).await;
     }
+    let classification = load_classifier(db, conn.station_id).await?.classify(&fields);
+    let ParsedFields {
+        artist: reported_artist,
+        title: reported_title,
+        album: reported_album,
+        reported_at,
+        reported_at_error,
+        duration_seconds: reported_duration_seconds,
+        extended,
+    } = fields;
 
     let payload_str = serde_json::to_string(&raw_payload).unwrap_or_default();
     let payload_hash = calculate_hash(conn.station_id, conn.id, &payload_str);
@@ -356,7 +887,7 @@ async fn process_fetch_result(
             station_id: Set(conn.station_id),
             connection_id: Set(conn.id),
             observed_at: Set(now),
-            reported_at: Set(reported_at.clone()),
+            reported_at: Set(reported_at),
             reported_artist: Set(reported_artist.clone()),
             reported_title: Set(reported_title.clone()),
             reported_album: Set(reported_album.clone()),
@@ -364,18 +895,194 @@ async fn process_fetch_result(
             payload_hash: Set(payload_hash),
             http_status: Set(Some(status)),
             content_type: Set(content_type.clone()),
+            mapping_version_id: Set(mapping_version_id),
+            reported_at_error: Set(reported_at_error),
+            reported_duration_seconds: Set(reported_duration_seconds),
+            expected_end_at: Set(expected_end_at(reported_at, reported_duration_seconds)),
+            isrc: Set(extended.isrc),
+            upc: Set(extended.upc),
+            artwork_url: Set(extended.artwork_url),
+            label: Set(extended.label),
+            composer: Set(extended.composer),
+            category: Set(extended.category),
+            item_type: Set(extended.item_type),
+            extra_fields: Set(extended.extra_fields.map(serde_json::Value::Object)),
+            validity_status: Set(verdict.status.as_str().to_string()),
+            validity_reasons: Set(verdict.reasons_json()),
+            content_class: Set(Some(classification.class.as_str().to_string())),
+            content_class_reason: Set(Some(classification.reason)),
+            created_at: Set(now),
+        };
+        event.insert(db).await?;
+    }
+
+    let (next_poll_at, next_same_song_backoff) =
+        next_poll_after_success(conn, now, is_duplicate, reported_at, reported_duration_seconds);
+    record_poll_success(db, conn, now, next_poll_at, next_same_song_backoff).await
+}
+
+// A payload was fetched but can't become an event; polling backs off as for
+// fetch errors.
+async fn record_rejected_payload(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    now: DateTime<FixedOffset>,
+    status: &str,
+    error: &str,
+) -> Result<(), DbErr> {
+    let mut active_conn: now_playing_connections::ActiveModel = conn.clone().into();
+    active_conn.last_polled_at = Set(Some(now));
+    active_conn.last_status = Set(Some(status.to_string()));
+    active_conn.last_error = Set(Some(error.to_string()));
+    let next_error_backoff = next_error_backoff_seconds(conn.error_backoff_seconds);
+    active_conn.error_backoff_seconds = Set(next_error_backoff);
+    active_conn.same_song_backoff_seconds = Set(0);
+    active_conn.next_poll_at = Set(Some(schedule_after_seconds(conn.id, now, next_error_backoff as i64, 5)));
+    active_conn.update(db).await?;
+    Ok(())
+}
+
+// List mode: one event per list item, oldest first. Items are matched against
+// stored plays for the station by reported_at + artist + title, so overlapping
+// polls only add plays that haven't been seen yet and gaps fill in on their own.
+pub async fn process_list_items(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    mapping: Option<&payload_mappings::Model>,
+    status: i32,
+    content_type: Option<String>,
+    items: Vec<ListItem>,
+    now: DateTime<FixedOffset>,
+) -> Result<usize, DbErr> {
+    let plays = recordable_plays(items);
+    let stored = stored_play_keys(db, conn.station_id, &plays).await?;
+    let rules = load_validity_rules(db, conn.station_id, mapping).await?;
+    let classifier = load_classifier(db, conn.station_id).await?;
+    let mapping_version_id = mapping.and_then(|m| m.current_version_id);
+
+    let mut inserted = 0;
+    for item in unseen_plays(&plays, stored) {
+        let fields = &item.fields;
+        let verdict = rules.evaluate(fields);
+        if verdict.status == ValidityStatus::Rejected {
+            tracing::debug!(connection_id = %conn.id, "Skipping list item: {}", verdict.reasons.join("; "));
+            if verdict.retain {
+                retain_rejected_event(db, conn, now, &item.payload, RetainedKey::Play, fields, &verdict.reasons)
+                    .await?;
+            }
+            continue;
+        }
+        let classification = classifier.classify(fields);
+
+        let payload_str = serde_json::to_string(&item.payload).unwrap_or_default();
+        let event = raw_now_playing_events::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            station_id: Set(conn.station_id),
+            connection_id: Set(conn.id),
+            observed_at: Set(now),
+            reported_at: Set(fields.reported_at),
+            reported_artist: Set(fields.artist.clone()),
+            reported_title: Set(fields.title.clone()),
+            reported_album: Set(fields.album.clone()),
+            raw_payload: Set(item.payload.clone()),
+            payload_hash: Set(calculate_hash(conn.station_id, conn.id, &payload_str)),
+            http_status: Set(Some(status)),
+            content_type: Set(content_type.clone()),
+            mapping_version_id: Set(mapping_version_id),
+            reported_at_error: Set(fields.reported_at_error.clone()),
+            reported_duration_seconds: Set(fields.duration_seconds),
+            expected_end_at: Set(expected_end_at(fields.reported_at, fields.duration_seconds)),
+            isrc: Set(fields.extended.isrc.clone()),
+            upc: Set(fields.extended.upc.clone()),
+            artwork_url: Set(fields.extended.artwork_url.clone()),
+            label: Set(fields.extended.label.clone()),
+            composer: Set(fields.extended.composer.clone()),
+            category: Set(fields.extended.category.clone()),
+            item_type: Set(fields.extended.item_type.clone()),
+            extra_fields: Set(fields.extended.extra_fields.clone().map(serde_json::Value::Object)),
+            validity_status: Set(verdict.status.as_str().to_string()),
+            validity_reasons: Set(verdict.reasons_json()),
+            content_class: Set(Some(classification.class.as_str().to_string())),
+            content_class_reason: Set(Some(classification.reason)),
             created_at: Set(now),
-            ..Default::default()
         };
         event.insert(db).await?;
+        inserted += 1;
     }
 
-    let (next_poll_at, next_same_song_backoff) = if is_duplicate {
+    // Schedule off the most recent play in the list.
+    let latest = plays.last().map(|item| &item.fields);
+    let (next_poll_at, next_same_song_backoff) = next_poll_after_success(
+        conn,
+        now,
+        inserted == 0,
+        latest.and_then(|f| f.reported_at),
+        latest.and_then(|f| f.duration_seconds),
+    );
+    record_poll_success(db, conn, now, next_poll_at, next_same_song_backoff).await?;
+
+    Ok(inserted)
+}
+
+// Items that can be recorded, oldest first.
+fn recordable_plays(items: Vec<ListItem>) -> Vec<ListItem> {
+    let mut plays: Vec<ListItem> = items.into_iter().filter(ListItem::is_recordable).collect();
+    plays.sort_by_key(|item| item.fields.reported_at);
+    plays
+}
+
+// The plays not yet stored, each once even if the list repeats it.
+fn unseen_plays(plays: &[ListItem], mut seen: HashSet<PlayKey>) -> Vec<&ListItem> {
+    plays.iter().filter(|item| seen.insert(item.play_key())).collect()
+}
+
+// Keys of the station's stored plays in the time span `plays` covers, so
+// overlapping polls skip what's already recorded and only fill the gaps.
+async fn stored_play_keys(
+    db: &DatabaseConnection,
+    station_id: Uuid,
+    plays: &[ListItem],
+) -> Result<HashSet<PlayKey>, DbErr> {
+    let (Some(first), Some(last)) = (
+        plays.first().and_then(|p| p.fields.reported_at),
+        plays.last().and_then(|p| p.fields.reported_at),
+    ) else {
+        return Ok(HashSet::new());
+    };
+    let stored = raw_now_playing_events::Entity::find()
+        .filter(raw_now_playing_events::Column::StationId.eq(station_id))
+        .filter(raw_now_playing_events::Column::ReportedAt.between(first, last))
+        .all(db)
+        .await?;
+    Ok(stored
+        .into_iter()
+        .map(|e| (e.reported_at, e.reported_artist, e.reported_title))
+        .collect())
+}
+
+// When a track with a known start and length should finish. Feeds that only
+// report a duration give no end time, since the start isn't known.
+pub fn expected_end_at(
+    reported_at: Option<DateTime<FixedOffset>>,
+    duration_seconds: Option<i64>,
+) -> Option<DateTime<FixedOffset>> {
+    let start = reported_at?;
+    let duration = chrono::Duration::try_seconds(duration_seconds?)?;
+    start.checked_add_signed(duration)
+}
+
+fn next_poll_after_success(
+    conn: &now_playing_connections::Model,
+    now: DateTime<FixedOffset>,
+    is_duplicate: bool,
+    reported_at: Option<DateTime<FixedOffset>>,
+    reported_duration_seconds: Option<i64>,
+) -> (Option<DateTime<FixedOffset>>, i32) {
+    if is_duplicate {
         let next_backoff = next_same_song_backoff_seconds(conn.same_song_backoff_seconds, conn.id, now);
         (Some(schedule_after_seconds(conn.id, now, next_backoff as i64, 5)), next_backoff)
     } else if conn.use_duration_polling {
-        if let (Some(start), Some(duration_s)) = (reported_at, reported_duration_seconds) {
-            let ends_at = start + chrono::Duration::seconds(duration_s);
+        if let Some(ends_at) = expected_end_at(reported_at, reported_duration_seconds) {
             let remaining = ends_at.signed_duration_since(now).num_seconds();
             let base_delay = if remaining > 0 {
                 // Poll shortly after the track is expected to end.
@@ -406,8 +1113,16 @@ async fn process_fetch_result(
             )),
             0,
         )
-    };
+    }
+}
 
+async fn record_poll_success(
+    db: &DatabaseConnection,
+    conn: &now_playing_connections::Model,
+    now: DateTime<FixedOffset>,
+    next_poll_at: Option<DateTime<FixedOffset>>,
+    next_same_song_backoff: i32,
+) -> Result<(), DbErr> {
     let mut active_conn: now_playing_connections::ActiveModel = conn.clone().into();
     active_conn.last_polled_at = Set(Some(now));
     active_conn.last_status = Set(Some("OK".to_string()));
@@ -417,182 +1132,499 @@ async fn process_fetch_result(
     active_conn.same_song_backoff_seconds = Set(next_same_song_backoff);
     active_conn.update(db).await?;
 
-    Ok(())
+    Ok(())
+}
+
+pub fn extract_fields(
+    payload: &serde_json::Value,
+    mapping: Option<&payload_mappings::Model>,
+    connection_type: &str,
+) -> ParsedFields {
+    extract_fields_traced(payload, mapping, connection_type).0
+}
+
+// Same as `extract_fields`, also returning each transform step so callers can
+// show how a value was cleaned up.
+pub fn extract_fields_traced(
+    payload: &serde_json::Value,
+    mapping: Option<&payload_mappings::Model>,
+    connection_type: &str,
+) -> (ParsedFields, Vec<FieldTrace>) {
+    let mut fields = extract_raw_fields(payload, mapping, connection_type);
+    let traces = apply_field_transforms(&mut fields, mapping.and_then(|m| m.mapping_json.as_object()));
+    (fields, traces)
 }
 
-fn extract_fields(
+fn extract_raw_fields(
     payload: &serde_json::Value,
     mapping: Option<&payload_mappings::Model>,
     connection_type: &str,
-) -> (
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<DateTime<FixedOffset>>,
-    Option<i64>,
-) {
+) -> ParsedFields {
+    if let Some(m) = mapping
+        && let Some(script) = mapping_script(m)
+    {
+        return script_fields(script, payload, m.mapping_json.as_object(), connection_type).unwrap_or_else(|e| {
+            tracing::warn!("Script mapping {} failed: {}", m.id, e);
+            ParsedFields::default()
+        });
+    }
+
+    if is_text_connection_type(connection_type)
+        && let Some(text) = payload.as_str()
+    {
+        return extract_text_fields(text, mapping.and_then(|m| m.mapping_json.as_object()));
+    }
+
     if let Some(m) = mapping {
         let mapping_obj = m.mapping_json.as_object();
 
-        if is_xml_connection_type(connection_type) {
-            if let Some(xml_str) = payload.as_str() {
-                let xml_values = extract_xml_values(xml_str);
-                let list_path = mapping_obj
-                    .and_then(|o| o.get("list_path"))
-                    .and_then(|v| v.as_str());
-
-                let artist = mapping_obj
-                    .and_then(|o| o.get("artist_path"))
-                    .and_then(|v| v.as_str())
-                    .and_then(|p| xml_lookup(&xml_values, list_path, p));
-
-                let title = mapping_obj
-                    .and_then(|o| o.get("title_path"))
-                    .and_then(|v| v.as_str())
-                    .and_then(|p| xml_lookup(&xml_values, list_path, p));
-
-                let album = mapping_obj
-                    .and_then(|o| o.get("album_path"))
-                    .and_then(|v| v.as_str())
-                    .and_then(|p| xml_lookup(&xml_values, list_path, p));
-
-                let reported_at = mapping_obj
-                    .and_then(|o| o.get("reported_at_path"))
-                    .and_then(|v| v.as_str())
-                    .and_then(|p| xml_lookup(&xml_values, list_path, p))
-                    .as_deref()
-                    .and_then(parse_reported_at);
-
-                let duration_seconds = mapping_obj
-                    .and_then(|o| o.get("duration_path"))
-                    .and_then(|v| v.as_str())
-                    .and_then(|p| xml_lookup(&xml_values, list_path, p))
-                    .as_deref()
-                    .and_then(parse_duration_seconds_str);
-
-                return (artist, title, album, reported_at, duration_seconds);
-            }
-        }
-
-        let mut candidates: Vec<&serde_json::Value> = vec![payload];
-        if let Some(obj) = payload.as_object() {
-            if obj.len() == 1 {
-                if let Some((_, value)) = obj.iter().next() {
-                    candidates.push(value);
-                }
-            }
+        if is_xml_connection_type(connection_type)
+            && let Some(xml_str) = payload.as_str()
+        {
+            let xml_values = extract_xml_values(xml_str);
+            let list_path = mapping_obj
+                .and_then(|o| o.get("list_path"))
+                .and_then(|v| v.as_str());
+            return xml_fields(xml_str, &xml_values, list_path, mapping_obj);
         }
 
-        for base in candidates {
+        for base in payload_candidates(payload) {
             let mut target_payload = base;
 
-            if let Some(list_path) = mapping_obj
-                .and_then(|o| o.get("list_path"))
-                .and_then(|v| v.as_str())
+            if let Some(first) = mapping_paths(mapping_obj, "list_path")
+                .into_iter()
+                .find_map(|p| first_list_item(base, p))
             {
-                if let Some(list) = get_path(base, list_path) {
-                    if let Some(arr) = list.as_array() {
-                        if let Some(first) = arr.first() {
-                            target_payload = first;
-                        }
-                    }
-                }
+                target_payload = first;
             }
 
-            let artist = mapping_obj
-                .and_then(|o| o.get("artist_path"))
-                .and_then(|v| v.as_str())
-                .and_then(|p| get_path(target_payload, p))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-
-            let title = mapping_obj
-                .and_then(|o| o.get("title_path"))
-                .and_then(|v| v.as_str())
-                .and_then(|p| get_path(target_payload, p))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-
-            let album = mapping_obj
-                .and_then(|o| o.get("album_path"))
-                .and_then(|v| v.as_str())
-                .and_then(|p| get_path(target_payload, p))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-
-            let reported_at = mapping_obj
-                .and_then(|o| o.get("reported_at_path"))
-                .and_then(|v| v.as_str())
-                .and_then(|p| get_path(target_payload, p))
-                .and_then(|v| v.as_str())
-                .and_then(parse_reported_at);
-
-            let duration_seconds = mapping_obj
-                .and_then(|o| o.get("duration_path"))
-                .and_then(|v| v.as_str())
-                .and_then(|p| get_path(target_payload, p))
-                .and_then(parse_duration_seconds_value);
-
-            if artist.is_some()
-                || title.is_some()
-                || album.is_some()
-                || reported_at.is_some()
-                || duration_seconds.is_some()
-            {
-                return (artist, title, album, reported_at, duration_seconds);
+            let fields = json_fields(target_payload, mapping_obj);
+            if !fields.is_empty() {
+                return fields;
             }
         }
 
-        return (None, None, None, None, None);
+        return ParsedFields::default();
     }
 
     // Best-effort extraction (legacy)
-    let mut artist = None;
-    let mut title = None;
-    let mut album = None;
-    let mut duration_seconds = None;
+    let mut fields = ParsedFields::default();
 
     if let Some(obj) = payload.as_object() {
-        artist = obj.get("artist").or_else(|| obj.get("artistName")).and_then(|v| v.as_str()).map(|s| s.to_string());
-        title = obj.get("title").or_else(|| obj.get("song")).or_else(|| obj.get("trackName")).and_then(|v| v.as_str()).map(|s| s.to_string());
-        album = obj.get("album").or_else(|| obj.get("collectionName")).and_then(|v| v.as_str()).map(|s| s.to_string());
-        duration_seconds = obj
+        fields.artist = obj.get("artist").or_else(|| obj.get("artistName")).and_then(|v| v.as_str()).map(|s| s.to_string());
+        fields.title = obj.get("title").or_else(|| obj.get("song")).or_else(|| obj.get("trackName")).and_then(|v| v.as_str()).map(|s| s.to_string());
+        fields.album = obj.get("album").or_else(|| obj.get("collectionName")).and_then(|v| v.as_str()).map(|s| s.to_string());
+        fields.duration_seconds = obj
             .get("duration")
             .or_else(|| obj.get("durationSeconds"))
             .or_else(|| obj.get("duration_seconds"))
             .and_then(parse_duration_seconds_value);
-    } else if let Some(arr) = payload.as_array() {
-        if let Some(first) = arr.first() {
-            return extract_fields(first, None, connection_type);
+    } else if let Some(first) = payload.as_array().and_then(|arr| arr.first()) {
+        return extract_raw_fields(first, None, connection_type);
+    }
+
+    fields
+}
+
+fn json_fields(
+    target: &serde_json::Value,
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> ParsedFields {
+    let artist = mapped_value(target, mapping_obj, "artist_path", |v| {
+        v.as_str().filter(|s| !s.is_empty()).map(|s| s.to_string())
+    });
+
+    let title = mapped_value(target, mapping_obj, "title_path", |v| {
+        v.as_str().filter(|s| !s.is_empty()).map(|s| s.to_string())
+    });
+
+    let album = mapped_value(target, mapping_obj, "album_path", |v| {
+        v.as_str().filter(|s| !s.is_empty()).map(|s| s.to_string())
+    });
+
+    let reported_at_values: Vec<String> = mapping_paths(mapping_obj, "reported_at_path")
+        .into_iter()
+        .flat_map(|p| select(target, p))
+        .filter_map(scalar_string)
+        .collect();
+    let (reported_at, reported_at_error) = reported_at_field(&reported_at_values, mapping_obj);
+
+    let duration_seconds = mapped_value(target, mapping_obj, "duration_path", |v| duration_field(v, mapping_obj));
+
+    let extended = ExtendedFields::extract(mapping_obj, false, |p| {
+        select(target, p).into_iter().find(|v| !v.is_null()).cloned()
+    });
+
+    ParsedFields {
+        artist,
+        title,
+        album,
+        reported_at,
+        reported_at_error,
+        duration_seconds,
+        extended,
+    }
+}
+
+fn xml_fields(
+    xml: &str,
+    xml_values: &HashMap<String, String>,
+    list_path: Option<&str>,
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> ParsedFields {
+    // The document is only parsed for XPath when a mapping path needs it.
+    let document = OnceCell::new();
+    let resolve = |path: &str| {
+        if !is_xpath_path(path) {
+            return xml_lookup(xml_values, list_path, path);
         }
+        let document = document
+            .get_or_init(|| XPathDocument::parse(xml))
+            .as_ref()
+            .ok()?;
+        document.evaluate(path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring mapping path: {}", e);
+            None
+        })
+    };
+    let lookup = |key: &str| mapping_paths(mapping_obj, key).into_iter().find_map(&resolve);
+    let reported_at_values: Vec<String> = mapping_paths(mapping_obj, "reported_at_path")
+        .into_iter()
+        .filter_map(&resolve)
+        .collect();
+    let (reported_at, reported_at_error) = reported_at_field(&reported_at_values, mapping_obj);
+
+    ParsedFields {
+        artist: lookup("artist_path"),
+        title: lookup("title_path"),
+        album: lookup("album_path"),
+        reported_at,
+        reported_at_error,
+        duration_seconds: mapping_paths(mapping_obj, "duration_path")
+            .into_iter()
+            .find_map(|p| resolve(p).and_then(|v| duration_field(&serde_json::Value::String(v), mapping_obj))),
+        extended: ExtendedFields::extract(mapping_obj, false, |p| resolve(p).map(serde_json::Value::String)),
     }
+}
 
-    (artist, title, album, None, duration_seconds)
+pub fn mapping_script(mapping: &payload_mappings::Model) -> Option<&str> {
+    mapping
+        .mapping_json
+        .get("script")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
 }
 
-fn parse_reported_at(value: &str) -> Option<DateTime<FixedOffset>> {
-    DateTime::parse_from_rfc3339(value)
-        .ok()
-        .or_else(|| DateTime::parse_from_str(value, "%d %b %Y %H:%M:%S").ok())
-        .or_else(|| {
-            let raw = value.trim();
-            let ts = raw.parse::<i64>().ok()?;
-            parse_epoch_seconds_or_millis(ts)
+// Runs a script mapping. XML and text payloads are also handed to the script
+// as flattened `values`, the same keys dotted paths would match.
+pub fn script_fields(
+    script: &str,
+    payload: &serde_json::Value,
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+    connection_type: &str,
+) -> Result<ParsedFields, String> {
+    let values = payload.as_str().and_then(|body| {
+        let values = if is_xml_connection_type(connection_type) {
+            extract_xml_values(body)
+        } else if is_text_connection_type(connection_type) {
+            extract_text_values(body, mapping_obj)
+        } else {
+            return None;
+        };
+        Some(
+            values
+                .into_iter()
+                .map(|(k, v)| (k, serde_json::Value::String(v)))
+                .collect(),
+        )
+    });
+    let settings = ReportedAtSettings::from_mapping(mapping_obj);
+    let input = ScriptInput {
+        payload,
+        values,
+        now: Utc::now().timestamp(),
+    };
+    let parse_time = move |s: &str| settings.parse(s).map(|dt| dt.timestamp());
+    let Some(output) = run_script(script, input, parse_time)? else {
+        return Ok(ParsedFields::default());
+    };
+
+    let text = |key: &str| output.get(key).and_then(scalar_string);
+    let reported_at_values: Vec<String> = output.get("reported_at").and_then(scalar_string).into_iter().collect();
+    let (reported_at, reported_at_error) = reported_at_field(&reported_at_values, mapping_obj);
+
+    let mut extended = ExtendedFields::default();
+    for field in EXTENDED_FIELDS {
+        if let Some(slot) = extended.slot_mut(field) {
+            *slot = text(field);
+        }
+    }
+    extended.extra_fields = output
+        .get("extra_fields")
+        .and_then(|v| v.as_object())
+        .map(|extras| {
+            extras
+                .iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<serde_json::Map<_, _>>()
         })
+        .filter(|extras| !extras.is_empty());
+
+    Ok(ParsedFields {
+        artist: text("artist"),
+        title: text("title"),
+        album: text("album"),
+        reported_at,
+        reported_at_error,
+        duration_seconds: output.get("duration").and_then(|v| duration_field(v, mapping_obj)),
+        extended,
+    })
 }
 
-fn parse_epoch_seconds_or_millis(ts: i64) -> Option<DateTime<FixedOffset>> {
-    // Heuristic: epoch millis are ~1.7e12, epoch seconds are ~1.7e9.
-    let millis = if ts.abs() > 100_000_000_000 {
-        ts
-    } else {
-        ts.saturating_mul(1000)
+// A feed that wraps everything in a single top-level key (`{"data": {...}}`)
+// is also tried one level down.
+pub fn payload_candidates(payload: &serde_json::Value) -> Vec<&serde_json::Value> {
+    let mut candidates: Vec<&serde_json::Value> = vec![payload];
+    if let Some(obj) = payload.as_object()
+        && obj.len() == 1
+        && let Some((_, value)) = obj.iter().next()
+    {
+        candidates.push(value);
+    }
+    candidates
+}
+
+// Recently-played feeds list several plays per response. Mappings opt into
+// recording all of them with `"list_mode": "all"`; otherwise only the first
+// list item is used.
+pub fn is_list_mode_all(mapping: Option<&payload_mappings::Model>) -> bool {
+    mapping
+        .and_then(|m| m.mapping_json.get("list_mode"))
+        .and_then(|v| v.as_str())
+        .map(|mode| mode.eq_ignore_ascii_case("all"))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone)]
+pub struct ListItem {
+    pub payload: serde_json::Value,
+    pub fields: ParsedFields,
+}
+
+// A play within a station: reported_at, artist and title, where a missing
+// value only matches another missing value.
+type PlayKey = (Option<DateTime<FixedOffset>>, Option<String>, Option<String>);
+
+impl ListItem {
+    fn play_key(&self) -> PlayKey {
+        (self.fields.reported_at, self.fields.artist.clone(), self.fields.title.clone())
+    }
+
+    // Without a timestamp a list item can't be told apart from a replay;
+    // whether it's a valid play is left to the validity rules.
+    fn is_recordable(&self) -> bool {
+        self.fields.reported_at.is_some()
+            && [&self.fields.artist, &self.fields.title]
+                .iter()
+                .any(|v| v.as_deref().is_some_and(|s| !s.trim().is_empty()))
+    }
+}
+
+pub fn extract_list_items(
+    payload: &serde_json::Value,
+    mapping: Option<&payload_mappings::Model>,
+    connection_type: &str,
+) -> Vec<ListItem> {
+    let Some(m) = mapping else {
+        return Vec::new();
+    };
+    let mapping_obj = m.mapping_json.as_object();
+
+    if is_xml_connection_type(connection_type) {
+        let Some(xml_str) = payload.as_str() else {
+            return Vec::new();
+        };
+        let Some(list_path) = mapping_obj
+            .and_then(|o| o.get("list_path"))
+            .and_then(|v| v.as_str())
+        else {
+            return Vec::new();
+        };
+        // Item documents are rooted at the list element itself.
+        let item_root = list_path.rsplit('.').next();
+        return xml_list_items(xml_str, list_path)
+            .into_iter()
+            .map(|item_xml| {
+                let mut fields = xml_fields(&item_xml, &extract_xml_values(&item_xml), item_root, mapping_obj);
+                apply_field_transforms(&mut fields, mapping_obj);
+                ListItem {
+                    payload: serde_json::Value::String(item_xml),
+                    fields,
+                }
+            })
+            .collect();
+    }
+
+    for base in payload_candidates(payload) {
+        let items = mapping_paths(mapping_obj, "list_path")
+            .into_iter()
+            .map(|p| list_items(base, p))
+            .find(|items| !items.is_empty())
+            .unwrap_or_default();
+        if !items.is_empty() {
+            return items
+                .into_iter()
+                .map(|item| {
+                    let mut fields = json_fields(item, mapping_obj);
+                    apply_field_transforms(&mut fields, mapping_obj);
+                    ListItem {
+                        payload: item.clone(),
+                        fields,
+                    }
+                })
+                .collect();
+        }
+    }
+
+    Vec::new()
+}
+
+fn list_items<'a>(payload: &'a serde_json::Value, path: &str) -> Vec<&'a serde_json::Value> {
+    let matches = select(payload, path);
+    match matches.as_slice() {
+        [single] if single.is_array() => single.as_array().map(|arr| arr.iter().collect()).unwrap_or_default(),
+        _ => matches,
+    }
+}
+
+// Splits an XML document into the serialized elements found at `list_path`
+// (dot-separated element names from the root), including self-closing ones
+// such as `<song artist="..." title="..."/>`.
+fn xml_list_items(xml: &str, list_path: &str) -> Vec<String> {
+    let normalized = normalize_xml_for_parse(xml);
+    let mut reader = Reader::from_str(&normalized);
+    let list_key = xml_key(list_path);
+    let mut tracker = XmlPathTracker::default();
+    let mut items = Vec::new();
+    let mut item_start: Option<usize> = None;
+
+    loop {
+        let position = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                tracker.open(&e);
+                if item_start.is_none() && tracker.path().as_deref() == Some(list_key.as_str()) {
+                    item_start = Some(position);
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                tracker.open(&e);
+                if item_start.is_none() && tracker.path().as_deref() == Some(list_key.as_str()) {
+                    let end = reader.buffer_position() as usize;
+                    items.push(normalized[position..end].trim().to_string());
+                }
+                tracker.close();
+            }
+            Ok(Event::End(_)) => {
+                if let Some(start) = item_start
+                    && tracker.path().as_deref() == Some(list_key.as_str())
+                {
+                    let end = reader.buffer_position() as usize;
+                    items.push(normalized[start..end].trim().to_string());
+                    item_start = None;
+                }
+                tracker.close();
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+    }
+
+    items
+}
+
+// A `<field>_path` entry is either a single path or an ordered list of
+// fallbacks, e.g. `"artist_path": ["artist", "performer"]`.
+pub fn mapping_paths<'a>(
+    mapping_obj: Option<&'a serde_json::Map<String, serde_json::Value>>,
+    key: &str,
+) -> Vec<&'a str> {
+    mapping_obj
+        .and_then(|o| o.get(key))
+        .map(value_paths)
+        .unwrap_or_default()
+}
+
+pub fn value_paths(value: &serde_json::Value) -> Vec<&str> {
+    match value {
+        serde_json::Value::String(path) => vec![path.as_str()],
+        serde_json::Value::Array(paths) => paths.iter().filter_map(|p| p.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn mapped_value<T>(
+    payload: &serde_json::Value,
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+    key: &str,
+    convert: impl Fn(&serde_json::Value) -> Option<T>,
+) -> Option<T> {
+    mapping_paths(mapping_obj, key)
+        .into_iter()
+        .find_map(|p| select(payload, p).into_iter().find_map(&convert))
+}
+
+// `list_path` may point at an array (its first element is used) or be a
+// filter expression that selects the items directly.
+fn first_list_item<'a>(payload: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let selected = select(payload, path);
+    match selected.as_slice() {
+        [serde_json::Value::Array(items)] => items.first(),
+        [first, ..] if path.contains('?') => Some(first),
+        _ => None,
+    }
+}
+
+fn extract_text_fields(
+    text: &str,
+    mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> ParsedFields {
+    let text_values = extract_text_values(text, mapping_obj);
+    // Without an explicit `<field>_path` the field name itself is the key, which
+    // matches regex capture names, `text_fields` entries and `ARTIST=` style keys.
+    let lookup = |field: &str| {
+        let keys = mapping_paths(mapping_obj, &format!("{}_path", field));
+        if keys.is_empty() {
+            text_lookup(&text_values, field)
+        } else {
+            keys.into_iter().find_map(|key| text_lookup(&text_values, key))
+        }
     };
-    let dt = chrono::DateTime::from_timestamp_millis(millis)?;
-    Some(dt.fixed_offset())
+
+    let reported_at_values: Vec<String> = lookup("reported_at").into_iter().collect();
+    let (reported_at, reported_at_error) = reported_at_field(&reported_at_values, mapping_obj);
+
+    ParsedFields {
+        artist: lookup("artist"),
+        title: lookup("title"),
+        album: lookup("album"),
+        reported_at,
+        reported_at_error,
+        duration_seconds: lookup("duration").and_then(|v| duration_field(&serde_json::Value::String(v), mapping_obj)),
+        extended: ExtendedFields::extract(mapping_obj, true, |key| {
+            text_lookup(&text_values, key).map(serde_json::Value::String)
+        }),
+    }
+}
+
+// Without mapping settings: offset-aware formats and epochs, naive times as UTC.
+pub fn parse_reported_at(value: &str) -> Option<DateTime<FixedOffset>> {
+    ReportedAtSettings::default().parse(value)
 }
 
-fn parse_duration_seconds_value(value: &serde_json::Value) -> Option<i64> {
+pub fn parse_duration_seconds_value(value: &serde_json::Value) -> Option<i64> {
     match value {
         serde_json::Value::Number(n) => {
             if let Some(i) = n.as_i64() {
@@ -607,7 +1639,7 @@ fn parse_duration_seconds_value(value: &serde_json::Value) -> Option<i64> {
     }
 }
 
-fn parse_duration_seconds_str(value: &str) -> Option<i64> {
+pub fn parse_duration_seconds_str(value: &str) -> Option<i64> {
     let s = value.trim();
     if s.is_empty() {
         return None;
@@ -628,9 +1660,7 @@ fn parse_iso8601_duration_seconds(value: &str) -> Option<i64> {
     let mut total: i64 = 0;
 
     while !rest.is_empty() {
-        let idx = rest
-            .find(|c: char| matches!(c, 'H' | 'M' | 'S'))
-            ?;
+        let idx = rest.find(['H', 'M', 'S'])?;
         let (num_str, unit_and_rest) = rest.split_at(idx);
         let unit = unit_and_rest.chars().next()?;
         let num = num_str.parse::<i64>().ok()?;
@@ -723,7 +1753,26 @@ pub fn is_ws_connection_type(connection_type: &str) -> bool {
     matches!(connection_type.to_ascii_lowercase().as_str(), "ws_json")
 }
 
-fn is_xml_connection_type(connection_type: &str) -> bool {
+pub fn is_push_connection_type(connection_type: &str) -> bool {
+    matches!(connection_type.to_ascii_lowercase().as_str(), "push")
+}
+
+pub fn is_hls_connection_type(connection_type: &str) -> bool {
+    matches!(connection_type.to_ascii_lowercase().as_str(), "hls_id3")
+}
+
+pub fn is_listener_connection_type(connection_type: &str) -> bool {
+    matches!(
+        connection_type.to_ascii_lowercase().as_str(),
+        "tcp_listen" | "udp_listen"
+    )
+}
+
+pub fn is_text_connection_type(connection_type: &str) -> bool {
+    matches!(connection_type.to_ascii_lowercase().as_str(), "http_text")
+}
+
+pub fn is_xml_connection_type(connection_type: &str) -> bool {
     matches!(
         connection_type.to_ascii_lowercase().as_str(),
         "http_xml" | "rss"
@@ -733,40 +1782,38 @@ fn is_xml_connection_type(connection_type: &str) -> bool {
 fn build_ws_subscribe_message(
     conn: &now_playing_connections::Model,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    if let Some(headers) = &conn.headers_json {
-        if let Some(obj) = headers.as_object() {
-            if let Some(payload) = obj.get("subscribe_payload").or_else(|| obj.get("subscribe_message")) {
-                if let Some(text) = payload.as_str() {
-                    return Ok(text.to_string());
-                }
-                return Ok(payload.to_string());
+    if let Some(obj) = conn.headers_json.as_ref().and_then(|h| h.as_object()) {
+        if let Some(payload) = obj.get("subscribe_payload").or_else(|| obj.get("subscribe_message")) {
+            if let Some(text) = payload.as_str() {
+                return Ok(text.to_string());
             }
+            return Ok(payload.to_string());
+        }
 
-            let service_id = obj.get("serviceId").or_else(|| obj.get("service_id"));
-            if let Some(value) = service_id {
-                let service_id_value = if let Some(text) = value.as_str() {
-                    serde_json::Value::String(text.to_string())
-                } else {
-                    value.clone()
-                };
-                let payload = serde_json::json!({
-                    "action": "subscribe",
-                    "serviceId": service_id_value,
-                });
-                return Ok(payload.to_string());
-            }
+        let service_id = obj.get("serviceId").or_else(|| obj.get("service_id"));
+        if let Some(value) = service_id {
+            let service_id_value = if let Some(text) = value.as_str() {
+                serde_json::Value::String(text.to_string())
+            } else {
+                value.clone()
+            };
+            let payload = serde_json::json!({
+                "action": "subscribe",
+                "serviceId": service_id_value,
+            });
+            return Ok(payload.to_string());
         }
     }
 
     Err("Missing subscribe_payload or serviceId in headers_json for ws_json connection".into())
 }
 
-async fn is_connection_enabled(db: &DatabaseConnection, id: Uuid) -> Result<bool, DbErr> {
+pub async fn is_connection_enabled(db: &DatabaseConnection, id: Uuid) -> Result<bool, DbErr> {
     let conn = now_playing_connections::Entity::find_by_id(id).one(db).await?;
     Ok(conn.map(|c| c.enabled).unwrap_or(false))
 }
 
-async fn update_connection_status(
+pub async fn update_connection_status(
     db: &DatabaseConnection,
     conn: &now_playing_connections::Model,
     status: Option<String>,
@@ -780,42 +1827,60 @@ async fn update_connection_status(
     Ok(())
 }
 
-fn extract_xml_values(xml: &str) -> HashMap<String, String> {
+// XML values are keyed two ways: the dotted element path
+// (`playlist.song.title`) holds the first occurrence, and the indexed path
+// (`playlist[0].song[2].title[0]`) holds every occurrence. Attributes extend
+// their element's path with `@name` (`playlist.song@artist`). Names are
+// namespace-local, so `<dc:creator>` is addressed as `creator`.
+pub fn extract_xml_values(xml: &str) -> HashMap<String, String> {
     let normalized = normalize_xml_for_parse(xml);
     let mut reader = Reader::from_str(&normalized);
-    reader.config_mut().trim_text(true);
     let mut buf = Vec::new();
-    let mut stack: Vec<String> = Vec::new();
+    let mut tracker = XmlPathTracker::default();
     let mut values: HashMap<String, String> = HashMap::new();
 
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                stack.push(name);
+                tracker.open(&e);
+                record_xml_attributes(&e, &tracker, &mut values);
+            }
+            Ok(Event::Empty(e)) => {
+                tracker.open(&e);
+                record_xml_attributes(&e, &tracker, &mut values);
+                tracker.close();
             }
             Ok(Event::End(_)) => {
-                stack.pop();
+                if let Some(frame) = tracker.stack.last() {
+                    let text = frame.text.trim();
+                    if !text.is_empty() {
+                        record_xml_value(&mut values, &tracker, "", text.to_string());
+                    }
+                }
+                tracker.close();
             }
             Ok(Event::Text(e)) => {
-                if let Ok(raw) = std::str::from_utf8(e.as_ref()) {
-                    if let Ok(unescaped) = unescape(raw) {
-                        let text = unescaped.into_owned();
-                        if !text.is_empty() {
-                            if let Some(path) = xml_stack_path(&stack) {
-                                values.entry(path).or_insert(text);
-                            }
-                        }
-                    }
+                if let Ok(raw) = std::str::from_utf8(e.as_ref())
+                    && let Ok(unescaped) = unescape(raw)
+                {
+                    tracker.push_text(&unescaped);
                 }
             }
             Ok(Event::CData(e)) => {
                 if let Ok(text) = std::str::from_utf8(e.as_ref()) {
-                    if !text.is_empty() {
-                        if let Some(path) = xml_stack_path(&stack) {
-                            values.entry(path).or_insert(text.to_string());
-                        }
-                    }
+                    tracker.push_text(text);
+                }
+            }
+            Ok(Event::GeneralRef(e)) => {
+                let resolved = match e.resolve_char_ref() {
+                    Ok(Some(c)) => Some(c.to_string()),
+                    _ => std::str::from_utf8(e.as_ref())
+                        .ok()
+                        .and_then(resolve_predefined_entity)
+                        .map(|s| s.to_string()),
+                };
+                if let Some(resolved) = resolved {
+                    tracker.push_text(&resolved);
                 }
             }
             Ok(Event::Eof) => break,
@@ -828,7 +1893,85 @@ fn extract_xml_values(xml: &str) -> HashMap<String, String> {
     values
 }
 
-fn xml_stack_path(stack: &[String]) -> Option<String> {
+struct XmlFrame {
+    name: String,
+    index: usize,
+    children: HashMap<String, usize>,
+    text: String,
+}
+
+// Tracks the open elements and how many of each name every parent has seen,
+// so repeated siblings get their own index.
+#[derive(Default)]
+struct XmlPathTracker {
+    stack: Vec<XmlFrame>,
+    roots: HashMap<String, usize>,
+}
+
+impl XmlPathTracker {
+    fn open(&mut self, e: &BytesStart) {
+        let name = xml_local_name(e.local_name().as_ref());
+        let counts = match self.stack.last_mut() {
+            Some(parent) => &mut parent.children,
+            None => &mut self.roots,
+        };
+        let count = counts.entry(name.clone()).or_insert(0);
+        let index = *count;
+        *count += 1;
+        self.stack.push(XmlFrame {
+            name,
+            index,
+            children: HashMap::new(),
+            text: String::new(),
+        });
+    }
+
+    fn close(&mut self) {
+        self.stack.pop();
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.text.push_str(text);
+        }
+    }
+
+    fn path(&self) -> Option<String> {
+        let names: Vec<String> = self.stack.iter().map(|f| f.name.clone()).collect();
+        xml_stack_path(&names)
+    }
+
+    fn indexed_path(&self) -> Option<String> {
+        let names: Vec<String> = self.stack.iter().map(|f| format!("{}[{}]", f.name, f.index)).collect();
+        xml_stack_path(&names)
+    }
+}
+
+fn record_xml_value(values: &mut HashMap<String, String>, tracker: &XmlPathTracker, suffix: &str, value: String) {
+    if let (Some(path), Some(indexed)) = (tracker.path(), tracker.indexed_path()) {
+        values.entry(format!("{}{}", path, suffix)).or_insert_with(|| value.clone());
+        values.insert(format!("{}{}", indexed, suffix), value);
+    }
+}
+
+fn record_xml_attributes(e: &BytesStart, tracker: &XmlPathTracker, values: &mut HashMap<String, String>) {
+    for attr in e.attributes().flatten() {
+        if attr.key.as_namespace_binding().is_some() {
+            continue;
+        }
+        let Ok(value) = attr.unescape_value() else {
+            continue;
+        };
+        let name = xml_local_name(attr.key.local_name().as_ref());
+        record_xml_value(values, tracker, &format!("@{}", name), value.into_owned());
+    }
+}
+
+pub fn xml_local_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).to_string()
+}
+
+pub fn xml_stack_path(stack: &[String]) -> Option<String> {
     if stack.is_empty() {
         None
     } else {
@@ -836,24 +1979,73 @@ fn xml_stack_path(stack: &[String]) -> Option<String> {
     }
 }
 
-fn xml_lookup(
+// Mapping paths may carry namespace prefixes (`dc:creator`), which are
+// dropped to match the stored local names. A path with any `[n]` index is
+// resolved against the indexed keys, treating unindexed elements as `[0]`.
+fn xml_key(path: &str) -> String {
+    let (elements, attribute) = match path.split_once('@') {
+        Some((elements, attribute)) => (elements, Some(strip_xml_prefix(attribute))),
+        None => (path, None),
+    };
+    let indexed = elements.contains('[');
+    let mut key = elements
+        .split('.')
+        .filter(|s| !s.is_empty())
+        .map(|segment| {
+            let (name, index) = match segment.split_once('[') {
+                Some((name, rest)) => (name, rest.trim_end_matches(']')),
+                None => (segment, "0"),
+            };
+            if indexed {
+                format!("{}[{}]", strip_xml_prefix(name), index.trim())
+            } else {
+                strip_xml_prefix(name).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".");
+    if let Some(attribute) = attribute {
+        key.push('@');
+        key.push_str(attribute);
+    }
+    key
+}
+
+fn strip_xml_prefix(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+pub fn xml_lookup(
     values: &HashMap<String, String>,
     list_path: Option<&str>,
     field_path: &str,
 ) -> Option<String> {
-    let combined = list_path
-        .map(|base| format!("{}.{}", base, field_path))
-        .unwrap_or_else(|| field_path.to_string());
+    let combined = match list_path {
+        Some(base) if field_path.starts_with('@') => format!("{}{}", base, field_path),
+        Some(base) => format!("{}.{}", base, field_path),
+        None => field_path.to_string(),
+    };
+    let combined = xml_key(&combined);
+    let field_key = xml_key(field_path);
 
     if let Some(value) = values.get(&combined) {
         return Some(value.clone());
     }
 
-    let needle = format!(".{}", field_path);
+    // Suffix matches stay within the same key style so an unindexed path
+    // never lands on a later repetition.
+    let indexed = field_key.contains('[');
+    let needle = if field_key.starts_with('@') {
+        field_key.clone()
+    } else {
+        format!(".{}", field_key)
+    };
     values
         .iter()
         .find_map(|(key, value)| {
-            if key == field_path || key.ends_with(&needle) {
+            if key.contains('[') != indexed {
+                None
+            } else if *key == field_key || key.ends_with(&needle) {
                 Some(value.clone())
             } else {
                 None
@@ -862,36 +2054,21 @@ fn xml_lookup(
 }
 
 fn normalize_xml_storage(input: &str) -> String {
-    input.replace('\n', "").replace('\t', "").replace('\r', "")
+    input.replace(['\n', '\t', '\r'], "")
 }
 
-fn normalize_xml_for_parse(input: &str) -> String {
+pub fn normalize_xml_for_parse(input: &str) -> String {
     let normalized = normalize_xml_storage(input);
-    if normalized.trim_start().starts_with("<?xml") {
-        if let Some(idx) = normalized.find("?>") {
-            return normalized[idx + 2..].to_string();
-        }
+    if normalized.trim_start().starts_with("<?xml")
+        && let Some(idx) = normalized.find("?>")
+    {
+        return normalized[idx + 2..].to_string();
     }
     normalized
 }
 
-fn get_path<'a>(val: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
-    let mut curr = val;
-    for part in path.split('.') {
-        if part.is_empty() {
-            continue;
-        }
-        if let Some(obj) = curr.as_object() {
-            if let Some(next) = obj.get(part) {
-                curr = next;
-            } else {
-                return None;
-            }
-        } else {
-            return None;
-        }
-    }
-    Some(curr)
+pub fn get_path<'a>(val: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    select_first(val, path)
 }
 
 fn calculate_hash(station_id: Uuid, conn_id: Uuid, payload: &str) -> String {
@@ -905,6 +2082,7 @@ fn calculate_hash(station_id: Uuid, conn_id: Uuid, payload: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::poller::fixtures::test_mapping;
 
     #[test]
     fn parses_duration_seconds_from_strings_and_numbers() {
@@ -920,6 +2098,18 @@ mod tests {
         assert_eq!(parse_duration_seconds_value(&serde_json::json!("PT180S")), Some(180));
     }
 
+    #[test]
+    fn expected_end_needs_start_and_duration() {
+        let start = DateTime::parse_from_rfc3339("2026-10-18T10:00:00Z").unwrap();
+        assert_eq!(
+            expected_end_at(Some(start), Some(263)),
+            Some(DateTime::parse_from_rfc3339("2026-10-18T10:04:23Z").unwrap())
+        );
+        assert_eq!(expected_end_at(None, Some(263)), None);
+        assert_eq!(expected_end_at(Some(start), None), None);
+        assert_eq!(expected_end_at(Some(start), Some(i64::MAX)), None);
+    }
+
     #[test]
     fn jitter_is_bounded_and_deterministic() {
         let conn_id = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
@@ -952,6 +2142,271 @@ mod tests {
         assert_eq!(b5, 120);
     }
 
+    #[test]
+    fn extracts_text_fields_with_and_without_mapping() {
+        let payload = serde_json::json!("ARTIST=Tom Petty\nTITLE=Free Fallin'\nLENGTH=00256");
+        let fields = extract_fields(&payload, None, "http_text");
+        assert_eq!(fields.artist.as_deref(), Some("Tom Petty"));
+        assert_eq!(fields.title.as_deref(), Some("Free Fallin'"));
+        assert_eq!(fields.duration_seconds, None);
+
+        let mapping = test_mapping(serde_json::json!({ "duration_path": "LENGTH" }));
+        let fields = extract_fields(&payload, Some(&mapping), "http_text");
+        assert_eq!(fields.artist.as_deref(), Some("Tom Petty"));
+        assert_eq!(fields.duration_seconds, Some(256));
+    }
+
+    #[test]
+    fn mapping_paths_support_filters_and_fallbacks() {
+        let payload = serde_json::json!({
+            "items": [
+                { "type": "spot", "title": "Station ID" },
+                { "type": "song", "title": "Runnin' Down a Dream", "performer": "Tom Petty", "length": 263 }
+            ]
+        });
+        let mapping = test_mapping(serde_json::json!({
+            "list_path": "items[?(@.type=='song')]",
+            "artist_path": ["artist", "performer"],
+            "title_path": "title",
+            "duration_path": ["duration", "length"]
+        }));
+        let fields = extract_fields(&payload, Some(&mapping), "http_json");
+        assert_eq!(fields.artist.as_deref(), Some("Tom Petty"));
+        assert_eq!(fields.title.as_deref(), Some("Runnin' Down a Dream"));
+        assert_eq!(fields.duration_seconds, Some(263));
+    }
+
+    #[test]
+    fn extracts_extended_metadata_and_extra_fields() {
+        let json_mapping = test_mapping(serde_json::json!({
+            "artist_path": "artist",
+            "isrc_path": ["isrc", "codes.isrc"],
+            "artwork_url_path": "images.large",
+            "item_type_path": "type",
+            "upc_path": "upc",
+            "extra_fields": { "cart": "cut.cart", "bpm": ["bpm", "tempo"], "missing": "nope" }
+        }));
+        let payload = serde_json::json!({
+            "artist": "Heart",
+            "codes": { "isrc": "USEP17700005" },
+            "images": { "large": "https://example.com/heart.jpg" },
+            "type": "song",
+            "upc": 74646506924u64,
+            "cut": { "cart": "S1234" },
+            "tempo": 137
+        });
+        let fields = extract_fields(&payload, Some(&json_mapping), "http_json");
+        assert_eq!(fields.extended.isrc.as_deref(), Some("USEP17700005"));
+        assert_eq!(fields.extended.artwork_url.as_deref(), Some("https://example.com/heart.jpg"));
+        assert_eq!(fields.extended.item_type.as_deref(), Some("song"));
+        assert_eq!(fields.extended.upc.as_deref(), Some("74646506924"));
+        let extras = fields.extended.extra_fields.unwrap();
+        assert_eq!(extras["cart"], "S1234");
+        assert_eq!(extras["bpm"], 137);
+        assert!(!extras.contains_key("missing"));
+
+        let xml_mapping = test_mapping(serde_json::json!({
+            "label_path": "track.label",
+            "extra_fields": { "cart": "track.cart" }
+        }));
+        let xml = "<track><label>Epic</label><cart>S1234</cart></track>";
+        let fields = extract_fields(&serde_json::Value::String(xml.to_string()), Some(&xml_mapping), "http_xml");
+        assert_eq!(fields.extended.label.as_deref(), Some("Epic"));
+        assert_eq!(fields.extended.extra_fields.unwrap()["cart"], "S1234");
+
+        // Text keys default to the field name.
+        let text_mapping = test_mapping(serde_json::json!({ "text_format": "key_value" }));
+        let text = "artist=Heart\ntitle=Barracuda\ncomposer=Ann Wilson\ncategory=A1";
+        let fields = extract_fields(&serde_json::Value::String(text.to_string()), Some(&text_mapping), "http_text");
+        assert_eq!(fields.extended.composer.as_deref(), Some("Ann Wilson"));
+        assert_eq!(fields.extended.category.as_deref(), Some("A1"));
+        assert_eq!(fields.extended.extra_fields, None);
+    }
+
+    #[test]
+    fn extracts_every_item_in_list_mode() {
+        let json_mapping = test_mapping(serde_json::json!({
+            "list_mode": "all",
+            "list_path": "recent",
+            "artist_path": "artist",
+            "title_path": "title",
+            "reported_at_path": "played_at"
+        }));
+        let payload = serde_json::json!({
+            "recent": [
+                { "artist": "Heart", "title": "Barracuda", "played_at": "2026-10-18T10:04:00Z" },
+                { "artist": "Boston", "title": "Foreplay", "played_at": "2026-10-18T10:00:00Z" }
+            ]
+        });
+        assert!(is_list_mode_all(Some(&json_mapping)));
+        let items = extract_list_items(&payload, Some(&json_mapping), "http_json");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].fields.artist.as_deref(), Some("Boston"));
+        assert_eq!(items[1].payload["title"], "Foreplay");
+        assert!(items.iter().all(ListItem::is_recordable));
+
+        let xml_mapping = test_mapping(serde_json::json!({
+            "list_mode": "all",
+            "list_path": "history.song",
+            "artist_path": "artist",
+            "title_path": "title",
+            "reported_at_path": "time"
+        }));
+        let xml = "<?xml version=\"1.0\"?>\n<history>\n  <song><artist>Heart</artist><title>Barracuda</title><time>2026-10-18T10:04:00Z</time></song>\n  <song><artist>Boston</artist><time>2026-10-18T10:00:00Z</time></song>\n</history>";
+        let items = extract_list_items(&serde_json::Value::String(xml.to_string()), Some(&xml_mapping), "http_xml");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].fields.title.as_deref(), Some("Barracuda"));
+        assert_eq!(items[1].fields.artist.as_deref(), Some("Boston"));
+        assert_eq!(items[1].fields.title, None);
+        assert!(items[1].payload.as_str().unwrap().starts_with("<song>"));
+
+        assert!(!is_list_mode_all(Some(&test_mapping(serde_json::json!({ "list_path": "recent" })))));
+    }
+
+    fn play(artist: Option<&str>, title: Option<&str>, reported_at: Option<&str>) -> ListItem {
+        ListItem {
+            payload: serde_json::json!({ "artist": artist, "title": title }),
+            fields: ParsedFields {
+                artist: artist.map(str::to_string),
+                title: title.map(str::to_string),
+                reported_at: reported_at.map(|t| DateTime::parse_from_rfc3339(t).unwrap()),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn titles(plays: &[&ListItem]) -> Vec<Option<String>> {
+        plays.iter().map(|p| p.fields.title.clone()).collect()
+    }
+
+    #[test]
+    fn records_only_timestamped_named_plays_oldest_first() {
+        let plays = recordable_plays(vec![
+            play(Some("Heart"), Some("Barracuda"), Some("2026-10-18T10:08:00Z")),
+            play(Some("Boston"), Some("Foreplay"), None),
+            play(Some(" "), None, Some("2026-10-18T10:06:00Z")),
+            play(None, Some("Station ID"), Some("2026-10-18T10:04:00Z")),
+            play(Some("Kansas"), Some("Carry On"), Some("2026-10-18T10:00:00Z")),
+        ]);
+        let titles: Vec<_> = plays.iter().map(|p| p.fields.title.as_deref()).collect();
+        assert_eq!(titles, vec![Some("Carry On"), Some("Station ID"), Some("Barracuda")]);
+    }
+
+    #[test]
+    fn overlapping_polls_only_fill_the_gaps() {
+        let first_poll = recordable_plays(vec![
+            play(Some("Kansas"), Some("Carry On"), Some("2026-10-18T10:00:00Z")),
+            play(Some("Heart"), None, Some("2026-10-18T10:08:00Z")),
+        ]);
+        let stored: HashSet<PlayKey> = unseen_plays(&first_poll, HashSet::new())
+            .into_iter()
+            .map(ListItem::play_key)
+            .collect();
+        assert_eq!(stored.len(), 2);
+
+        // The next poll repeats both, including the title-less one, and
+        // reveals a play between them that the first poll missed.
+        let second_poll = recordable_plays(vec![
+            play(Some("Heart"), None, Some("2026-10-18T10:08:00Z")),
+            play(Some("Boston"), Some("Foreplay"), Some("2026-10-18T10:04:00Z")),
+            play(Some("Boston"), Some("Foreplay"), Some("2026-10-18T10:04:00Z")),
+            play(Some("Heart"), Some("Barracuda"), Some("2026-10-18T10:08:00Z")),
+            play(Some("Kansas"), Some("Carry On"), Some("2026-10-18T10:00:00Z")),
+        ]);
+        let new = unseen_plays(&second_poll, stored);
+        assert_eq!(titles(&new), vec![Some("Foreplay".to_string()), Some("Barracuda".to_string())]);
+    }
+
+    #[test]
+    fn addresses_xml_attributes_indexes_and_namespaces() {
+        let xml = "<?xml version=\"1.0\"?>\n<rss xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><channel>\n  <item id=\"1\"><title>Barracuda</title><dc:creator>Heart</dc:creator></item>\n  <item id=\"2\"><title>Hall &amp; Oates &#8211; Kiss On My List</title><dc:creator>Hall &amp; Oates</dc:creator></item>\n</channel></rss>";
+        let values = extract_xml_values(xml);
+        assert_eq!(xml_lookup(&values, None, "item.title").as_deref(), Some("Barracuda"));
+        assert_eq!(xml_lookup(&values, None, "item[1].dc:creator").as_deref(), Some("Hall & Oates"));
+        assert_eq!(xml_lookup(&values, None, "item[1].title").as_deref(), Some("Hall & Oates \u{2013} Kiss On My List"));
+        assert_eq!(xml_lookup(&values, None, "rss.channel.item[1]@id").as_deref(), Some("2"));
+        assert_eq!(xml_lookup(&values, Some("rss.channel.item"), "creator").as_deref(), Some("Heart"));
+        assert_eq!(xml_lookup(&values, None, "item[5].title"), None);
+
+        let mapping = test_mapping(serde_json::json!({
+            "list_mode": "all",
+            "list_path": "playlist.song",
+            "artist_path": "@artist",
+            "title_path": "@title",
+            "reported_at_path": "@played"
+        }));
+        let xml = "<playlist><song artist=\"Heart\" title=\"Barracuda\" played=\"2026-10-18T10:04:00Z\"/><song artist=\"Boston\" title=\"Foreplay\" played=\"2026-10-18T10:00:00Z\"/></playlist>";
+        let payload = serde_json::Value::String(xml.to_string());
+        let items = extract_list_items(&payload, Some(&mapping), "http_xml");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].fields.artist.as_deref(), Some("Boston"));
+        assert_eq!(items[1].fields.title.as_deref(), Some("Foreplay"));
+        assert!(items.iter().all(ListItem::is_recordable));
+
+        let fields = extract_fields(&payload, Some(&mapping), "http_xml");
+        assert_eq!(fields.artist.as_deref(), Some("Heart"));
+    }
+
+    #[test]
+    fn mixes_xpath_and_dotted_paths() {
+        let mapping = test_mapping(serde_json::json!({
+            "artist_path": "//Current/Song/@Artist",
+            "title_path": ["//Current/Song/@Missing", "Current.Song@Title"],
+            "duration_path": "number(//Current/Song/@Duration) div 1000",
+            "album_path": "//Current/Song["
+        }));
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Schedule><Current><Song Artist=\"Journey\" Title=\"Separate Ways\" Duration=\"323000\"/></Current></Schedule>";
+        let fields = extract_fields(&serde_json::Value::String(xml.to_string()), Some(&mapping), "rss");
+        assert_eq!(fields.artist.as_deref(), Some("Journey"));
+        assert_eq!(fields.title.as_deref(), Some("Separate Ways"));
+        assert_eq!(fields.duration_seconds, Some(323));
+        assert_eq!(fields.album, None);
+    }
+
+    #[test]
+    fn runs_script_mappings_against_xml_values() {
+        let mapping = test_mapping(serde_json::json!({
+            "script": "#{ artist: values[\"Schedule.Current.Song@Artist\"], title: values[\"Schedule.Next.Song@Title\"], \
+                reported_at: values[\"Schedule.Current.Song@Start\"], duration: 251, extra_fields: #{ cart: \"0420\" } }",
+            "reported_at_timezone": "America/Chicago",
+            "title_transforms": [{ "op": "case", "mode": "upper" }]
+        }));
+        let xml = "<Schedule><Current><Song Artist=\"Journey\" Start=\"2026-10-17 14:03:22\"/></Current>\
+            <Next><Song Title=\"Separate Ways\"/></Next></Schedule>";
+        let fields = extract_fields(&serde_json::Value::String(xml.to_string()), Some(&mapping), "http_xml");
+        assert_eq!(fields.artist.as_deref(), Some("Journey"));
+        assert_eq!(fields.title.as_deref(), Some("SEPARATE WAYS"));
+        assert_eq!(fields.reported_at.unwrap().to_rfc3339(), "2026-10-17T14:03:22-05:00");
+        assert_eq!(fields.duration_seconds, Some(251));
+        assert_eq!(fields.extended.extra_fields.unwrap()["cart"], "0420");
+
+        let err = script_fields("payload.missing.field", &serde_json::json!({}), None, "http_json").unwrap_err();
+        assert!(!err.is_empty());
+    }
+
+    #[test]
+    fn transforms_times_and_durations_before_parsing() {
+        let mapping = test_mapping(serde_json::json!({
+            "reported_at_path": "started",
+            "duration_path": "length",
+            "reported_at_transforms": [{ "op": "regex_replace", "pattern": "^Started ", "replacement": "" }],
+            "duration_transforms": [{ "op": "split", "delimiter": " ", "index": 0 }]
+        }));
+        let payload = serde_json::json!({ "started": "Started 2026-10-18T10:04:00Z", "length": "245 sec" });
+        let fields = extract_fields(&payload, Some(&mapping), "http_json");
+        assert_eq!(fields.reported_at.unwrap().to_rfc3339(), "2026-10-18T10:04:00+00:00");
+        assert_eq!(fields.duration_seconds, Some(245));
+    }
+
+    #[test]
+    fn detects_push_body_format_from_content_type_or_body() {
+        assert_eq!(push_body_connection_type(Some("application/json; charset=utf-8"), b"{}"), "http_json");
+        assert_eq!(push_body_connection_type(Some("text/xml"), b"<a/>"), "http_xml");
+        assert_eq!(push_body_connection_type(None, b"  <nowplaying/>"), "http_xml");
+        assert_eq!(push_body_connection_type(None, br#"{"artist":"A"}"#), "http_json");
+        assert_eq!(push_body_connection_type(Some("text/plain"), b"A - B"), "http_text");
+    }
+
     #[test]
     fn parses_reported_at_from_epoch_seconds_and_millis() {
         let s = parse_reported_at("1700000000").unwrap();
@@ -0,0 +1,382 @@
+use regex::Regex;
+use sea_orm::{prelude::*, Set};
+use chrono::{DateTime, FixedOffset};
+use crate::entities::{now_playing_connections, payload_mappings, rejected_events, stations};
+use crate::mapping_spec::{expand_block_pattern, EventField, RuleAction, ValidityRule};
+use super::utils::ParsedFields;
+
+// Validity rules decide what happens to an extracted event before it's stored:
+//
+// "validity_rules": [
+//   { "required": ["artist"], "retain": true },
+//   { "name": "imaging", "block_patterns": ["^{callsign}$", "commercial break"], "action": "store_non_music" },
+//   { "fields": ["title"], "max_length": 120, "action": "store_flagged" }
+// ]
+//
+// A mapping's rules replace the station's; with neither, events without an
+// artist are rejected. Rejected events aren't stored (rules with `retain` keep
+// a copy in `rejected_events`); the others are stored with a validity_status
+// and the reasons that applied.
+
+const ALL_FIELDS: [EventField; 12] = [
+    EventField::Artist,
+    EventField::Title,
+    EventField::Album,
+    EventField::ReportedAt,
+    EventField::Duration,
+    EventField::Isrc,
+    EventField::Upc,
+    EventField::ArtworkUrl,
+    EventField::Label,
+    EventField::Composer,
+    EventField::Category,
+    EventField::ItemType,
+];
+
+const DEFAULT_TEXT_FIELDS: [EventField; 2] = [EventField::Artist, EventField::Title];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidityStatus {
+    Valid,
+    Flagged,
+    NonMusic,
+    Rejected,
+}
+
+impl ValidityStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValidityStatus::Valid => "VALID",
+            ValidityStatus::Flagged => "FLAGGED",
+            ValidityStatus::NonMusic => "NON_MUSIC",
+            ValidityStatus::Rejected => "REJECTED",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    pub status: ValidityStatus,
+    pub reasons: Vec<String>,
+    // A failing reject rule asked for the payload to be kept.
+    pub retain: bool,
+}
+
+impl Verdict {
+    pub fn reasons_json(&self) -> Option<serde_json::Value> {
+        (!self.reasons.is_empty()).then(|| serde_json::json!(self.reasons))
+    }
+}
+
+struct CompiledRule {
+    rule: ValidityRule,
+    // (source pattern, compiled) pairs; patterns naming a station value the
+    // station doesn't have are left out.
+    patterns: Vec<(String, Regex)>,
+}
+
+pub struct ValidityRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl ValidityRules {
+    pub fn compile(rules: Vec<ValidityRule>, station: Option<&stations::Model>) -> Self {
+        let callsign = station.and_then(|s| s.callsign.as_deref());
+        let station_name = station.map(|s| s.name.as_str());
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let patterns = rule
+                    .block_patterns
+                    .iter()
+                    .filter_map(|pattern| {
+                        let expanded = expand_block_pattern(pattern, callsign, station_name)?;
+                        Regex::new(&expanded).ok().map(|re| (pattern.clone(), re))
+                    })
+                    .collect();
+                CompiledRule { rule, patterns }
+            })
+            .collect();
+        ValidityRules { rules }
+    }
+
+    pub fn evaluate(&self, fields: &ParsedFields) -> Verdict {
+        let mut verdict = Verdict {
+            status: ValidityStatus::Valid,
+            reasons: Vec::new(),
+            retain: false,
+        };
+        for compiled in &self.rules {
+            let failures = rule_failures(compiled, fields);
+            if failures.is_empty() {
+                continue;
+            }
+            let rule = &compiled.rule;
+            let status = match rule.action {
+                RuleAction::Reject => ValidityStatus::Rejected,
+                RuleAction::StoreNonMusic => ValidityStatus::NonMusic,
+                RuleAction::StoreFlagged => ValidityStatus::Flagged,
+            };
+            verdict.status = stricter(verdict.status, status);
+            verdict.retain |= rule.retain && rule.action == RuleAction::Reject;
+            verdict.reasons.extend(failures.into_iter().map(|failure| match &rule.name {
+                Some(name) => format!("{}: {}", name, failure),
+                None => failure,
+            }));
+        }
+        verdict
+    }
+}
+
+fn stricter(a: ValidityStatus, b: ValidityStatus) -> ValidityStatus {
+    let rank = |s| match s {
+        ValidityStatus::Valid => 0,
+        ValidityStatus::Flagged => 1,
+        ValidityStatus::NonMusic => 2,
+        ValidityStatus::Rejected => 3,
+    };
+    if rank(b) > rank(a) { b } else { a }
+}
+
+fn rule_failures(compiled: &CompiledRule, fields: &ParsedFields) -> Vec<String> {
+    let rule = &compiled.rule;
+    let mut failures = Vec::new();
+    for field in &rule.required {
+        if field_value(fields, *field).is_none() {
+            failures.push(format!("Missing {}", field.name()));
+        }
+    }
+    if !rule.required_any.is_empty() && rule.required_any.iter().all(|f| field_value(fields, *f).is_none()) {
+        let names: Vec<&str> = rule.required_any.iter().map(|f| f.name()).collect();
+        failures.push(format!("Missing all of {}", names.join(", ")));
+    }
+
+    let text_fields = if rule.fields.is_empty() { &DEFAULT_TEXT_FIELDS[..] } else { &rule.fields[..] };
+    for field in text_fields {
+        let Some(value) = field_value(fields, *field) else {
+            continue;
+        };
+        let length = value.chars().count();
+        if let Some(min) = rule.min_length
+            && length < min
+        {
+            failures.push(format!("{} is shorter than {} characters", field.name(), min));
+        }
+        if let Some(max) = rule.max_length
+            && length > max
+        {
+            failures.push(format!("{} is longer than {} characters", field.name(), max));
+        }
+        if let Some((pattern, _)) = compiled.patterns.iter().find(|(_, re)| re.is_match(&value)) {
+            failures.push(format!("{} matches blocked pattern `{}`", field.name(), pattern));
+        }
+    }
+    failures
+}
+
+// Trimmed, non-empty field values as the rules see them.
+pub fn field_value(fields: &ParsedFields, field: EventField) -> Option<String> {
+    let extended = &fields.extended;
+    let text = match field {
+        EventField::ReportedAt => return fields.reported_at.map(|t| t.to_rfc3339()),
+        EventField::Duration => return fields.duration_seconds.map(|d| d.to_string()),
+        EventField::Artist => &fields.artist,
+        EventField::Title => &fields.title,
+        EventField::Album => &fields.album,
+        EventField::Isrc => &extended.isrc,
+        EventField::Upc => &extended.upc,
+        EventField::ArtworkUrl => &extended.artwork_url,
+        EventField::Label => &extended.label,
+        EventField::Composer => &extended.composer,
+        EventField::Category => &extended.category,
+        EventField::ItemType => &extended.item_type,
+    };
+    text.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+// The mapping's rules, else the station's, else the built-in missing-artist rule.
+pub async fn load_validity_rules<C: ConnectionTrait>(
+    db: &C,
+    station_id: Uuid,
+    mapping: Option<&payload_mappings::Model>,
+) -> Result<ValidityRules, DbErr> {
+    let station = stations::Entity::find_by_id(station_id).one(db).await?;
+    let rules = mapping
+        .and_then(|m| m.mapping_json.get("validity_rules"))
+        .and_then(|v| parse_rules(v, "mapping"))
+        .or_else(|| {
+            station
+                .as_ref()
+                .and_then(|s| s.validity_rules_json.as_ref())
+                .and_then(|v| parse_rules(v, "station"))
+        })
+        .unwrap_or_else(ValidityRule::default_rules);
+    Ok(ValidityRules::compile(rules, station.as_ref()))
+}
+
+fn parse_rules(value: &serde_json::Value, source: &str) -> Option<Vec<ValidityRule>> {
+    match serde_json::from_value(value.clone()) {
+        Ok(rules) => Some(rules),
+        Err(e) => {
+            tracing::warn!("Ignoring invalid {} validity_rules: {}", source, e);
+            None
+        }
+    }
+}
+
+// How a retained payload is recognised when a later poll sees it again: list
+// items by the play they describe, whole payloads by their hash.
+pub enum RetainedKey<'a> {
+    Play,
+    Payload(&'a str),
+}
+
+fn retained_copy(station_id: Uuid, key: &RetainedKey, fields: &ParsedFields) -> Select<rejected_events::Entity> {
+    let query = rejected_events::Entity::find().filter(rejected_events::Column::StationId.eq(station_id));
+    match key {
+        RetainedKey::Payload(hash) => query.filter(rejected_events::Column::PayloadHash.eq(*hash)),
+        RetainedKey::Play => {
+            let text = |column: rejected_events::Column, value: &Option<String>| match value {
+                Some(v) => column.eq(v.clone()),
+                None => column.is_null(),
+            };
+            let reported_at = match fields.reported_at {
+                Some(t) => rejected_events::Column::ReportedAt.eq(t),
+                None => rejected_events::Column::ReportedAt.is_null(),
+            };
+            query
+                .filter(reported_at)
+                .filter(text(rejected_events::Column::ReportedArtist, &fields.artist))
+                .filter(text(rejected_events::Column::ReportedTitle, &fields.title))
+        }
+    }
+}
+
+// Keeps a rejected event, or a payload no mapping fits, for auditing, once:
+// polls that see it again add nothing.
+pub async fn retain_rejected_event<C: ConnectionTrait>(
+    db: &C,
+    conn: &now_playing_connections::Model,
+    now: DateTime<FixedOffset>,
+    raw_payload: &serde_json::Value,
+    key: RetainedKey<'_>,
+    fields: &ParsedFields,
+    reasons: &[String],
+) -> Result<(), DbErr> {
+    if retained_copy(conn.station_id, &key, fields).one(db).await?.is_some() {
+        return Ok(());
+    }
+    let extracted: serde_json::Map<String, serde_json::Value> = ALL_FIELDS
+        .iter()
+        .filter_map(|f| field_value(fields, *f).map(|v| (f.name().to_string(), serde_json::Value::String(v))))
+        .collect();
+    rejected_events::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        station_id: Set(conn.station_id),
+        connection_id: Set(conn.id),
+        observed_at: Set(now),
+        reported_at: Set(fields.reported_at),
+        reported_artist: Set(fields.artist.clone()),
+        reported_title: Set(fields.title.clone()),
+        raw_payload: Set(raw_payload.clone()),
+        payload_hash: Set(match key {
+            RetainedKey::Payload(hash) => Some(hash.to_string()),
+            RetainedKey::Play => None,
+        }),
+        extracted_json: Set(serde_json::Value::Object(extracted)),
+        reasons: Set(serde_json::json!(reasons)),
+        created_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::fixtures::test_station;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    fn rules(value: serde_json::Value, callsign: Option<&str>) -> ValidityRules {
+        let station = test_station(callsign);
+        ValidityRules::compile(serde_json::from_value(value).unwrap(), Some(&station))
+    }
+
+    fn fields(artist: Option<&str>, title: Option<&str>) -> ParsedFields {
+        ParsedFields {
+            artist: artist.map(str::to_string),
+            title: title.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_rule_rejects_missing_artist() {
+        let rules = ValidityRules::compile(ValidityRule::default_rules(), None);
+        let verdict = rules.evaluate(&fields(Some("  "), Some("Barracuda")));
+        assert_eq!(verdict.status, ValidityStatus::Rejected);
+        assert_eq!(verdict.reasons, vec!["Missing artist".to_string()]);
+        assert!(!verdict.retain);
+        assert_eq!(rules.evaluate(&fields(Some("Heart"), None)).status, ValidityStatus::Valid);
+    }
+
+    #[test]
+    fn strictest_action_wins_and_reasons_accumulate() {
+        let rules = rules(
+            serde_json::json!([
+                { "required_any": ["artist", "title"], "retain": true },
+                { "name": "imaging", "block_patterns": ["^{callsign}\\b", "commercial break"], "action": "store_non_music" },
+                { "fields": ["title"], "max_length": 10, "action": "store_flagged" }
+            ]),
+            Some("KXYZ"),
+        );
+
+        let verdict = rules.evaluate(&fields(Some("kxyz"), Some("Commercial Break")));
+        assert_eq!(verdict.status, ValidityStatus::NonMusic);
+        assert_eq!(
+            verdict.reasons,
+            vec![
+                "imaging: artist matches blocked pattern `^{callsign}\\b`".to_string(),
+                "imaging: title matches blocked pattern `commercial break`".to_string(),
+                "title is longer than 10 characters".to_string(),
+            ]
+        );
+        assert_eq!(verdict.reasons_json(), Some(serde_json::json!(verdict.reasons)));
+
+        let verdict = rules.evaluate(&fields(None, None));
+        assert_eq!(verdict.status, ValidityStatus::Rejected);
+        assert!(verdict.retain);
+
+        // Without a callsign the placeholder pattern is skipped.
+        let rules = self::rules(
+            serde_json::json!([{ "block_patterns": ["^{callsign}$"], "action": "store_flagged" }]),
+            None,
+        );
+        assert_eq!(rules.evaluate(&fields(Some("KXYZ"), None)).status, ValidityStatus::Valid);
+    }
+
+    #[test]
+    fn finds_retained_copies_by_play_or_payload_hash() {
+        let station_id = Uuid::nil();
+        let sql = |key: RetainedKey, fields: &ParsedFields| {
+            let query = retained_copy(station_id, &key, fields).build(DbBackend::Postgres).to_string();
+            query.split_once(" WHERE ").unwrap().1.to_string()
+        };
+
+        let play = ParsedFields {
+            reported_at: Some(DateTime::parse_from_rfc3339("2026-10-18T10:00:00Z").unwrap()),
+            ..fields(Some("KXYZ"), None)
+        };
+        let query = sql(RetainedKey::Play, &play);
+        assert!(query.contains(r#""station_id" = '00000000-0000-0000-0000-000000000000'"#));
+        assert!(query.contains(r#""reported_at" = '2026-10-18 10:00:00.000000 +00:00'"#));
+        assert!(query.contains(r#""reported_artist" = 'KXYZ'"#));
+        assert!(query.contains(r#""reported_title" IS NULL"#));
+        assert!(!query.contains("payload_hash"));
+
+        let query = sql(RetainedKey::Payload("abc123"), &play);
+        assert!(query.contains(r#""payload_hash" = 'abc123'"#));
+        assert!(!query.contains("reported_artist"));
+    }
+}
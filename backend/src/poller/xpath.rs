@@ -0,0 +1,181 @@
+use sxd_document::dom::{ChildOfRoot, Document};
+use sxd_document::{parser, Package};
+use sxd_xpath::{Context, Factory, Value};
+use crate::mapping_spec::is_xpath_path;
+use super::utils::{mapping_paths, normalize_xml_for_parse};
+
+// XPath 1.0 for `http_xml` and `rss` mappings. A path such as `//item[1]/title`
+// or `string(//Current/Song/@Artist)` is evaluated against the whole stored
+// document (or the item document in list mode); dotted paths keep going
+// through `xml_lookup`. Prefixes declared on the root element, like `dc:` in
+// RSS feeds, can be used in expressions.
+
+pub struct XPathDocument {
+    package: Package,
+}
+
+impl XPathDocument {
+    pub fn parse(xml: &str) -> Result<Self, String> {
+        parser::parse(&normalize_xml_for_parse(xml))
+            .map(|package| Self { package })
+            .map_err(|e| format!("invalid XML: {}", e))
+    }
+
+    // The first node in document order for node-sets; strings, numbers and
+    // booleans as text. Empty results are `None`.
+    pub fn evaluate(&self, expr: &str) -> Result<Option<String>, String> {
+        let xpath = Factory::new()
+            .build(expr)
+            .map_err(|e| format!("invalid XPath {:?}: {}", expr, e))?
+            .ok_or_else(|| format!("invalid XPath {:?}: empty expression", expr))?;
+
+        let document = self.package.as_document();
+        let namespaces = root_namespaces(&document);
+        // sxd-xpath panics on an unbound prefix, so check them up front.
+        if let Some(prefix) = name_prefixes(expr)
+            .into_iter()
+            .find(|prefix| !namespaces.iter().any(|(bound, _)| bound == prefix))
+        {
+            return Err(format!("XPath {:?} failed: unknown namespace prefix `{}`", expr, prefix));
+        }
+        let mut context = Context::new();
+        for (prefix, uri) in &namespaces {
+            context.set_namespace(prefix, uri);
+        }
+        let value = xpath
+            .evaluate(&context, document.root())
+            .map_err(|e| format!("XPath {:?} failed: {}", expr, e))?;
+
+        let text = match value {
+            Value::Nodeset(nodes) => nodes.document_order_first().map(|node| node.string_value()),
+            Value::String(s) => Some(s),
+            Value::Number(n) if n.is_finite() && n.fract() == 0.0 => Some((n as i64).to_string()),
+            Value::Number(n) if n.is_finite() => Some(n.to_string()),
+            Value::Number(_) => None,
+            Value::Boolean(b) => Some(b.to_string()),
+        };
+        Ok(text.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()))
+    }
+}
+
+fn root_namespaces(document: &Document) -> Vec<(String, String)> {
+    document
+        .root()
+        .children()
+        .into_iter()
+        .find_map(|child| match child {
+            ChildOfRoot::Element(element) => Some(element),
+            _ => None,
+        })
+        .map(|element| {
+            element
+                .namespaces_in_scope()
+                .into_iter()
+                .map(|ns| (ns.prefix().to_string(), ns.uri().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Prefixes of qualified names (`dc:creator`, `x:*`) outside string literals.
+// Axis separators (`child::`) are not prefixes.
+fn name_prefixes(expr: &str) -> Vec<String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut prefixes = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+        } else if c == '"' || c == '\'' {
+            quote = Some(c);
+        } else if c == ':' && chars.get(i + 1) != Some(&':') && (i == 0 || chars[i - 1] != ':') {
+            let start = (0..i).rev().take_while(|&j| is_name_char(chars[j])).last();
+            let followed_by_name = chars.get(i + 1).is_some_and(|n| n.is_alphabetic() || *n == '_' || *n == '*');
+            if let Some(start) = start
+                && followed_by_name
+            {
+                prefixes.push(chars[start..i].iter().collect());
+            }
+        }
+        i += 1;
+    }
+    prefixes
+}
+
+// Every XPath in the mapping that doesn't compile or fails against `xml`,
+// keyed by its mapping entry, so the test endpoint can show why a field is
+// empty.
+pub fn xpath_errors(xml: &str, mapping_obj: Option<&serde_json::Map<String, serde_json::Value>>) -> Vec<String> {
+    let Some(obj) = mapping_obj else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<(String, &str)> = obj
+        .keys()
+        .filter(|key| key.ends_with("_path") && key.as_str() != "list_path")
+        .flat_map(|key| mapping_paths(mapping_obj, key).into_iter().map(move |p| (key.clone(), p)))
+        .collect();
+    if let Some(extras) = obj.get("extra_fields").and_then(|v| v.as_object()) {
+        for name in extras.keys() {
+            let key = format!("extra_fields.{}", name);
+            for path in mapping_paths(Some(extras), name) {
+                paths.push((key.clone(), path));
+            }
+        }
+    }
+    paths.retain(|(_, path)| is_xpath_path(path));
+    if paths.is_empty() {
+        return Vec::new();
+    }
+
+    let document = match XPathDocument::parse(xml) {
+        Ok(document) => document,
+        Err(e) => return vec![e],
+    };
+    paths
+        .into_iter()
+        .filter_map(|(key, path)| document.evaluate(path).err().map(|e| format!("{}: {}", key, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_paths_attributes_and_functions() {
+        let xml = "<rss xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><channel>\
+            <item><title>Barracuda</title><dc:creator>Heart</dc:creator></item>\
+            <item><title>Foreplay</title><dc:creator>Boston</dc:creator></item>\
+            </channel><Current><Song Artist=\"Journey\" Duration=\"251\"/></Current></rss>";
+        let document = XPathDocument::parse(xml).unwrap();
+        assert_eq!(document.evaluate("//item[2]/title").unwrap().as_deref(), Some("Foreplay"));
+        assert_eq!(document.evaluate("//item/dc:creator").unwrap().as_deref(), Some("Heart"));
+        assert_eq!(document.evaluate("//Current/Song/@Artist").unwrap().as_deref(), Some("Journey"));
+        assert_eq!(document.evaluate("count(//item)").unwrap().as_deref(), Some("2"));
+        assert_eq!(document.evaluate("//item[3]/title").unwrap(), None);
+        assert!(document.evaluate("//item[").unwrap_err().starts_with("invalid XPath \"//item[\""));
+        assert!(document.evaluate("//x:album").unwrap_err().contains("unknown namespace prefix `x`"));
+        assert_eq!(name_prefixes("//child::dc:creator[. = 'a:b']"), vec!["dc".to_string()]);
+    }
+
+    #[test]
+    fn reports_broken_expressions_by_key() {
+        let mapping = serde_json::json!({
+            "artist_path": "//item/artist",
+            "title_path": ["//item[", "title"],
+            "album_path": "//x:album",
+            "extra_fields": { "cart": "//cart[" }
+        });
+        let errors = xpath_errors("<item><artist>Heart</artist></item>", mapping.as_object());
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().any(|e| e.starts_with("title_path: invalid XPath")));
+        assert!(errors.iter().any(|e| e.starts_with("album_path: XPath \"//x:album\" failed")));
+        assert!(errors.iter().any(|e| e.starts_with("extra_fields.cart: invalid XPath")));
+    }
+}
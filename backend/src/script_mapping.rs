@@ -0,0 +1,194 @@
+use base64::Engine as _;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use serde_json::{Map, Value};
+use std::time::{Duration, Instant};
+
+// Script mappings for payloads that need real logic. A mapping with a `script`
+// key runs it in an embedded Rhai engine instead of evaluating `<field>_path`
+// entries. The script sees:
+//
+// - `payload`: the stored payload (a map/array for JSON, a string for XML and text)
+// - `values`: flattened key/value pairs for XML and text payloads
+// - `now`: the current time as epoch seconds
+//
+// plus `parse_json(s)`, `base64_decode(s)` and `parse_time(s)` (epoch seconds
+// or `()`), and must evaluate to a map of fields or `()` when nothing matches:
+//
+// let item = payload.now.filter(|it| it.start <= now).pop();
+// if item == () { return; }
+// #{ artist: item.artist, title: item.title, duration: item.length }
+//
+// The engine has no file, network or module access; operations, nesting,
+// string/array/map sizes and wall-clock time are capped.
+
+pub const SCRIPT_OUTPUT_KEYS: [&str; 13] = [
+    "artist",
+    "title",
+    "album",
+    "reported_at",
+    "duration",
+    "isrc",
+    "upc",
+    "artwork_url",
+    "label",
+    "composer",
+    "category",
+    "item_type",
+    "extra_fields",
+];
+
+const MAX_OPERATIONS: u64 = 200_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 4 * 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 10_000;
+const MAX_MAP_SIZE: usize = 10_000;
+const MAX_RUN_TIME: Duration = Duration::from_millis(250);
+
+pub struct ScriptInput<'a> {
+    pub payload: &'a Value,
+    pub values: Option<Map<String, Value>>,
+    pub now: i64,
+}
+
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {})
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_map_size(MAX_MAP_SIZE);
+
+    engine.register_fn("parse_json", |s: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        let value: Value = serde_json::from_str(s).map_err(|e| format!("parse_json: {}", e))?;
+        rhai::serde::to_dynamic(value)
+    });
+    engine.register_fn("base64_decode", |s: &str| -> Result<String, Box<EvalAltResult>> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(s.trim())
+            .map_err(|e| format!("base64_decode: {}", e))?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    });
+    engine
+}
+
+pub fn compile_script(source: &str) -> Result<AST, String> {
+    sandboxed_engine().compile(source).map_err(|e| e.to_string())
+}
+
+// Runs `source` against one payload. `parse_time` backs the script's
+// `parse_time(s)` so it reads timestamps the same way `reported_at` is read.
+pub fn run_script(
+    source: &str,
+    input: ScriptInput,
+    parse_time: impl Fn(&str) -> Option<i64> + 'static,
+) -> Result<Option<Map<String, Value>>, String> {
+    let mut engine = sandboxed_engine();
+    engine.register_fn("parse_time", move |s: &str| match parse_time(s) {
+        Some(ts) => Dynamic::from(ts),
+        None => Dynamic::UNIT,
+    });
+    let deadline = Instant::now() + MAX_RUN_TIME;
+    engine.on_progress(move |_| (Instant::now() > deadline).then(|| Dynamic::from("timeout")));
+
+    let ast = engine.compile(source).map_err(|e| e.to_string())?;
+    let mut scope = Scope::new();
+    scope.push_constant_dynamic("payload", rhai::serde::to_dynamic(input.payload).map_err(|e| e.to_string())?);
+    scope.push_constant_dynamic(
+        "values",
+        rhai::serde::to_dynamic(input.values.unwrap_or_default()).map_err(|e| e.to_string())?,
+    );
+    scope.push_constant("now", input.now);
+
+    let result = engine
+        .eval_ast_with_scope::<Dynamic>(&mut scope, &ast)
+        .map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) => {
+                format!("script exceeded its {} ms time limit", MAX_RUN_TIME.as_millis())
+            }
+            e => e.to_string(),
+        })?;
+    if result.is_unit() {
+        return Ok(None);
+    }
+
+    match rhai::serde::from_dynamic::<Value>(&result).map_err(|e| e.to_string())? {
+        Value::Object(fields) => {
+            if let Some(key) = fields.keys().find(|key| !SCRIPT_OUTPUT_KEYS.contains(&key.as_str())) {
+                return Err(format!(
+                    "script returned unknown field `{}` (expected one of {})",
+                    key,
+                    SCRIPT_OUTPUT_KEYS.join(", ")
+                ));
+            }
+            if let Some(extras) = fields.get("extra_fields")
+                && !extras.is_object()
+                && !extras.is_null()
+            {
+                return Err("script field `extra_fields` must be a map".to_string());
+            }
+            Ok(Some(fields))
+        }
+        Value::Null => Ok(None),
+        other => Err(format!("script must return a map of fields or (), got {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str, payload: Value) -> Result<Option<Map<String, Value>>, String> {
+        let input = ScriptInput {
+            payload: &payload,
+            values: None,
+            now: 1_760_700_000,
+        };
+        run_script(source, input, |s| s.parse().ok())
+    }
+
+    #[test]
+    fn picks_entries_and_decodes_fields() {
+        let payload = serde_json::json!({
+            "now": [
+                { "start": 1_760_699_000, "title": "Old" },
+                { "start": 1_760_699_900, "title": "Current" },
+                { "start": 1_760_700_300, "title": "Next" }
+            ],
+            "meta": { "artist_b64": "SGVhcnQ=" }
+        });
+        let script = r#"
+            let best = ();
+            for item in payload.now {
+                if item.start <= now && (best == () || item.start > best.start) { best = item; }
+            }
+            #{ artist: base64_decode(payload.meta.artist_b64), title: best.title,
+               extra_fields: #{ start: parse_time(`${best.start}`) } }
+        "#;
+        let fields = run(script, payload).unwrap().unwrap();
+        assert_eq!(fields["artist"], "Heart");
+        assert_eq!(fields["title"], "Current");
+        assert_eq!(fields["extra_fields"]["start"], 1_760_699_900);
+
+        assert_eq!(run("()", Value::Null).unwrap(), None);
+    }
+
+    #[test]
+    fn enforces_limits_and_output_shape() {
+        let err = run("loop { }", Value::Null).unwrap_err();
+        assert!(err.contains("Too many operations") || err.contains("time limit"), "{}", err);
+        assert!(run("#{ artst: \"x\" }", Value::Null).unwrap_err().contains("unknown field `artst`"));
+        assert!(run("42", Value::Null).unwrap_err().starts_with("script must return a map"));
+        assert!(compile_script("import \"fs\" as fs; fs::read()").is_ok());
+        assert!(run("import \"fs\" as fs; ()", Value::Null).is_err());
+        assert!(compile_script("eval(\"1\")").is_err());
+        assert!(compile_script("#{ artist: ").is_err());
+    }
+}
@@ -152,6 +152,12 @@ export default function ConnectionsPage() {
           'Cache-Control': 'no-cache',
           Pragma: 'no-cache',
         };
+      case 'hls_id3':
+        return {
+          Accept: 'application/vnd.apple.mpegurl, application/x-mpegurl;q=0.9, */*;q=0.8',
+          'Cache-Control': 'no-cache',
+          Pragma: 'no-cache',
+        };
       case 'rss':
         return {
           Accept: 'application/rss+xml, application/xml;q=0.9, */*;q=0.8',
@@ -250,7 +256,11 @@ export default function ConnectionsPage() {
               <option value="http_xml">HTTP XML</option>
               <option value="http_text">HTTP Text</option>
               <option value="ws_json">WebSocket JSON</option>
+              <option value="push">Push (Ingest)</option>
+              <option value="tcp_listen">TCP Listener</option>
+              <option value="udp_listen">UDP Listener</option>
               <option value="rss">RSS</option>
+              <option value="hls_id3">HLS Timed Metadata (ID3)</option>
             </select>
           </div>
           <div className="sm:col-span-2">
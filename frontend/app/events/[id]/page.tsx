@@ -11,6 +11,22 @@ interface RawEvent {
   reported_artist?: string;
   reported_title?: string;
   reported_album?: string;
+  reported_at?: string;
+  reported_at_error?: string;
+  reported_duration_seconds?: number;
+  expected_end_at?: string;
+  isrc?: string;
+  upc?: string;
+  artwork_url?: string;
+  label?: string;
+  composer?: string;
+  category?: string;
+  item_type?: string;
+  extra_fields?: Record<string, unknown>;
+  validity_status: string;
+  validity_reasons?: string[];
+  content_class?: string;
+  content_class_reason?: string;
   raw_payload: unknown;
   http_status?: number;
   content_type?: string;
@@ -58,9 +74,31 @@ export default function EventDetailPage({ params }: { params: Promise<{ id: stri
         <div><span className="font-semibold">Artist:</span> {event.reported_artist || '-'}</div>
         <div><span className="font-semibold">Title:</span> {event.reported_title || '-'}</div>
         <div><span className="font-semibold">Album:</span> {event.reported_album || '-'}</div>
+        <div><span className="font-semibold">Reported At:</span> {event.reported_at ? new Date(event.reported_at).toLocaleString() : '-'}</div>
+        {event.reported_at_error && <div className="text-red-600"><span className="font-semibold">Reported At Error:</span> {event.reported_at_error}</div>}
+        <div><span className="font-semibold">Duration:</span> {event.reported_duration_seconds != null ? `${event.reported_duration_seconds}s` : '-'}</div>
+        <div><span className="font-semibold">Expected End:</span> {event.expected_end_at ? new Date(event.expected_end_at).toLocaleString() : '-'}</div>
+        <div><span className="font-semibold">Validity:</span> {event.validity_status}</div>
+        {event.validity_reasons && event.validity_reasons.length > 0 && <div className="text-amber-700"><span className="font-semibold">Validity Reasons:</span> {event.validity_reasons.join('; ')}</div>}
+        <div><span className="font-semibold">Content Class:</span> {event.content_class ? `${event.content_class}${event.content_class_reason ? ` (${event.content_class_reason})` : ''}` : '-'}</div>
+        <div><span className="font-semibold">Item Type:</span> {event.item_type || '-'}</div>
+        <div><span className="font-semibold">ISRC:</span> {event.isrc || '-'}</div>
+        <div><span className="font-semibold">UPC:</span> {event.upc || '-'}</div>
+        <div><span className="font-semibold">Label:</span> {event.label || '-'}</div>
+        <div><span className="font-semibold">Composer:</span> {event.composer || '-'}</div>
+        <div><span className="font-semibold">Category:</span> {event.category || '-'}</div>
+        <div className="col-span-2"><span className="font-semibold">Artwork:</span> {event.artwork_url ? <a href={event.artwork_url} className="text-blue-600 hover:underline" target="_blank" rel="noreferrer">{event.artwork_url}</a> : '-'}</div>
         <div><span className="font-semibold">HTTP Status:</span> {event.http_status}</div>
         <div><span className="font-semibold">Content Type:</span> {event.content_type || '-'}</div>
       </div>
+      {event.extra_fields && Object.keys(event.extra_fields).length > 0 && (
+        <div>
+          <h3 className="font-semibold mb-2">Extra Fields:</h3>
+          <pre className="bg-gray-100 p-4 rounded overflow-auto text-xs">
+            {JSON.stringify(event.extra_fields, null, 2)}
+          </pre>
+        </div>
+      )}
       <div>
         <h3 className="font-semibold mb-2">Raw Payload:</h3>
         <pre className="bg-gray-800 text-green-400 p-4 rounded overflow-auto max-h-96 text-xs">
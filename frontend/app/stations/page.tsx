@@ -8,6 +8,9 @@ interface Station {
   name: string;
   callsign?: string;
   website_url?: string;
+  timezone?: string;
+  validity_rules_json?: unknown[];
+  classification_json?: Record<string, unknown>;
 }
 
 export default function StationsPage() {
@@ -16,6 +19,7 @@ export default function StationsPage() {
     name: '',
     callsign: '',
     website_url: '',
+    timezone: '',
   });
   const [editingId, setEditingId] = useState<string | null>(null);
 
@@ -44,11 +48,15 @@ export default function StationsPage() {
         name: formData.name, 
         callsign: formData.callsign || null, 
         website_url: formData.website_url || null,
+        timezone: formData.timezone || null,
+        // Not edited here; keep whatever the station already has.
+        validity_rules_json: stations.find((s) => s.id === editingId)?.validity_rules_json ?? null,
+        classification_json: stations.find((s) => s.id === editingId)?.classification_json ?? null,
       }),
     });
 
     if (res.ok) {
-      setFormData({ name: '', callsign: '', website_url: '' });
+      setFormData({ name: '', callsign: '', website_url: '', timezone: '' });
       setEditingId(null);
       fetchStations();
     } else {
@@ -62,6 +70,7 @@ export default function StationsPage() {
       name: s.name,
       callsign: s.callsign || '',
       website_url: s.website_url || '',
+      timezone: s.timezone || '',
     });
   };
 
@@ -92,13 +101,17 @@ export default function StationsPage() {
               <label className="block text-sm font-medium text-gray-700">Website URL</label>
               <input type="url" value={formData.website_url} onChange={(e) => setFormData({...formData, website_url: e.target.value})} className="mt-1 block w-full border border-gray-300 rounded-md shadow-sm p-2 bg-white text-gray-900" />
             </div>
+            <div className="sm:col-span-2">
+              <label className="block text-sm font-medium text-gray-700">Timezone</label>
+              <input type="text" placeholder="America/Chicago" value={formData.timezone} onChange={(e) => setFormData({...formData, timezone: e.target.value})} className="mt-1 block w-full border border-gray-300 rounded-md shadow-sm p-2 bg-white text-gray-900" />
+            </div>
           </div>
           <div className="flex space-x-2">
             <button type="submit" className="inline-flex justify-center py-2 px-4 border border-transparent shadow-sm text-sm font-medium rounded-md text-white bg-indigo-600 hover:bg-indigo-700">
               {editingId ? 'Update Station' : 'Create Station'}
             </button>
             {editingId && (
-              <button type="button" onClick={() => { setEditingId(null); setFormData({ name: '', callsign: '', website_url: '' }); }} className="inline-flex justify-center py-2 px-4 border border-gray-300 shadow-sm text-sm font-medium rounded-md text-gray-700 bg-white hover:bg-gray-50">
+              <button type="button" onClick={() => { setEditingId(null); setFormData({ name: '', callsign: '', website_url: '', timezone: '' }); }} className="inline-flex justify-center py-2 px-4 border border-gray-300 shadow-sm text-sm font-medium rounded-md text-gray-700 bg-white hover:bg-gray-50">
                 Cancel
               </button>
             )}
@@ -6,6 +6,21 @@ mod m20260108_135100_json_mappings;
 mod m20260108_141000_station_mappings;
 mod m20260108_142300_remove_station_mapping;
 mod m20260109_000100_adaptive_polling;
+mod m20261018_000100_push_ingest;
+mod m20261018_000200_hls_media_sequence;
+mod m20261018_000300_credentials;
+mod m20261018_000400_request_chains;
+mod m20261018_000500_response_charset;
+mod m20261018_000600_cookie_jar;
+mod m20261018_000700_proxies;
+mod m20261018_000800_mapping_versions;
+mod m20261018_000900_extended_metadata;
+mod m20261018_001000_event_duration;
+mod m20261018_001100_timezones;
+mod m20261018_001200_auto_mapping;
+mod m20261018_001300_validity_rules;
+mod m20261018_001400_content_class;
+mod m20261018_001500_rejected_event_keys;
 
 pub struct Migrator;
 
@@ -19,6 +34,21 @@ impl MigratorTrait for Migrator {
             Box::new(m20260108_141000_station_mappings::Migration),
             Box::new(m20260108_142300_remove_station_mapping::Migration),
             Box::new(m20260109_000100_adaptive_polling::Migration),
+            Box::new(m20261018_000100_push_ingest::Migration),
+            Box::new(m20261018_000200_hls_media_sequence::Migration),
+            Box::new(m20261018_000300_credentials::Migration),
+            Box::new(m20261018_000400_request_chains::Migration),
+            Box::new(m20261018_000500_response_charset::Migration),
+            Box::new(m20261018_000600_cookie_jar::Migration),
+            Box::new(m20261018_000700_proxies::Migration),
+            Box::new(m20261018_000800_mapping_versions::Migration),
+            Box::new(m20261018_000900_extended_metadata::Migration),
+            Box::new(m20261018_001000_event_duration::Migration),
+            Box::new(m20261018_001100_timezones::Migration),
+            Box::new(m20261018_001200_auto_mapping::Migration),
+            Box::new(m20261018_001300_validity_rules::Migration),
+            Box::new(m20261018_001400_content_class::Migration),
+            Box::new(m20261018_001500_rejected_event_keys::Migration),
         ]
     }
 }
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .add_column(ColumnDef::new(NowPlayingConnections::IngestToken).string())
+                    .add_column(ColumnDef::new(NowPlayingConnections::IngestHmacSecret).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .drop_column(NowPlayingConnections::IngestToken)
+                    .drop_column(NowPlayingConnections::IngestHmacSecret)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum NowPlayingConnections {
+    Table,
+    IngestToken,
+    IngestHmacSecret,
+}
@@ -0,0 +1,105 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Credentials::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Credentials::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Credentials::Name).string().not_null())
+                    .col(ColumnDef::new(Credentials::Kind).string().not_null())
+                    .col(ColumnDef::new(Credentials::ConfigJson).json_binary().not_null())
+                    .col(ColumnDef::new(Credentials::AccessToken).string())
+                    .col(ColumnDef::new(Credentials::AccessTokenExpiresAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Credentials::LastError).string())
+                    .col(
+                        ColumnDef::new(Credentials::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Credentials::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .add_column(ColumnDef::new(NowPlayingConnections::CredentialId).uuid())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-connection-credential_id")
+                    .from(NowPlayingConnections::Table, NowPlayingConnections::CredentialId)
+                    .to(Credentials::Table, Credentials::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-connection-credential_id")
+                    .table(NowPlayingConnections::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .drop_column(NowPlayingConnections::CredentialId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Credentials::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Credentials {
+    Table,
+    Id,
+    Name,
+    Kind,
+    ConfigJson,
+    AccessToken,
+    AccessTokenExpiresAt,
+    LastError,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum NowPlayingConnections {
+    Table,
+    CredentialId,
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .add_column(
+                        ColumnDef::new(NowPlayingConnections::CookieJarEnabled)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(ColumnDef::new(NowPlayingConnections::CookieWarmupUrl).string())
+                    .add_column(ColumnDef::new(NowPlayingConnections::CookiesJson).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .drop_column(NowPlayingConnections::CookieJarEnabled)
+                    .drop_column(NowPlayingConnections::CookieWarmupUrl)
+                    .drop_column(NowPlayingConnections::CookiesJson)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum NowPlayingConnections {
+    Table,
+    CookieJarEnabled,
+    CookieWarmupUrl,
+    CookiesJson,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProxyPools::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ProxyPools::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(ProxyPools::Name).string().not_null())
+                    .col(ColumnDef::new(ProxyPools::ProxyUrlsJson).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(ProxyPools::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ProxyPools::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .add_column(ColumnDef::new(NowPlayingConnections::ProxyUrl).string())
+                    .add_column(ColumnDef::new(NowPlayingConnections::ProxyPoolId).uuid())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-connection-proxy_pool_id")
+                    .from(NowPlayingConnections::Table, NowPlayingConnections::ProxyPoolId)
+                    .to(ProxyPools::Table, ProxyPools::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-connection-proxy_pool_id")
+                    .table(NowPlayingConnections::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NowPlayingConnections::Table)
+                    .drop_column(NowPlayingConnections::ProxyUrl)
+                    .drop_column(NowPlayingConnections::ProxyPoolId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ProxyPools::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ProxyPools {
+    Table,
+    Id,
+    Name,
+    ProxyUrlsJson,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum NowPlayingConnections {
+    Table,
+    ProxyUrl,
+    ProxyPoolId,
+}
@@ -0,0 +1,215 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PayloadMappingVersions::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PayloadMappingVersions::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(PayloadMappingVersions::MappingId).uuid().not_null())
+                    .col(ColumnDef::new(PayloadMappingVersions::Version).integer().not_null())
+                    .col(ColumnDef::new(PayloadMappingVersions::MappingJson).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(PayloadMappingVersions::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-mapping_version-mapping_id")
+                            .from(PayloadMappingVersions::Table, PayloadMappingVersions::MappingId)
+                            .to(PayloadMappings::Table, PayloadMappings::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-mapping_version-mapping_id-version")
+                    .table(PayloadMappingVersions::Table)
+                    .col(PayloadMappingVersions::MappingId)
+                    .col(PayloadMappingVersions::Version)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PayloadMappings::Table)
+                    .add_column(ColumnDef::new(PayloadMappings::CurrentVersionId).uuid())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::MappingVersionId).uuid())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-event-mapping_version_id")
+                    .from(RawNowPlayingEvents::Table, RawNowPlayingEvents::MappingVersionId)
+                    .to(PayloadMappingVersions::Table, PayloadMappingVersions::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing mappings become version 1.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "INSERT INTO payload_mapping_versions (id, mapping_id, version, mapping_json, created_at) \
+             SELECT gen_random_uuid(), id, 1, mapping_json, updated_at FROM payload_mappings",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE payload_mappings SET current_version_id = v.id \
+             FROM payload_mapping_versions v WHERE v.mapping_id = payload_mappings.id",
+        )
+        .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReextractionJobs::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ReextractionJobs::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(ReextractionJobs::StationId).uuid().not_null())
+                    .col(ColumnDef::new(ReextractionJobs::ConnectionId).uuid())
+                    .col(ColumnDef::new(ReextractionJobs::ObservedFrom).timestamp_with_time_zone())
+                    .col(ColumnDef::new(ReextractionJobs::ObservedTo).timestamp_with_time_zone())
+                    .col(ColumnDef::new(ReextractionJobs::DryRun).boolean().not_null().default(true))
+                    .col(ColumnDef::new(ReextractionJobs::Status).string().not_null())
+                    .col(ColumnDef::new(ReextractionJobs::TotalEvents).integer().not_null().default(0))
+                    .col(ColumnDef::new(ReextractionJobs::ProcessedEvents).integer().not_null().default(0))
+                    .col(ColumnDef::new(ReextractionJobs::ChangedEvents).integer().not_null().default(0))
+                    .col(ColumnDef::new(ReextractionJobs::ReportJson).json_binary())
+                    .col(ColumnDef::new(ReextractionJobs::Error).text())
+                    .col(
+                        ColumnDef::new(ReextractionJobs::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ReextractionJobs::StartedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(ReextractionJobs::FinishedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-reextraction_job-station_id")
+                            .from(ReextractionJobs::Table, ReextractionJobs::StationId)
+                            .to(Stations::Table, Stations::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReextractionJobs::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-event-mapping_version_id")
+                    .table(RawNowPlayingEvents::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .drop_column(RawNowPlayingEvents::MappingVersionId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PayloadMappings::Table)
+                    .drop_column(PayloadMappings::CurrentVersionId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PayloadMappingVersions::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum PayloadMappingVersions {
+    Table,
+    Id,
+    MappingId,
+    Version,
+    MappingJson,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum PayloadMappings {
+    Table,
+    Id,
+    CurrentVersionId,
+}
+
+#[derive(Iden)]
+enum RawNowPlayingEvents {
+    Table,
+    MappingVersionId,
+}
+
+#[derive(Iden)]
+enum Stations {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum ReextractionJobs {
+    Table,
+    Id,
+    StationId,
+    ConnectionId,
+    ObservedFrom,
+    ObservedTo,
+    DryRun,
+    Status,
+    TotalEvents,
+    ProcessedEvents,
+    ChangedEvents,
+    ReportJson,
+    Error,
+    CreatedAt,
+    StartedAt,
+    FinishedAt,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::Isrc).string())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::Upc).string())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ArtworkUrl).text())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::Label).string())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::Composer).string())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::Category).string())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ItemType).string())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ExtraFields).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-event-isrc")
+                    .table(RawNowPlayingEvents::Table)
+                    .col(RawNowPlayingEvents::Isrc)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-event-isrc")
+                    .table(RawNowPlayingEvents::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .drop_column(RawNowPlayingEvents::Isrc)
+                    .drop_column(RawNowPlayingEvents::Upc)
+                    .drop_column(RawNowPlayingEvents::ArtworkUrl)
+                    .drop_column(RawNowPlayingEvents::Label)
+                    .drop_column(RawNowPlayingEvents::Composer)
+                    .drop_column(RawNowPlayingEvents::Category)
+                    .drop_column(RawNowPlayingEvents::ItemType)
+                    .drop_column(RawNowPlayingEvents::ExtraFields)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum RawNowPlayingEvents {
+    Table,
+    Isrc,
+    Upc,
+    ArtworkUrl,
+    Label,
+    Composer,
+    Category,
+    ItemType,
+    ExtraFields,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Older events are filled in by running a re-extraction job.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ReportedDurationSeconds).big_integer())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ExpectedEndAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .drop_column(RawNowPlayingEvents::ReportedDurationSeconds)
+                    .drop_column(RawNowPlayingEvents::ExpectedEndAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum RawNowPlayingEvents {
+    Table,
+    ReportedDurationSeconds,
+    ExpectedEndAt,
+}
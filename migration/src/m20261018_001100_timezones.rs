@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stations::Table)
+                    .add_column(ColumnDef::new(Stations::Timezone).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ReportedAtError).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .drop_column(RawNowPlayingEvents::ReportedAtError)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stations::Table)
+                    .drop_column(Stations::Timezone)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Stations {
+    Table,
+    Timezone,
+}
+
+#[derive(Iden)]
+enum RawNowPlayingEvents {
+    Table,
+    ReportedAtError,
+}
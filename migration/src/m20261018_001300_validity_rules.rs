@@ -0,0 +1,142 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stations::Table)
+                    .add_column(ColumnDef::new(Stations::ValidityRulesJson).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .add_column(
+                        ColumnDef::new(RawNowPlayingEvents::ValidityStatus)
+                            .string()
+                            .not_null()
+                            .default("VALID"),
+                    )
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ValidityReasons).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RejectedEvents::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(RejectedEvents::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(RejectedEvents::StationId).uuid().not_null())
+                    .col(ColumnDef::new(RejectedEvents::ConnectionId).uuid().not_null())
+                    .col(ColumnDef::new(RejectedEvents::ObservedAt).timestamp_with_time_zone().not_null())
+                    .col(ColumnDef::new(RejectedEvents::RawPayload).json_binary().not_null())
+                    .col(ColumnDef::new(RejectedEvents::ExtractedJson).json_binary().not_null())
+                    .col(ColumnDef::new(RejectedEvents::Reasons).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(RejectedEvents::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-rejected_event-station_id")
+                            .from(RejectedEvents::Table, RejectedEvents::StationId)
+                            .to(Stations::Table, Stations::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-rejected_event-connection_id")
+                            .from(RejectedEvents::Table, RejectedEvents::ConnectionId)
+                            .to(NowPlayingConnections::Table, NowPlayingConnections::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-rejected_event-station_id-observed_at")
+                    .table(RejectedEvents::Table)
+                    .col(RejectedEvents::StationId)
+                    .col(RejectedEvents::ObservedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RejectedEvents::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .drop_column(RawNowPlayingEvents::ValidityStatus)
+                    .drop_column(RawNowPlayingEvents::ValidityReasons)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stations::Table)
+                    .drop_column(Stations::ValidityRulesJson)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Stations {
+    Table,
+    Id,
+    ValidityRulesJson,
+}
+
+#[derive(Iden)]
+enum NowPlayingConnections {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum RawNowPlayingEvents {
+    Table,
+    ValidityStatus,
+    ValidityReasons,
+}
+
+#[derive(Iden)]
+enum RejectedEvents {
+    Table,
+    Id,
+    StationId,
+    ConnectionId,
+    ObservedAt,
+    RawPayload,
+    ExtractedJson,
+    Reasons,
+    CreatedAt,
+}
@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stations::Table)
+                    .add_column(ColumnDef::new(Stations::ClassificationJson).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ContentClass).string())
+                    .add_column(ColumnDef::new(RawNowPlayingEvents::ContentClassReason).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-event-station_id-content_class")
+                    .table(RawNowPlayingEvents::Table)
+                    .col(RawNowPlayingEvents::StationId)
+                    .col(RawNowPlayingEvents::ContentClass)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-event-station_id-content_class")
+                    .table(RawNowPlayingEvents::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RawNowPlayingEvents::Table)
+                    .drop_column(RawNowPlayingEvents::ContentClass)
+                    .drop_column(RawNowPlayingEvents::ContentClassReason)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stations::Table)
+                    .drop_column(Stations::ClassificationJson)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Stations {
+    Table,
+    ClassificationJson,
+}
+
+#[derive(Iden)]
+enum RawNowPlayingEvents {
+    Table,
+    StationId,
+    ContentClass,
+    ContentClassReason,
+}
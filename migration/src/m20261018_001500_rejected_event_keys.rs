@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RejectedEvents::Table)
+                    .add_column(ColumnDef::new(RejectedEvents::PayloadHash).string())
+                    .add_column(ColumnDef::new(RejectedEvents::ReportedAt).timestamp_with_time_zone())
+                    .add_column(ColumnDef::new(RejectedEvents::ReportedArtist).text())
+                    .add_column(ColumnDef::new(RejectedEvents::ReportedTitle).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-rejected_event-station_id-payload_hash")
+                    .table(RejectedEvents::Table)
+                    .col(RejectedEvents::StationId)
+                    .col(RejectedEvents::PayloadHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-rejected_event-station_id-reported_at")
+                    .table(RejectedEvents::Table)
+                    .col(RejectedEvents::StationId)
+                    .col(RejectedEvents::ReportedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-rejected_event-station_id-reported_at")
+                    .table(RejectedEvents::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-rejected_event-station_id-payload_hash")
+                    .table(RejectedEvents::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RejectedEvents::Table)
+                    .drop_column(RejectedEvents::PayloadHash)
+                    .drop_column(RejectedEvents::ReportedAt)
+                    .drop_column(RejectedEvents::ReportedArtist)
+                    .drop_column(RejectedEvents::ReportedTitle)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum RejectedEvents {
+    Table,
+    StationId,
+    PayloadHash,
+    ReportedAt,
+    ReportedArtist,
+    ReportedTitle,
+}